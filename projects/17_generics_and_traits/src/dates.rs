@@ -0,0 +1,365 @@
+// Times as seconds since the Unix epoch (1970-01-01 00:00 UTC), to and from the text formats
+// the syndication feeds use:
+//
+//   RFC 822 (RSS):                  Wed, 01 May 2024 12:00:00 GMT
+//   RFC 3339 (Atom and JSON Feed):  2024-05-01T12:00:00Z
+//
+// Both allow a time zone offset instead of GMT/Z, e.g. +01:00, which is taken off to give UTC.
+// Times before 1970 can't be held in a u64 and are an error.
+//
+// The calendar arithmetic is Howard Hinnant's.  It counts in 400 year eras, each of which has
+// exactly 146097 days, with years starting on March 1st so that the leap day comes last.
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"]; // From 1970-01-01.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u64,
+    pub month: u64, // 1 to 12.
+    pub day: u64,
+    pub hour: u64,
+    pub minute: u64,
+    pub second: u64,
+}
+
+impl DateTime {
+    pub fn from_unix(secs: u64) -> DateTime {
+        let (year, month, day) = civil_from_days(secs / 86_400);
+        let secs_of_day = secs % 86_400;
+        DateTime {
+            year,
+            month,
+            day,
+            hour: secs_of_day / 3600,
+            minute: secs_of_day % 3600 / 60,
+            second: secs_of_day % 60,
+        }
+    }
+
+    // Checks the fields are in range, and the date is on or after 1970-01-01.
+    pub fn to_unix(&self) -> Result<u64, String> {
+        if !(1..=12).contains(&self.month) {
+            return Err(format!("month {} out of range", self.month));
+        }
+        if self.day < 1 || self.day > days_in_month(self.year, self.month) {
+            return Err(format!("day {} out of range", self.day));
+        }
+        if self.hour > 23 || self.minute > 59 || self.second > 60 {
+            return Err(String::from("time out of range"));
+        }
+        if self.year < 1970 {
+            return Err(String::from("dates before 1970 are not supported"));
+        }
+        let days = days_from_civil(self.year, self.month, self.day);
+        // A leap second is taken as the first second of the next minute.
+        Ok(days * 86_400 + self.hour * 3600 + self.minute * 60 + self.second)
+    }
+}
+
+fn is_leap_year(year: u64) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// The date 'days' days after 1970-01-01.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468; // From 0000-03-01 instead.
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// The opposite of civil_from_days, for dates on or after 1970-01-01.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// 'YYYY-MM-DD HH:MM' in UTC, for people.
+pub fn format_short(secs: u64) -> String {
+    let t = DateTime::from_unix(secs);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        t.year, t.month, t.day, t.hour, t.minute
+    )
+}
+
+pub fn format_rfc3339(secs: u64) -> String {
+    let t = DateTime::from_unix(secs);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        t.year, t.month, t.day, t.hour, t.minute, t.second
+    )
+}
+
+pub fn format_rfc822(secs: u64) -> String {
+    let t = DateTime::from_unix(secs);
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(secs / 86_400 % 7) as usize],
+        t.day,
+        MONTHS[t.month as usize - 1],
+        t.year,
+        t.hour,
+        t.minute,
+        t.second
+    )
+}
+
+// YYYY-MM-DDTHH:MM:SS, then optional fractions of a second (dropped), then Z or +HH:MM/-HH:MM.
+pub fn parse_rfc3339(text: &str) -> Result<u64, String> {
+    let invalid = || format!("'{}' is not an RFC 3339 date", text);
+    let text = text.trim();
+    if text.len() < 20 || !text.is_ascii() {
+        return Err(invalid());
+    }
+    let (date_time, rest) = text.split_at(19);
+    let b = date_time.as_bytes();
+    if b[4] != b'-' || b[7] != b'-' || !b"Tt ".contains(&b[10]) || b[13] != b':' || b[16] != b':' {
+        return Err(invalid());
+    }
+    let number = |range: std::ops::Range<usize>| digits(&date_time[range]).ok_or_else(invalid);
+    let t = DateTime {
+        year: number(0..4)?,
+        month: number(5..7)?,
+        day: number(8..10)?,
+        hour: number(11..13)?,
+        minute: number(14..16)?,
+        second: number(17..19)?,
+    };
+
+    let zone = match rest.strip_prefix('.') {
+        Some(fraction) => {
+            let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                return Err(invalid());
+            }
+            &fraction[digits..]
+        }
+        None => rest,
+    };
+    let offset = match zone {
+        "Z" | "z" => 0,
+        _ => parse_offset(zone, true).ok_or_else(invalid)?,
+    };
+    t.to_unix()
+        .and_then(|local| apply_offset(local, offset))
+        .map_err(|e| format!("'{}': {}", text, e))
+}
+
+// [Wed, ]01 May 2024 12:00[:00] GMT, or with a numeric zone, +0100.  Two digit years are taken
+// as 1950 to 2049.
+pub fn parse_rfc822(text: &str) -> Result<u64, String> {
+    let invalid = || format!("'{}' is not an RFC 822 date", text);
+    let text = text.trim();
+    let mut rest = text;
+    if let Some(comma) = text.find(',') {
+        if !WEEKDAYS.iter().any(|d| text[..comma].trim() == *d) {
+            return Err(invalid());
+        }
+        rest = &text[comma + 1..];
+    }
+
+    let parts: Vec<&str> = rest.split_whitespace().collect();
+    if parts.len() != 5 {
+        return Err(invalid());
+    }
+    let day = digits(parts[0]).filter(|_| parts[0].len() <= 2);
+    let month = MONTHS.iter().position(|m| m.eq_ignore_ascii_case(parts[1]));
+    let year = match (parts[2].len(), digits(parts[2])) {
+        (4, Some(year)) => year,
+        (2, Some(year)) if year < 50 => 2000 + year,
+        (2, Some(year)) => 1900 + year,
+        _ => return Err(invalid()),
+    };
+    let time: Vec<Option<u64>> = parts[3]
+        .split(':')
+        .map(|n| digits(n).filter(|_| n.len() == 2))
+        .collect();
+    let (hour, minute, second) = match time.as_slice() {
+        [Some(h), Some(m)] => (*h, *m, 0),
+        [Some(h), Some(m), Some(s)] => (*h, *m, *s),
+        _ => return Err(invalid()),
+    };
+    let offset = match parts[4] {
+        "GMT" | "UT" | "UTC" | "Z" => 0,
+        "EST" => -5 * 3600,
+        "EDT" => -4 * 3600,
+        "CST" => -6 * 3600,
+        "CDT" => -5 * 3600,
+        "MST" => -7 * 3600,
+        "MDT" => -6 * 3600,
+        "PST" => -8 * 3600,
+        "PDT" => -7 * 3600,
+        zone => parse_offset(zone, false).ok_or_else(invalid)?,
+    };
+
+    let t = DateTime {
+        year,
+        month: month.ok_or_else(invalid)? as u64 + 1,
+        day: day.ok_or_else(invalid)?,
+        hour,
+        minute,
+        second,
+    };
+    t.to_unix()
+        .and_then(|local| apply_offset(local, offset))
+        .map_err(|e| format!("'{}': {}", text, e))
+}
+
+// All ASCII digits, as a number.
+fn digits(text: &str) -> Option<u64> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+// +HH:MM (with_colon) or +HHMM, in seconds east of UTC.
+fn parse_offset(zone: &str, with_colon: bool) -> Option<i64> {
+    // Checked first so the byte slicing below can't split a character.
+    if !zone.is_ascii() {
+        return None;
+    }
+    let sign = match zone.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = if with_colon {
+        let (h, m) = zone[1..].split_once(':')?;
+        (h, m)
+    } else if zone.len() == 5 {
+        zone[1..].split_at(2)
+    } else {
+        return None;
+    };
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let (hours, minutes) = (digits(hours)?, digits(minutes)?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60) as i64)
+}
+
+// A local time 'offset' seconds east of UTC, to UTC.
+fn apply_offset(local: u64, offset: i64) -> Result<u64, String> {
+    let utc = local as i64 - offset;
+    if utc < 0 {
+        Err(String::from("dates before 1970 are not supported"))
+    } else {
+        Ok(utc as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-05-01 12:00 UTC, a Wednesday.
+    const NOON: u64 = 1_714_564_800;
+
+    #[test]
+    fn formats() {
+        assert_eq!(format_short(NOON + 59), "2024-05-01 12:00");
+        assert_eq!(format_rfc3339(NOON + 59), "2024-05-01T12:00:59Z");
+        assert_eq!(format_rfc822(NOON), "Wed, 01 May 2024 12:00:00 GMT");
+        assert_eq!(format_rfc822(0), "Thu, 01 Jan 1970 00:00:00 GMT");
+        // Leap days, and the turn of the century rules.
+        assert_eq!(format_short(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_short(4_107_542_399), "2100-02-28 23:59");
+        assert_eq!(format_short(4_107_542_400), "2100-03-01 00:00");
+    }
+
+    #[test]
+    fn days_round_trip() {
+        for days in (0..200_000).step_by(7) {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn parses_rfc3339() {
+        assert_eq!(parse_rfc3339("2024-05-01T12:00:00Z"), Ok(NOON));
+        assert_eq!(parse_rfc3339("2024-05-01t12:00:00.123456z"), Ok(NOON));
+        assert_eq!(parse_rfc3339("2024-05-01 13:30:00+01:30"), Ok(NOON));
+        assert_eq!(parse_rfc3339("2024-05-01T07:00:00-05:00"), Ok(NOON));
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Ok(0));
+
+        for bad in &[
+            "2024-05-01",
+            "2024-05-01T12:00:00",
+            "2024-05-01T12:00:00+0100",
+            "2024-13-01T12:00:00Z",
+            "2023-02-29T12:00:00Z",
+            "2024-05-01T24:00:00Z",
+            "2024-05-01T12:00:00.Z",
+            "2024/05/01T12:00:00Z",
+            "1969-12-31T23:59:59Z",
+            "1970-01-01T00:30:00+01:00",
+        ] {
+            assert!(parse_rfc3339(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn parses_rfc822() {
+        assert_eq!(parse_rfc822("Wed, 01 May 2024 12:00:00 GMT"), Ok(NOON));
+        assert_eq!(parse_rfc822("1 May 2024 12:00 UT"), Ok(NOON));
+        assert_eq!(parse_rfc822("Wed, 01 may 24 13:00:00 +0100"), Ok(NOON));
+        assert_eq!(parse_rfc822("Wed, 01 May 2024 08:00:00 EDT"), Ok(NOON));
+        assert_eq!(parse_rfc822("Thu, 01 Jan 70 00:00:00 GMT"), Ok(0));
+
+        for bad in &[
+            "01 May 2024",
+            "Xyz, 01 May 2024 12:00:00 GMT",
+            "01 Mai 2024 12:00:00 GMT",
+            "01 May 2024 12:00:00 BST",
+            "01 May 2024 12:00:00 +01:00",
+            "001 May 2024 12:00:00 GMT",
+            "31 Apr 2024 12:00:00 GMT",
+            "01 May 2024 12:0:00 GMT",
+            "01 May 2024 12:00:00 +1é2",
+        ] {
+            assert!(parse_rfc822(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        for &secs in &[0, 59, NOON, 951_782_400, 4_107_542_399] {
+            assert_eq!(parse_rfc3339(&format_rfc3339(secs)), Ok(secs));
+            assert_eq!(parse_rfc822(&format_rfc822(secs)), Ok(secs));
+        }
+    }
+}
@@ -0,0 +1,295 @@
+// A news feed of anything that implements Summary.
+//
+// The items are trait objects, Box<dyn Summary>, so one feed can hold NewsArticles, Tweets and
+// any other type that implements the trait.  A Vec<T> with a generic T couldn't, since every
+// item of a Vec<T> has the same concrete type.  Everything the feed does goes through the
+// trait's methods:
+//
+//   let feed = Feed::new(items).without_replies().without_retweets().deduplicated();
+//   println!("{}", feed.render(FeedFormat::Markdown));
+//
+// Items are kept newest first.  Items without a published time go after all the others, and
+// items published at the same time stay in the order they were given.
+use crate::dates;
+use crate::summary::Summary;
+use serde_json::json;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Text,
+    Markdown,
+    Json,
+}
+
+pub struct Feed {
+    items: Vec<Box<dyn Summary>>,
+}
+
+impl Feed {
+    pub fn new(mut items: Vec<Box<dyn Summary>>) -> Feed {
+        // Newest first, None sorts before Some so it is reversed into last place.  Stable, so
+        // ties keep their order.
+        items.sort_by_key(|item| std::cmp::Reverse(item.published()));
+        Feed { items }
+    }
+
+    // Adds an item in its place by published time, after any items with the same time.
+    pub fn push(&mut self, item: Box<dyn Summary>) {
+        let published = item.published();
+        let at = self
+            .items
+            .partition_point(|existing| existing.published() >= published);
+        self.items.insert(at, item);
+    }
+
+    pub fn items(&self) -> &[Box<dyn Summary>] {
+        &self.items
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn without_replies(self) -> Feed {
+        self.retain(|item| !item.is_reply())
+    }
+
+    pub fn without_retweets(self) -> Feed {
+        self.retain(|item| !item.is_retweet())
+    }
+
+    // Keeps only the newest of the items with the same summary.
+    pub fn deduplicated(self) -> Feed {
+        let mut seen = HashSet::new();
+        self.retain(|item| seen.insert(item.summarize()))
+    }
+
+    fn retain<F: FnMut(&dyn Summary) -> bool>(mut self, mut keep: F) -> Feed {
+        self.items.retain(|item| keep(item.as_ref()));
+        self
+    }
+
+    pub fn render(&self, format: FeedFormat) -> String {
+        match format {
+            FeedFormat::Text => self.render_text(),
+            FeedFormat::Markdown => self.render_markdown(),
+            FeedFormat::Json => self.render_json(),
+        }
+    }
+
+    // One item per line: '2024-05-01 12:00  ebooks: blah blah blah'.
+    fn render_text(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| format!("{}  {}\n", format_time(item.published()), item.summarize()))
+            .collect()
+    }
+
+    // A bulleted list, the time in italics: '- *2024-05-01 12:00* ebooks: blah blah blah'.
+    fn render_markdown(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| {
+                format!(
+                    "- *{}* {}\n",
+                    format_time(item.published()),
+                    escape_markdown(&item.summarize())
+                )
+            })
+            .collect()
+    }
+
+    // An array of {"published": 1714564800, "summary": "..."}, published is null if unknown.
+    fn render_json(&self) -> String {
+        let items: Vec<serde_json::Value> = self
+            .items
+            .iter()
+            .map(|item| json!({ "published": item.published(), "summary": item.summarize() }))
+            .collect();
+        serde_json::to_string_pretty(&items).expect("a Value always serializes")
+    }
+}
+
+// 'YYYY-MM-DD HH:MM' in UTC, or 'undated'.
+pub fn format_time(published: Option<u64>) -> String {
+    match published {
+        Some(secs) => dates::format_short(secs),
+        None => String::from("undated"),
+    }
+}
+
+// A backslash before each character that means something in Markdown.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_{}[]()<>#+-.!|".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::{NewsArticle, Tweet};
+
+    // 2024-05-01 12:00 UTC.
+    const NOON: u64 = 1_714_564_800;
+
+    fn tweet(username: &str, content: &str, published: u64) -> Tweet {
+        Tweet {
+            username: String::from(username),
+            content: String::from(content),
+            reply: false,
+            retweet: false,
+            published,
+        }
+    }
+
+    fn article(headline: &str, published: u64) -> NewsArticle {
+        NewsArticle {
+            headline: String::from(headline),
+            location: String::from("Leeds"),
+            author: String::from("Ann"),
+            published,
+        }
+    }
+
+    // Summary only needs summarize, so this has no published time.
+    struct Note(&'static str);
+
+    impl Summary for Note {
+        fn summarize(&self) -> String {
+            String::from(self.0)
+        }
+    }
+
+    fn summaries(feed: &Feed) -> Vec<String> {
+        feed.items().iter().map(|item| item.summarize()).collect()
+    }
+
+    fn mixed_feed() -> Feed {
+        let reply = Tweet {
+            reply: true,
+            ..tweet("bob", "@ann agreed", NOON + 120)
+        };
+        let retweet = Tweet {
+            retweet: true,
+            ..tweet("cat", "RT rain", NOON + 60)
+        };
+        Feed::new(vec![
+            Box::new(tweet("ann", "rain later", NOON)),
+            Box::new(Note("remember milk")),
+            Box::new(article("Rain expected", NOON + 180)),
+            Box::new(reply),
+            Box::new(retweet),
+            Box::new(tweet("ann", "rain later", NOON - 60)),
+        ])
+    }
+
+    #[test]
+    fn sorted_newest_first() {
+        let feed = mixed_feed();
+        assert_eq!(
+            summaries(&feed),
+            vec![
+                "Rain expected, by Ann (Leeds)",
+                "bob: @ann agreed",
+                "cat: RT rain",
+                "ann: rain later",
+                "ann: rain later",
+                "remember milk",
+            ]
+        );
+        assert_eq!(feed.items()[3].published(), Some(NOON));
+    }
+
+    #[test]
+    fn push_keeps_the_order() {
+        let mut feed = Feed::new(Vec::new());
+        assert!(feed.is_empty());
+        feed.push(Box::new(Note("undated")));
+        feed.push(Box::new(tweet("a", "old", NOON)));
+        feed.push(Box::new(tweet("b", "new", NOON + 1)));
+        feed.push(Box::new(tweet("c", "also old", NOON)));
+        assert_eq!(
+            summaries(&feed),
+            vec!["b: new", "a: old", "c: also old", "undated"]
+        );
+    }
+
+    #[test]
+    fn filters_and_dedup() {
+        let feed = mixed_feed().without_replies();
+        assert_eq!(feed.len(), 5);
+        assert!(feed.items().iter().all(|item| !item.is_reply()));
+
+        let feed = feed.without_retweets();
+        assert_eq!(feed.len(), 4);
+
+        // The newer of the two identical tweets is kept.
+        let feed = feed.deduplicated();
+        assert_eq!(
+            summaries(&feed),
+            vec![
+                "Rain expected, by Ann (Leeds)",
+                "ann: rain later",
+                "remember milk"
+            ]
+        );
+        assert_eq!(feed.items()[1].published(), Some(NOON));
+    }
+
+    #[test]
+    fn renders_text() {
+        let feed = mixed_feed()
+            .without_replies()
+            .without_retweets()
+            .deduplicated();
+        assert_eq!(
+            feed.render(FeedFormat::Text),
+            "2024-05-01 12:03  Rain expected, by Ann (Leeds)\n\
+             2024-05-01 12:00  ann: rain later\n\
+             undated  remember milk\n"
+        );
+    }
+
+    #[test]
+    fn renders_markdown() {
+        let feed = Feed::new(vec![Box::new(tweet("ann_b", "*so* [rainy]", NOON))]);
+        assert_eq!(
+            feed.render(FeedFormat::Markdown),
+            "- *2024-05-01 12:00* ann\\_b: \\*so\\* \\[rainy\\]\n"
+        );
+    }
+
+    #[test]
+    fn renders_json() {
+        let feed = Feed::new(vec![
+            Box::new(Note("say \"hi\"")),
+            Box::new(tweet("ann", "rain", NOON)),
+        ]);
+        let json: serde_json::Value = serde_json::from_str(&feed.render(FeedFormat::Json)).unwrap();
+        assert_eq!(
+            json,
+            json!([
+                { "published": NOON, "summary": "ann: rain" },
+                { "published": null, "summary": "say \"hi\"" },
+            ])
+        );
+        assert_eq!(Feed::new(Vec::new()).render(FeedFormat::Json), "[]");
+    }
+
+    #[test]
+    fn formats_times() {
+        assert_eq!(format_time(Some(NOON + 59)), "2024-05-01 12:00");
+        assert_eq!(format_time(None), "undated");
+    }
+}
@@ -0,0 +1,9 @@
+// The longer examples from main.rs, in a library so they can have tests of their own.
+pub mod dates;
+pub mod feed;
+pub mod pair;
+pub mod point;
+pub mod search;
+pub mod selection;
+pub mod summary;
+pub mod syndication;
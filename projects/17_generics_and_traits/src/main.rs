@@ -1,11 +1,14 @@
 fn main() {
     test_generic_method();
     test_mixup();
+    test_vector_point();
     test_tweet();
+    test_feed();
     test_show_user();
     test_notify();
     test_notify2();
     test_largest();
+    test_pair();
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -82,6 +85,17 @@ fn test_mixup() {
     println!("p3.x = {}, p3.y = {}", p3.x, p3.y);
 }
 
+// A Point that is a real vector type, with N dimensions and the arithmetic operators, is in
+// src/point.rs.
+use generic_traits_and_lifetimes::point::Point as Vector;
+
+fn test_vector_point() {
+    let a: Vector<f64, 3> = Vector::new([1.0, 2.0, 2.0]);
+    let b = Vector::new([0.5, 0.0, -1.0]);
+    println!("a + b = {}, a - b = {}, -a * 2 = {}", a + b, a - b, -a * 2.0);
+    println!("a . b = {}, |a| = {}", a.dot(&b), a.norm());
+}
+
 // Generic functions...
 //
 // This wont work... we need to restrict T to types that can be compared.
@@ -118,46 +132,58 @@ fn test_largest() {
 // call the same method on those types.
 
 // Define a trait which enforces method signatures.
-pub trait Summary {
-    fn summarize(&self) -> String;
-}
-
-pub struct NewsArticle {
-    pub headline: String,
-    pub location: String,
-    pub author: String
-}
-
-impl Summary for NewsArticle {
-    fn summarize(&self) -> String {
-        format!("{}, by {} ({})", self.headline, self.author, self.location)
-    }
-}
-
-pub struct Tweet {
-    pub username : String,
-    pub content: String,
-    pub reply: bool,
-    pub retweet: bool
-}
-
-impl Summary for Tweet {
-    fn summarize(&self) -> String {
-        format!("{}: {}", self.username, self.content)
-    }
-}
+//
+// pub trait Summary {
+//     fn summarize(&self) -> String;
+// }
+//
+// Summary, NewsArticle and Tweet (which implement it) now live in src/summary.rs, so the feed
+// in src/feed.rs can use them too.  Both types also have a 'published' time, for the feed.
+use generic_traits_and_lifetimes::summary::{NewsArticle, Summary, Tweet};
 
 fn test_tweet() {
     let tweet = Tweet {
         username: String::from("ebooks"),
         content: String::from("blah blah blah"),
         reply: false,
-        retweet: false
+        retweet: false,
+        published: 0
     };
 
     println!("tweet: {}", tweet.summarize());
 }
 
+// Trait objects (Box<dyn Summary>) let one Vec hold both types, see src/feed.rs.
+use generic_traits_and_lifetimes::feed::{Feed, FeedFormat};
+
+fn test_feed() {
+    let items: Vec<Box<dyn Summary>> = vec![
+        Box::new(Tweet {
+            username: String::from("ebooks"),
+            content: String::from("blah blah blah"),
+            reply: false,
+            retweet: false,
+            published: 1_714_564_800
+        }),
+        Box::new(Tweet {
+            username: String::from("fans"),
+            content: String::from("@ebooks blah"),
+            reply: true,
+            retweet: false,
+            published: 1_714_564_900
+        }),
+        Box::new(NewsArticle {
+            headline: String::from("headline"),
+            location: String::from("location"),
+            author: String::from("author"),
+            published: 1_714_565_000
+        }),
+    ];
+
+    let feed = Feed::new(items).without_replies();
+    print!("feed:\n{}", feed.render(FeedFormat::Text));
+}
+
 // Note that we can only implement a trait on a type if either the trait or type
 // is local to our crate.
 // E.g. We can implement a trait from std on our own types but we can't 
@@ -178,7 +204,8 @@ fn test_show_user() {
     let article = NewsArticle {
         headline: String::from("headline"),
         location: String::from("location"),
-        author: String::from("author")
+        author: String::from("author"),
+        published: 0
     };
 
     println!("user: {}", article.summarize_user());   
@@ -205,13 +232,15 @@ fn test_notify() {
         username: String::from("ebooks"),
         content: String::from("blah blah blah"),
         reply: false,
-        retweet: false
+        retweet: false,
+        published: 0
     };
 
     let article = NewsArticle {
         headline: String::from("headline"),
         location: String::from("location"),
-        author: String::from("author")
+        author: String::from("author"),
+        published: 0
     };
 
     notify(tweet);
@@ -235,27 +264,31 @@ fn test_notify2() {
         username: String::from("Bilbo"),
         content: String::from("blah blah blah"),
         reply: false,
-        retweet: false
+        retweet: false,
+        published: 0
     };
 
     let tweet2 = Tweet {
         username: String::from("Sam"),
         content: String::from("blah blah blah"),
         reply: false,
-        retweet: false
+        retweet: false,
+        published: 0
     };
 
     let tweet3 = Tweet {
         username: String::from("Smaud"),
         content: String::from("blah blah blah"),
         reply: false,
-        retweet: false
+        retweet: false,
+        published: 0
     };
 
     let article = NewsArticle {
         headline: String::from("headline"),
         location: String::from("location"),
-        author: String::from("Frodo")
+        author: String::from("Frodo"),
+        published: 0
     };
 
     notify2(tweet1);
@@ -287,6 +320,7 @@ fn some_overly_verbose_function<T: Display + Clone, U: Clone + Debug>(t: T, u: U
 
 #[allow(dead_code)]
 #[allow(unused_variables)]
+#[allow(clippy::extra_unused_type_parameters)]
 fn terse_function_with_multiple_trait_bounds<T, U>(t: T, u: T) -> i32 
     where   T: Display + Clone,
             U: Clone + Debug 
@@ -302,7 +336,8 @@ fn returns_summarisable() -> impl Summary {
         username: String::from("Smaud"),
         content: String::from("blah blah blah"),
         reply: false,
-        retweet: false
+        retweet: false,
+        published: 0
     }    
 }
 
@@ -342,6 +377,10 @@ fn largest<T: PartialOrd + Copy>(list: &[T]) -> T {
     largest
 }
 
+// This panics on an empty slice and won't take a String, as that isn't Copy.  Versions that
+// return an Option<&T> instead, along with top_k, min_max and median, are in src/selection.rs.
+use generic_traits_and_lifetimes::selection;
+
 fn test_largest() {
 
     let num_list = vec![34, 54, 12, 34];
@@ -349,6 +388,11 @@ fn test_largest() {
 
     let char_list = vec!['y', 'a', 'b', 'm'];
     println!("largest2: {}", largest(&char_list));
+
+    let word_list = vec![String::from("pear"), String::from("fig"), String::from("plum")];
+    println!("largest3: {:?}", selection::largest(&word_list));
+    println!("longest: {:?}", selection::largest_by_key(&word_list, |w| w.len()));
+    println!("top2: {:?}, median: {:?}", selection::top_k(&num_list, 2), selection::median(&num_list));
 }
 
 // Using Trait bounds to conditionally implement methods.
@@ -370,6 +414,15 @@ impl<T: Display + PartialOrd> Pair<T> {
     }
 }
 
+// A Pair that returns the largest member rather than printing it, and is Ord, Hash, etc. when T
+// is, is in src/pair.rs.
+use generic_traits_and_lifetimes::pair::Pair as OrderedPair;
+
+fn test_pair() {
+    let pair = OrderedPair::new(String::from("pear"), String::from("fig"));
+    println!("largest member = {}, sorted = {:?}", pair.largest(), pair.clone().sorted());
+}
+
 // Conditionally implement a trait for any type that implements another trait.
 // For example the standard library implements a ToString trait on any type that implements the 
 // Display trait...
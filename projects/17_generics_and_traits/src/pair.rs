@@ -0,0 +1,180 @@
+// The Pair<T> from main.rs, with methods that return what they find rather than printing it.
+//
+//   let pair = Pair::new(3, 7);
+//   assert_eq!(pair.largest(), &7);
+//   assert_eq!(Pair::new(7, 3).sorted(), pair);
+//   assert_eq!(pair.map(|n| n * 2), Pair::new(6, 14));
+//
+// Like cmp_display, largest and smallest only need T: PartialOrd, so they work on floats.  If x
+// and y are equal both return x.  If they can't be compared, as with a NaN, both return y.
+// They aren't called max and min because a Pair of an Ord type is Ord itself, and then
+// pair.max() would find Ord::max, which compares two pairs, before a method taking &self.
+//
+// The traits are derived, so a Pair is Ord, Hash, etc. whenever T is.  Pairs are ordered by x
+// and then by y, like the tuple (x, y).  With serde a pair is the same as that tuple, [x, y] in
+// JSON.
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::mem;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pair<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Pair<T> {
+    pub const fn new(x: T, y: T) -> Pair<T> {
+        Pair { x, y }
+    }
+
+    // Swaps x and y in place.
+    pub fn swap(&mut self) {
+        mem::swap(&mut self.x, &mut self.y);
+    }
+
+    // A new pair with 'f' applied to both members, e.g. pair.map(f64::from).
+    pub fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> Pair<U> {
+        Pair::new(f(self.x), f(self.y))
+    }
+
+    pub fn as_ref(&self) -> Pair<&T> {
+        Pair::new(&self.x, &self.y)
+    }
+}
+
+impl<T: PartialOrd> Pair<T> {
+    pub fn largest(&self) -> &T {
+        if self.x >= self.y {
+            &self.x
+        } else {
+            &self.y
+        }
+    }
+
+    pub fn smallest(&self) -> &T {
+        if self.x <= self.y {
+            &self.x
+        } else {
+            &self.y
+        }
+    }
+
+    // The same pair with the smaller member in x.  Left as it is if x and y are equal or can't
+    // be compared.
+    pub fn sorted(mut self) -> Pair<T> {
+        if self.y < self.x {
+            self.swap();
+        }
+        self
+    }
+}
+
+impl<T> From<(T, T)> for Pair<T> {
+    fn from((x, y): (T, T)) -> Pair<T> {
+        Pair::new(x, y)
+    }
+}
+
+impl<T> From<Pair<T>> for (T, T) {
+    fn from(pair: Pair<T>) -> (T, T) {
+        (pair.x, pair.y)
+    }
+}
+
+// serde's derive isn't enabled in this crate, and it would give {"x":..,"y":..} anyway, so
+// these go through the tuple's implementations.
+impl<T: Serialize> Serialize for Pair<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (&self.x, &self.y).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Pair<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Pair<T>, D::Error> {
+        <(T, T)>::deserialize(deserializer).map(Pair::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn largest_and_smallest() {
+        let pair = Pair::new(3, 7);
+        assert_eq!(pair.largest(), &7);
+        assert_eq!(pair.smallest(), &3);
+        assert_eq!(Pair::new(7, 3).largest(), &7);
+        assert_eq!(Pair::new(7, 3).smallest(), &3);
+
+        // They return references, so T needn't be Copy.
+        let words = Pair::new(String::from("pear"), String::from("fig"));
+        assert_eq!(words.largest(), "pear");
+        assert_eq!(words.smallest(), "fig");
+    }
+
+    #[test]
+    fn equal_and_incomparable_members() {
+        let pair = Pair::new((1, 'a'), (1, 'a'));
+        assert!(std::ptr::eq(pair.largest(), &pair.x));
+        assert!(std::ptr::eq(pair.smallest(), &pair.x));
+
+        let nan = Pair::new(f64::NAN, 1.0);
+        assert_eq!(nan.largest(), &1.0);
+        assert_eq!(nan.smallest(), &1.0);
+        assert!(nan.sorted().x.is_nan());
+    }
+
+    #[test]
+    fn sorted_swap_and_map() {
+        assert_eq!(Pair::new(9, 2).sorted(), Pair::new(2, 9));
+        assert_eq!(Pair::new(2, 9).sorted(), Pair::new(2, 9));
+
+        let mut pair = Pair::new('a', 'b');
+        pair.swap();
+        assert_eq!(pair, Pair::new('b', 'a'));
+
+        assert_eq!(Pair::new(1u8, 2).map(f64::from), Pair::new(1.0, 2.0));
+        assert_eq!(
+            Pair::new(String::from("ab"), String::from("c"))
+                .as_ref()
+                .map(String::len),
+            Pair::new(2, 1)
+        );
+    }
+
+    #[test]
+    fn ordering_and_hashing() {
+        let mut pairs = vec![Pair::new(2, 1), Pair::new(1, 5), Pair::new(1, 2)];
+        pairs.sort();
+        assert_eq!(
+            pairs,
+            vec![Pair::new(1, 2), Pair::new(1, 5), Pair::new(2, 1)]
+        );
+        assert_eq!(pairs.iter().max(), Some(&Pair::new(2, 1)));
+
+        let set: HashSet<_> = vec![Pair::new(1, 2), Pair::new(2, 1), Pair::new(1, 2)]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn tuple_conversions() {
+        let pair: Pair<_> = (1, 2).into();
+        assert_eq!(pair, Pair::new(1, 2));
+        assert_eq!(<(i32, i32)>::from(pair), (1, 2));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let pair = Pair::new(String::from("x"), String::from("y"));
+        let json = serde_json::to_string(&pair).unwrap();
+        assert_eq!(json, r#"["x","y"]"#);
+        assert_eq!(serde_json::from_str::<Pair<String>>(&json).unwrap(), pair);
+
+        assert!(serde_json::from_str::<Pair<i32>>("[1]").is_err());
+        assert!(serde_json::from_str::<Pair<i32>>("[1, 2, 3]").is_err());
+    }
+}
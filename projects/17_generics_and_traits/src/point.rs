@@ -0,0 +1,360 @@
+// The Point<T> from main.rs as a proper vector type.
+//
+// The number of dimensions is a const generic parameter as well as the type of the coordinates,
+// so Point<f64, 2> and Point<f64, 3> are different types and adding one to the other won't
+// compile.  The coordinates are a [T; N], and the operators work on any T that supports them:
+//
+//   let a = Point::new([1, 2, 3]);
+//   let b = Point::new([4, 5, 6]);
+//   assert_eq!(a + b, Point::new([5, 7, 9]));
+//   assert_eq!(-a * 2, Point::new([-2, -4, -6]));
+//   assert_eq!(a.dot(&b), 32);
+//
+// Like distance_from_origin in main.rs, the methods that need a square root (norm, distance,
+// normalized) are only implemented for the float types.  As norm exists for both f32 and f64,
+// a point of untyped float literals needs its type given before calling them:
+// Point::new([3.0f64, 4.0]).norm().
+//
+// With serde a point is a sequence of its coordinates, so [1, 2, 3] in JSON.
+//
+// The conversions to and from the JSON TCP example's Point3D are behind the point3d feature, as
+// that crate brings TLS and all of its serde formats with it:
+//   cargo test --features point3d
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeTuple, Serializer};
+use std::convert::TryInto;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point<T, const N: usize> {
+    coords: [T; N],
+}
+
+impl<T, const N: usize> Point<T, N> {
+    pub const fn new(coords: [T; N]) -> Point<T, N> {
+        Point { coords }
+    }
+
+    pub fn coords(&self) -> &[T; N] {
+        &self.coords
+    }
+
+    pub fn into_coords(self) -> [T; N] {
+        self.coords
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.coords.iter()
+    }
+
+    // A new point with 'f' applied to each coordinate, e.g. p.map(f64::from).
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> Point<U, N> {
+        Point::new(self.coords.map(f))
+    }
+}
+
+// Default::default() is zero for the number types, so this is the origin for them.
+impl<T: Default, const N: usize> Default for Point<T, N> {
+    fn default() -> Point<T, N> {
+        Point::new(std::array::from_fn(|_| T::default()))
+    }
+}
+
+impl<T, const N: usize> Point<T, N>
+where
+    T: Copy + Default + Add<Output = T> + Mul<Output = T>,
+{
+    // The sum of the products of the coordinates.
+    pub fn dot(&self, other: &Point<T, N>) -> T {
+        self.iter()
+            .zip(other.iter())
+            .fold(T::default(), |sum, (&a, &b)| sum + a * b)
+    }
+}
+
+// Specific implementations for f32 and f64, written once with a macro.
+macro_rules! impl_float_point {
+    ($float:ty) => {
+        impl<const N: usize> Point<$float, N> {
+            // The length of the vector from the origin to this point.
+            pub fn norm(&self) -> $float {
+                self.dot(self).sqrt()
+            }
+
+            pub fn distance(&self, other: &Point<$float, N>) -> $float {
+                (*self - *other).norm()
+            }
+
+            // The point at the same direction from the origin but at distance 1, or None for
+            // the origin, which has no direction.
+            pub fn normalized(&self) -> Option<Point<$float, N>> {
+                let norm = self.norm();
+                if norm == 0.0 || !norm.is_finite() {
+                    None
+                } else {
+                    Some(self.map(|c| c / norm))
+                }
+            }
+        }
+    };
+}
+
+impl_float_point!(f32);
+impl_float_point!(f64);
+
+impl<T, const N: usize> Add for Point<T, N>
+where
+    T: Copy + Add<Output = T>,
+{
+    type Output = Point<T, N>;
+
+    fn add(self, other: Point<T, N>) -> Point<T, N> {
+        Point::new(std::array::from_fn(|i| self.coords[i] + other.coords[i]))
+    }
+}
+
+impl<T, const N: usize> Sub for Point<T, N>
+where
+    T: Copy + Sub<Output = T>,
+{
+    type Output = Point<T, N>;
+
+    fn sub(self, other: Point<T, N>) -> Point<T, N> {
+        Point::new(std::array::from_fn(|i| self.coords[i] - other.coords[i]))
+    }
+}
+
+// Multiplying by a scalar, point * 2.  The scalar has to be the coordinate type.
+impl<T, const N: usize> Mul<T> for Point<T, N>
+where
+    T: Copy + Mul<Output = T>,
+{
+    type Output = Point<T, N>;
+
+    fn mul(self, scalar: T) -> Point<T, N> {
+        self.map(|c| c * scalar)
+    }
+}
+
+impl<T, const N: usize> Neg for Point<T, N>
+where
+    T: Neg<Output = T>,
+{
+    type Output = Point<T, N>;
+
+    fn neg(self) -> Point<T, N> {
+        self.map(|c| -c)
+    }
+}
+
+impl<T, const N: usize> Index<usize> for Point<T, N> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        &self.coords[i]
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for Point<T, N> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        &mut self.coords[i]
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Point<T, N> {
+    fn from(coords: [T; N]) -> Point<T, N> {
+        Point::new(coords)
+    }
+}
+
+// (1, 2, 3).  Formatting options are passed on to each coordinate, so {:.1} gives (1.0, 2.0).
+impl<T: fmt::Display, const N: usize> fmt::Display for Point<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        for (i, c) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            fmt::Display::fmt(c, f)?;
+        }
+        write!(f, ")")
+    }
+}
+
+// serde only derives for arrays up to a fixed length, so the two traits are written by hand.
+impl<T: Serialize, const N: usize> Serialize for Point<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for c in self.iter() {
+            tuple.serialize_element(c)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for Point<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Point<T, N>, D::Error> {
+        deserializer.deserialize_tuple(N, PointVisitor(PhantomData))
+    }
+}
+
+struct PointVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for PointVisitor<T, N> {
+    type Value = Point<T, N>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of {} coordinates", N)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Point<T, N>, A::Error> {
+        let mut coords = Vec::with_capacity(N);
+        while coords.len() < N {
+            match seq.next_element()? {
+                Some(c) => coords.push(c),
+                None => return Err(de::Error::invalid_length(coords.len(), &self)),
+            }
+        }
+        if seq.next_element::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(N + 1, &self));
+        }
+        match coords.try_into() {
+            Ok(coords) => Ok(Point::new(coords)),
+            Err(_) => unreachable!("exactly N coordinates were read"),
+        }
+    }
+}
+
+// The Point3D sent over the network in the JSON TCP example.
+#[cfg(feature = "point3d")]
+mod point3d {
+    use super::Point;
+    use json_tcp_client_and_server::Point3D;
+
+    impl From<Point3D> for Point<u32, 3> {
+        fn from(p: Point3D) -> Point<u32, 3> {
+            Point::new([p.x, p.y, p.z])
+        }
+    }
+
+    // u32 converts to f64 exactly, and this is the type with a norm.
+    impl From<Point3D> for Point<f64, 3> {
+        fn from(p: Point3D) -> Point<f64, 3> {
+            Point::<u32, 3>::from(p).map(f64::from)
+        }
+    }
+
+    impl From<Point<u32, 3>> for Point3D {
+        fn from(p: Point<u32, 3>) -> Point3D {
+            let [x, y, z] = p.into_coords();
+            Point3D { x, y, z }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = Point::new([1, 2, 3]);
+        let b = Point::new([4, 5, 6]);
+        assert_eq!(a + b, Point::new([5, 7, 9]));
+        assert_eq!(b - a, Point::new([3, 3, 3]));
+        assert_eq!(a * 3, Point::new([3, 6, 9]));
+        assert_eq!(-a, Point::new([-1, -2, -3]));
+        assert_eq!(a.dot(&b), 32);
+        assert_eq!(a + Point::default(), a);
+
+        let p = Point::new([1.5, -0.5]);
+        assert_eq!(p * 2.0, Point::new([3.0, -1.0]));
+        assert_eq!(p - p, Point::default());
+    }
+
+    #[test]
+    fn zero_and_one_dimensions() {
+        let empty: Point<i32, 0> = Point::new([]);
+        assert_eq!(empty + empty, empty);
+        assert_eq!(empty.dot(&empty), 0);
+        assert_eq!(Point::<f64, 0>::default().norm(), 0.0);
+
+        assert_eq!(Point::new([-4.0f32]).norm(), 4.0);
+    }
+
+    #[test]
+    fn float_methods() {
+        let p = Point::new([3.0f64, 4.0]);
+        assert_eq!(p.norm(), 5.0);
+        assert_eq!(p.distance(&Point::new([0.0, 8.0])), 5.0);
+
+        let unit = p.normalized().unwrap();
+        assert!((unit.norm() - 1.0).abs() < 1e-12);
+        assert_eq!(unit, Point::new([0.6, 0.8]));
+        assert_eq!(Point::<f32, 3>::default().normalized(), None);
+        assert_eq!(Point::new([f64::INFINITY, 1.0]).normalized(), None);
+    }
+
+    #[test]
+    fn indexing() {
+        let mut p = Point::new(['a', 'b', 'c']);
+        assert_eq!(p[1], 'b');
+        p[1] = 'z';
+        assert_eq!(p.coords(), &['a', 'z', 'c']);
+        assert_eq!(Point::from([1, 2]).into_coords(), [1, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn indexing_out_of_range_panics() {
+        let p = Point::new([1, 2]);
+        let _ = p[2];
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Point::new([1, -2, 3]).to_string(), "(1, -2, 3)");
+        assert_eq!(format!("{:.1}", Point::new([1.0, 2.25])), "(1.0, 2.2)");
+        assert_eq!(Point::<u8, 0>::new([]).to_string(), "()");
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let p = Point::new([1.5, -2.0, 3.25]);
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(json, "[1.5,-2.0,3.25]");
+        assert_eq!(serde_json::from_str::<Point<f64, 3>>(&json).unwrap(), p);
+
+        let err = serde_json::from_str::<Point<i32, 3>>("[1, 2]").unwrap_err();
+        assert!(
+            err.to_string().contains("a sequence of 3 coordinates"),
+            "{}",
+            err
+        );
+        assert!(serde_json::from_str::<Point<i32, 3>>("[1, 2, 3, 4]").is_err());
+        assert!(serde_json::from_str::<Point<i32, 2>>("[1, \"two\"]").is_err());
+    }
+
+    #[cfg(feature = "point3d")]
+    #[test]
+    fn converts_from_point3d() {
+        use json_tcp_client_and_server::Point3D;
+
+        let p3 = Point3D { x: 3, y: 4, z: 12 };
+        let p: Point<u32, 3> = p3.into();
+        assert_eq!(p, Point::new([3, 4, 12]));
+        assert_eq!(Point3D::from(p), p3);
+
+        // The same distance the JSON TCP server works out.
+        let p: Point<f64, 3> = p3.into();
+        assert_eq!(p.norm(), p3.distance());
+        let big = Point3D {
+            x: u32::MAX,
+            y: u32::MAX,
+            z: 0,
+        };
+        assert_eq!(Point::<f64, 3>::from(big).norm(), big.distance());
+    }
+}
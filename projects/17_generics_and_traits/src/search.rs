@@ -0,0 +1,619 @@
+// Full text search over anything that implements Summary.
+//
+// An inverted index maps each word to the items it appears in, rather than each item to its
+// words, so finding the items with a word is a single lookup:
+//
+//   let mut index = SearchIndex::new();
+//   let id = index.add(&article);
+//   for hit in index.search("rain \"fog on the tyne\"") {
+//       println!("{:.3} {}", hit.score, index.summary(hit.id).unwrap());
+//   }
+//
+// Indexing: summarize() is split into tokens, runs of letters and digits (an apostrophe inside
+// a word is kept, "don't"), lowercased.  Stop words, common words like "the" that say nothing
+// about what an item is about, are not indexed, but they still count when numbering the
+// positions of the other tokens, so phrases can be matched across them.
+//
+// Searching: the query's words are matched against the index in the same way.  A "quoted
+// phrase" must appear in an item, in that order, for the item to match, and then the words
+// outside quotes only affect the ranking.  Without phrases, an item matches if it contains any
+// of the words.  The matches are ranked by TF-IDF: for each query word, how often it appears in
+// the item (as a fraction of the item's tokens) times how rare it is across all items,
+// ln(1 + items / items with the word).  So a word that appears in every item counts for little.
+//
+// The index can be saved to and loaded from a text file.  Its first line is the format's name
+// and version, 'summary-index 1', and loading rejects any other version.  Changes to the
+// tokenizer or stop words change what is in the index, so they need a new version too.
+use crate::summary::Summary;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+pub type DocId = u64;
+
+pub const FORMAT_VERSION: u32 = 1;
+const FORMAT_NAME: &str = "summary-index";
+
+const STOP_WORDS: [&str; 33] = [
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "he",
+    "i", "in", "is", "it", "its", "of", "on", "or", "she", "so", "that", "the", "their", "they",
+    "this", "to", "was", "were", "with",
+];
+
+pub fn is_stop_word(token: &str) -> bool {
+    STOP_WORDS.contains(&token)
+}
+
+// The lowercased tokens in 'text', stop words included.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '\u{2019}'))
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase().replace('\u{2019}', "'"))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub id: DocId,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Doc {
+    summary: String,
+    length: u32, // The number of tokens, stop words included.
+}
+
+// The words and phrases in a search.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Query {
+    terms: Vec<String>,        // Outside quotes, stop words taken out.
+    phrases: Vec<Vec<String>>, // Inside quotes, stop words left in.
+}
+
+impl Query {
+    // An unclosed quote runs to the end of the query.
+    fn parse(text: &str) -> Query {
+        let mut query = Query::default();
+        for (i, part) in text.split('"').enumerate() {
+            let tokens = tokenize(part);
+            if i % 2 == 0 {
+                query
+                    .terms
+                    .extend(tokens.into_iter().filter(|t| !is_stop_word(t)));
+            } else if tokens.iter().any(|t| !is_stop_word(t)) {
+                query.phrases.push(tokens);
+            }
+        }
+        query
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchIndex {
+    // token -> the items it is in -> its positions in each.
+    postings: HashMap<String, BTreeMap<DocId, Vec<u32>>>,
+    docs: BTreeMap<DocId, Doc>,
+    next_id: DocId,
+}
+
+impl SearchIndex {
+    pub fn new() -> SearchIndex {
+        SearchIndex::default()
+    }
+
+    // Works for trait objects as well, index.add(boxed.as_ref()).
+    pub fn add<S: Summary + ?Sized>(&mut self, item: &S) -> DocId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let summary = item.summarize();
+        let tokens = tokenize(&summary);
+        for (position, token) in tokens.iter().enumerate() {
+            if !is_stop_word(token) {
+                self.postings
+                    .entry(token.clone())
+                    .or_default()
+                    .entry(id)
+                    .or_default()
+                    .push(position as u32);
+            }
+        }
+        let length = tokens.len() as u32;
+        self.docs.insert(id, Doc { summary, length });
+        id
+    }
+
+    // Returns false if there was no item 'id'.  Ids aren't reused.
+    pub fn remove(&mut self, id: DocId) -> bool {
+        let doc = match self.docs.remove(&id) {
+            Some(doc) => doc,
+            None => return false,
+        };
+        for token in tokenize(&doc.summary) {
+            if let Some(docs) = self.postings.get_mut(&token) {
+                docs.remove(&id);
+                if docs.is_empty() {
+                    self.postings.remove(&token);
+                }
+            }
+        }
+        true
+    }
+
+    pub fn summary(&self, id: DocId) -> Option<&str> {
+        self.docs.get(&id).map(|doc| doc.summary.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    // The number of different tokens indexed.
+    pub fn vocabulary(&self) -> usize {
+        self.postings.len()
+    }
+
+    // The matching items, best first.  Items with the same score are in the order they were
+    // added.
+    pub fn search(&self, query: &str) -> Vec<Hit> {
+        let query = Query::parse(query);
+        if query.terms.is_empty() && query.phrases.is_empty() {
+            return Vec::new();
+        }
+
+        // Every item with all the phrases, or with any term if there are no phrases.
+        let mut candidates: Option<HashSet<DocId>> = None;
+        for phrase in &query.phrases {
+            let found = self.phrase_matches(phrase);
+            candidates = Some(match candidates {
+                Some(before) => before.intersection(&found).copied().collect(),
+                None => found,
+            });
+        }
+        let candidates = candidates.unwrap_or_else(|| {
+            query
+                .terms
+                .iter()
+                .filter_map(|term| self.postings.get(term))
+                .flat_map(|docs| docs.keys().copied())
+                .collect()
+        });
+
+        // Phrase words count towards the score as well.
+        let mut scored_words: Vec<&str> = query.terms.iter().map(String::as_str).collect();
+        scored_words.extend(
+            query
+                .phrases
+                .iter()
+                .flatten()
+                .filter(|t| !is_stop_word(t))
+                .map(String::as_str),
+        );
+
+        let mut hits: Vec<Hit> = candidates
+            .into_iter()
+            .map(|id| Hit {
+                id,
+                score: scored_words.iter().map(|word| self.tf_idf(word, id)).sum(),
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        hits
+    }
+
+    fn tf_idf(&self, token: &str, id: DocId) -> f64 {
+        let docs = match self.postings.get(token) {
+            Some(docs) => docs,
+            None => return 0.0,
+        };
+        let count = docs.get(&id).map_or(0, Vec::len);
+        let length = self.docs[&id].length.max(1);
+        let tf = count as f64 / f64::from(length);
+        let idf = (1.0 + self.docs.len() as f64 / docs.len() as f64).ln();
+        tf * idf
+    }
+
+    // The items with 'phrase' in them.  Its stop words aren't indexed, so they only hold the
+    // places of the other words.
+    fn phrase_matches(&self, phrase: &[String]) -> HashSet<DocId> {
+        let words: Vec<(u32, &BTreeMap<DocId, Vec<u32>>)> = match phrase
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| !is_stop_word(token))
+            .map(|(offset, token)| self.postings.get(token).map(|docs| (offset as u32, docs)))
+            .collect::<Option<_>>()
+        {
+            Some(words) => words,
+            None => return HashSet::new(), // A word that is in no item.
+        };
+        let (first_offset, first_docs) = words[0];
+
+        first_docs
+            .iter()
+            .filter(|(id, starts)| {
+                starts.iter().any(|&start| {
+                    let phrase_start = match start.checked_sub(first_offset) {
+                        Some(phrase_start) => phrase_start,
+                        None => return false,
+                    };
+                    words[1..].iter().all(|(offset, docs)| {
+                        docs.get(id).is_some_and(|positions| {
+                            positions.binary_search(&(phrase_start + offset)).is_ok()
+                        })
+                    })
+                })
+            })
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    // The format, a line at a time:
+    //
+    //   summary-index 1
+    //   next <the next id>
+    //   docs <count>
+    //   <id> <length> <summary>                one line per item
+    //   tokens <count>
+    //   <token> <id>:<pos>,<pos> <id>:<pos>    one line per token
+    //
+    // Backslashes, newlines and tabs in summaries are escaped as \\, \n and \t.
+    pub fn save<W: Write>(&self, writer: W) -> Result<(), String> {
+        let mut w = BufWriter::new(writer);
+        let result = (|| -> std::io::Result<()> {
+            writeln!(w, "{} {}", FORMAT_NAME, FORMAT_VERSION)?;
+            writeln!(w, "next {}", self.next_id)?;
+            writeln!(w, "docs {}", self.docs.len())?;
+            for (id, doc) in &self.docs {
+                writeln!(w, "{} {} {}", id, doc.length, escape(&doc.summary))?;
+            }
+            writeln!(w, "tokens {}", self.postings.len())?;
+            // Sorted, so the same index always gives the same file.
+            let mut tokens: Vec<&String> = self.postings.keys().collect();
+            tokens.sort();
+            for token in tokens {
+                write!(w, "{}", token)?;
+                for (id, positions) in &self.postings[token] {
+                    let positions: Vec<String> = positions.iter().map(u32::to_string).collect();
+                    write!(w, " {}:{}", id, positions.join(","))?;
+                }
+                writeln!(w)?;
+            }
+            w.flush()
+        })();
+        result.map_err(|e| e.to_string())
+    }
+
+    pub fn load<R: BufRead>(reader: R) -> Result<SearchIndex, String> {
+        let mut lines = reader.lines().enumerate().map(|(i, line)| {
+            line.map(|line| (i + 1, line))
+                .map_err(|e| format!("line {}: {}", i + 1, e))
+        });
+        let mut next_line = |what: &str| -> Result<(usize, String), String> {
+            lines
+                .next()
+                .unwrap_or_else(|| Err(format!("unexpected end of file, expected {}", what)))
+        };
+
+        let (_, header) = next_line("the header")?;
+        match header.strip_prefix(FORMAT_NAME).map(str::trim) {
+            Some(version) if version == FORMAT_VERSION.to_string() => {}
+            Some(version) => {
+                return Err(format!(
+                    "line 1: unsupported index version '{}', expected {}",
+                    version, FORMAT_VERSION
+                ))
+            }
+            None => return Err(String::from("line 1: not a summary index")),
+        }
+
+        let mut index = SearchIndex::new();
+        index.next_id = counted_line(next_line("next")?, "next")?;
+
+        let doc_count = counted_line(next_line("docs")?, "docs")?;
+        for _ in 0..doc_count {
+            let (n, line) = next_line("a document")?;
+            let bad = |what: &str| format!("line {}: bad {}", n, what);
+            let mut fields = line.splitn(3, ' ');
+            let id: DocId = parse_field(fields.next(), || bad("document id"))?;
+            let length: u32 = parse_field(fields.next(), || bad("document length"))?;
+            let summary = unescape(fields.next().unwrap_or("")).ok_or_else(|| bad("summary"))?;
+            if id >= index.next_id || index.docs.insert(id, Doc { summary, length }).is_some() {
+                return Err(bad("document id"));
+            }
+        }
+
+        let token_count = counted_line(next_line("tokens")?, "tokens")?;
+        for _ in 0..token_count {
+            let (n, line) = next_line("a token")?;
+            let bad = |what: &str| format!("line {}: bad {}", n, what);
+            let mut fields = line.split(' ');
+            let token = fields
+                .next()
+                .filter(|t| !t.is_empty())
+                .ok_or_else(|| bad("token"))?;
+            let mut docs = BTreeMap::new();
+            for posting in fields {
+                let (id, positions) = posting.split_once(':').ok_or_else(|| bad("posting"))?;
+                let id: DocId = id.parse().map_err(|_| bad("posting"))?;
+                let positions: Vec<u32> = positions
+                    .split(',')
+                    .map(|p| p.parse().map_err(|_| bad("position")))
+                    .collect::<Result<_, _>>()?;
+                let length = index.docs.get(&id).ok_or_else(|| bad("posting id"))?.length;
+                if positions.windows(2).any(|w| w[0] >= w[1])
+                    || positions.iter().any(|&p| p >= length)
+                {
+                    return Err(bad("position"));
+                }
+                docs.insert(id, positions);
+            }
+            if docs.is_empty() || index.postings.insert(token.to_string(), docs).is_some() {
+                return Err(bad("token"));
+            }
+        }
+
+        if let Some((n, _)) = lines.next().transpose()? {
+            return Err(format!("line {}: unexpected data after the index", n));
+        }
+        Ok(index)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        self.save(file)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn load_from(path: &Path) -> Result<SearchIndex, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        SearchIndex::load(BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+// A line '<name> <number>'.
+fn counted_line<T: std::str::FromStr>((n, line): (usize, String), name: &str) -> Result<T, String> {
+    line.strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix(' '))
+        .and_then(|number| number.parse().ok())
+        .ok_or_else(|| format!("line {}: expected '{} <number>'", n, name))
+}
+
+fn parse_field<T: std::str::FromStr>(
+    field: Option<&str>,
+    bad: impl Fn() -> String,
+) -> Result<T, String> {
+    field.and_then(|f| f.parse().ok()).ok_or_else(bad)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.push(match chars.next()? {
+                '\\' => '\\',
+                'n' => '\n',
+                't' => '\t',
+                _ => return None,
+            });
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::summary::{NewsArticle, Tweet};
+    use std::env;
+    use std::fs;
+
+    fn tweet(username: &str, content: &str) -> Tweet {
+        Tweet {
+            username: String::from(username),
+            content: String::from(content),
+            reply: false,
+            retweet: false,
+            published: 0,
+        }
+    }
+
+    fn article(headline: &str, location: &str) -> NewsArticle {
+        NewsArticle {
+            headline: String::from(headline),
+            location: String::from(location),
+            author: String::from("Ann"),
+            published: 0,
+        }
+    }
+
+    // Ids 0 to 4.
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::new();
+        index.add(&article("Fog on the Tyne all day", "Newcastle"));
+        index.add(&tweet("bob", "Rain, rain and more rain in Leeds"));
+        index.add(&article("Rain expected in Leeds", "Leeds"));
+        index.add(&tweet("cat", "The fog is on its way to the Tyne"));
+        let boxed: Box<dyn Summary> = Box::new(tweet("dan", "Sunny in Tyne and Wear"));
+        index.add(boxed.as_ref());
+        index
+    }
+
+    fn ids(hits: &[Hit]) -> Vec<DocId> {
+        hits.iter().map(|hit| hit.id).collect()
+    }
+
+    #[test]
+    fn tokenizes() {
+        assert_eq!(
+            tokenize("Rain, RAIN & don't \u{2019}panic\u{2019}: 12.5mm -- Zürich!"),
+            vec!["rain", "rain", "don't", "panic", "12", "5mm", "zürich"]
+        );
+        assert!(tokenize(" ... ").is_empty());
+        assert!(is_stop_word("the"));
+        assert!(!is_stop_word("rain"));
+    }
+
+    #[test]
+    fn parses_queries() {
+        let query = Query::parse("rain the \"fog ON the Tyne\" leeds \"the\" \"sunny");
+        assert_eq!(query.terms, vec!["rain", "leeds"]);
+        assert_eq!(
+            query.phrases,
+            vec![vec!["fog", "on", "the", "tyne"], vec!["sunny"]]
+        );
+    }
+
+    #[test]
+    fn ranks_by_tf_idf() {
+        let index = index();
+        assert_eq!(index.len(), 5);
+
+        // Three rains in 8 tokens beat one in 7.
+        let hits = index.search("rain");
+        assert_eq!(ids(&hits), vec![1, 2]);
+        assert!(hits[0].score > hits[1].score);
+
+        // "leeds" is in both as well, but twice in the article.
+        let hits = index.search("Leeds");
+        assert_eq!(ids(&hits), vec![2, 1]);
+
+        // Either word will do, the rarer word counts for more.
+        let hits = index.search("sunny fog");
+        assert_eq!(ids(&hits), vec![4, 0, 3]);
+
+        assert!(index.search("snow").is_empty());
+        assert!(index.search("the and").is_empty());
+        assert!(index.search("").is_empty());
+    }
+
+    #[test]
+    fn scores() {
+        let index = index();
+        // 'sunny' is in 1 of 5 items, and is 1 of the 6 tokens of "dan: Sunny in Tyne and Wear".
+        let hits = index.search("sunny");
+        let expected = (1.0 / 6.0) * (1.0f64 + 5.0).ln();
+        assert!((hits[0].score - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn phrase_queries() {
+        let index = index();
+        // Stop words hold their places: "fog is on its way to the tyne" doesn't match.
+        assert_eq!(ids(&index.search("\"fog on the Tyne\"")), vec![0]);
+        assert_eq!(ids(&index.search("\"fog on a Tyne\"")), vec![0]);
+        assert!(index.search("\"Tyne fog\"").is_empty());
+        assert_eq!(ids(&index.search("\"rain in leeds\"")), vec![1]);
+        assert_eq!(ids(&index.search("\"rain expected\"")), vec![2]);
+
+        // Phrases are required, the other words only rank.
+        assert_eq!(ids(&index.search("\"tyne\" sunny")), vec![4, 0, 3]);
+        assert_eq!(ids(&index.search("\"tyne\" \"the fog\"")), vec![3]);
+        assert!(index.search("\"rain\" \"fog\"").is_empty());
+        assert!(index.search("\"fog on the Wear\"").is_empty());
+    }
+
+    #[test]
+    fn add_and_remove() {
+        let mut index = index();
+        let vocabulary = index.vocabulary();
+
+        assert!(index.remove(1));
+        assert!(!index.remove(1));
+        assert!(!index.remove(99));
+        assert_eq!(index.summary(1), None);
+        assert_eq!(ids(&index.search("rain")), vec![2]);
+        // "bob" and "more" were only in item 1.
+        assert_eq!(index.vocabulary(), vocabulary - 2);
+
+        let id = index.add(&tweet("eve", "More rain"));
+        assert_eq!(id, 5);
+        assert_eq!(ids(&index.search("rain")), vec![5, 2]);
+        assert_eq!(index.summary(5), Some("eve: More rain"));
+
+        for id in 0..=5 {
+            index.remove(id);
+        }
+        assert!(index.is_empty());
+        assert_eq!(index.vocabulary(), 0);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut index = index();
+        index.add(&tweet("odd", "line\nbreak\tand \\ backslash"));
+        index.remove(2);
+
+        let mut saved = Vec::new();
+        index.save(&mut saved).unwrap();
+        let text = String::from_utf8(saved.clone()).unwrap();
+        assert!(text.starts_with("summary-index 1\nnext 6\ndocs 5\n0 9 Fog on the Tyne"));
+        assert!(text.contains("\n5 5 odd: line\\nbreak\\tand \\\\ backslash\n"));
+        assert!(text.contains("\nrain 1:1,2,5\n"));
+
+        let loaded = SearchIndex::load(&saved[..]).unwrap();
+        assert_eq!(loaded, index);
+        assert_eq!(
+            loaded.search("\"fog on the tyne\""),
+            index.search("\"fog on the tyne\"")
+        );
+
+        let path = env::temp_dir().join(format!("summary-index-{}", std::process::id()));
+        index.save_to(&path).unwrap();
+        assert_eq!(SearchIndex::load_from(&path).unwrap(), index);
+        fs::remove_file(&path).unwrap();
+        assert!(SearchIndex::load_from(&path).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let load = |text: &str| SearchIndex::load(text.as_bytes()).unwrap_err();
+        assert_eq!(
+            load("summary-index 2\n"),
+            "line 1: unsupported index version '2', expected 1"
+        );
+        assert_eq!(load("hello\n"), "line 1: not a summary index");
+        assert_eq!(load(""), "unexpected end of file, expected the header");
+        assert_eq!(
+            load("summary-index 1\nnext x\n"),
+            "line 2: expected 'next <number>'"
+        );
+
+        let good = "summary-index 1\nnext 2\ndocs 1\n1 2 a: rain\ntokens 1\nrain 1:1\n";
+        assert!(SearchIndex::load(good.as_bytes()).is_ok());
+        for (bad, error) in &[
+            ("1 2 a: rain", "1 2 a: \\q"),
+            ("1 2 a: rain", "2 2 a: rain"),
+            ("rain 1:1", "rain 1:2"),
+            ("rain 1:1", "rain 3:1"),
+            ("rain 1:1", "rain 1:x"),
+            ("rain 1:1", "rain"),
+        ] {
+            let text = good.replace(bad, error);
+            assert!(SearchIndex::load(text.as_bytes()).is_err(), "{}", text);
+        }
+        assert_eq!(
+            load(&format!("{}extra\n", good)),
+            "line 7: unexpected data after the index"
+        );
+        assert_eq!(
+            load("summary-index 1\nnext 2\ndocs 2\n1 2 a: rain\n"),
+            "unexpected end of file, expected a document"
+        );
+    }
+}
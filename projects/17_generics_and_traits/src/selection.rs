@@ -0,0 +1,355 @@
+// The largest function from main.rs, without its two limits:
+// 1).  It starts with list[0], so it panics on an empty slice.  These return None instead.
+// 2).  T has to be Copy so the largest item can be copied out of the slice, which rules out
+//      String.  These return a reference into the slice instead, &T, so T needs no more than
+//      PartialOrd.
+//
+//   let words = vec![String::from("pear"), String::from("fig"), String::from("plum")];
+//   assert_eq!(largest(&words), Some(&words[2]));
+//   assert_eq!(top_k(&[3, 1, 4, 1, 5], 2), vec![&5, &4]);
+//   assert_eq!(min_max(&[3, 1, 4]), Some((&1, &4)));
+//   assert_eq!(median(&[3, 1, 4]), Some(&3));
+//
+// PartialOrd rather than Ord lets them work on floats, which aren't Ord because NaN (not a
+// number) is neither bigger nor smaller than anything, not even itself.  NaNs can't be ranked, so
+// they are skipped: largest(&[1.0, f64::NAN]) is Some(&1.0), and a slice of only NaNs is treated
+// as empty.  This isn't special to floats, any item that can't be compared with itself is
+// skipped.
+//
+// Each function has a _by_key variant that ranks items by a key worked out from each, e.g.
+// largest_by_key(&words, |w| w.len()) for the longest word.  The key function is called once
+// per item, and as it is given a reference into the slice it can return one, |s| s.as_str().
+//
+// Where items are equal the first one wins, so largest(&[a, b]) with a == b returns a, and
+// equal items in top_k keep their order in the slice.
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+// False for NaN.
+fn is_comparable<K: PartialOrd>(key: &K) -> bool {
+    key.partial_cmp(key).is_some()
+}
+
+// An item with its key and its position in the slice.  Ordered by key and then by position,
+// with the earlier item the larger, so that it wins ties.  Only made for keys that aren't NaN,
+// which gives a total order, so it can be Ord for BinaryHeap and select_nth_unstable.
+struct Ranked<'a, T, K> {
+    key: K,
+    index: usize,
+    item: &'a T,
+}
+
+impl<T, K: PartialOrd> Ord for Ranked<'_, T, K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key
+            .partial_cmp(&other.key)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl<T, K: PartialOrd> PartialOrd for Ranked<'_, T, K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, K: PartialOrd> PartialEq for Ranked<'_, T, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T, K: PartialOrd> Eq for Ranked<'_, T, K> {}
+
+fn ranked<'a, T, K, F>(list: &'a [T], mut key: F) -> impl Iterator<Item = Ranked<'a, T, K>>
+where
+    K: PartialOrd,
+    F: FnMut(&'a T) -> K,
+{
+    list.iter()
+        .enumerate()
+        .map(move |(index, item)| Ranked {
+            key: key(item),
+            index,
+            item,
+        })
+        .filter(|r| is_comparable(&r.key))
+}
+
+pub fn largest<T: PartialOrd>(list: &[T]) -> Option<&T> {
+    largest_by_key(list, |item| item)
+}
+
+pub fn largest_by_key<'a, T, K, F>(list: &'a [T], key: F) -> Option<&'a T>
+where
+    K: PartialOrd,
+    F: FnMut(&'a T) -> K,
+{
+    ranked(list, key).max().map(|r| r.item)
+}
+
+// The k largest items, largest first.  All of them if there are fewer than k.
+//
+// Rather than sorting the whole slice this keeps the k largest seen so far in a heap, with the
+// smallest of them on top so it can be dropped when a larger one turns up.  That's O(n log k)
+// rather than O(n log n), and only k items are held at once.
+pub fn top_k<T: PartialOrd>(list: &[T], k: usize) -> Vec<&T> {
+    top_k_by_key(list, k, |item| item)
+}
+
+pub fn top_k_by_key<'a, T, K, F>(list: &'a [T], k: usize, key: F) -> Vec<&'a T>
+where
+    K: PartialOrd,
+    F: FnMut(&'a T) -> K,
+{
+    if k == 0 {
+        return Vec::new();
+    }
+
+    // BinaryHeap keeps the largest on top, Reverse turns it around.
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for r in ranked(list, key) {
+        heap.push(Reverse(r));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+
+    // Ascending order of Reverse is descending order of the items.
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(r)| r.item)
+        .collect()
+}
+
+// The smallest and largest items, found in one pass.  Taking the items in pairs and comparing
+// the smaller of each pair with the minimum and the larger with the maximum takes 3 comparisons
+// per 2 items, rather than 2 per item comparing every item with both.
+pub fn min_max<T: PartialOrd>(list: &[T]) -> Option<(&T, &T)> {
+    min_max_by_key(list, |item| item)
+}
+
+pub fn min_max_by_key<'a, T, K, F>(list: &'a [T], key: F) -> Option<(&'a T, &'a T)>
+where
+    K: PartialOrd,
+    F: FnMut(&'a T) -> K,
+{
+    let mut items = ranked(list, key);
+
+    // Splits a pair into its smaller and larger item.  If they are equal, both are the first
+    // item, but each with its own key so the key needn't be Clone.
+    let order = |a: Ranked<'a, T, K>, b: Ranked<'a, T, K>| match b.key.partial_cmp(&a.key) {
+        Some(Ordering::Less) => (b, a),
+        Some(Ordering::Greater) => (a, b),
+        _ => {
+            let item = a.item;
+            (a, Ranked { item, ..b })
+        }
+    };
+
+    let first = items.next()?;
+    let (mut min, mut max) = match items.next() {
+        Some(second) => order(first, second),
+        None => return Some((first.item, first.item)),
+    };
+
+    while let Some(a) = items.next() {
+        let (small, large) = match items.next() {
+            Some(b) => order(a, b),
+            None => {
+                // One left over, compare it with both.
+                if a.key < min.key {
+                    min = a;
+                } else if a.key > max.key {
+                    max = a;
+                }
+                break;
+            }
+        };
+        if small.key < min.key {
+            min = small;
+        }
+        if large.key > max.key {
+            max = large;
+        }
+    }
+
+    Some((min.item, max.item))
+}
+
+// The item that would be at position n (from 0) if the slice was sorted, smallest first, or
+// None if there are only n items or fewer.
+//
+// This is quickselect, which the standard library has as select_nth_unstable: partition around
+// a pivot like quicksort, but then only carry on into the side that holds position n.  That's
+// O(n) on average rather than sorting's O(n log n).  The slice is borrowed, not sorted in place,
+// so this works on references to the items.
+pub fn nth_element<T: PartialOrd>(list: &[T], n: usize) -> Option<&T> {
+    nth_element_by_key(list, n, |item| item)
+}
+
+pub fn nth_element_by_key<'a, T, K, F>(list: &'a [T], n: usize, key: F) -> Option<&'a T>
+where
+    K: PartialOrd,
+    F: FnMut(&'a T) -> K,
+{
+    select(ranked(list, key).collect(), n)
+}
+
+// The middle item.  With an even number of items there are two, and as the median of those is
+// returned by reference rather than worked out as their average, it's the lower one.
+pub fn median<T: PartialOrd>(list: &[T]) -> Option<&T> {
+    median_by_key(list, |item| item)
+}
+
+pub fn median_by_key<'a, T, K, F>(list: &'a [T], key: F) -> Option<&'a T>
+where
+    K: PartialOrd,
+    F: FnMut(&'a T) -> K,
+{
+    // The NaNs have to be left out before counting the items to find the middle.
+    let items: Vec<_> = ranked(list, key).collect();
+    let middle = items.len().saturating_sub(1) / 2;
+    select(items, middle)
+}
+
+fn select<'a, T, K: PartialOrd>(mut items: Vec<Ranked<'a, T, K>>, n: usize) -> Option<&'a T> {
+    if n >= items.len() {
+        return None;
+    }
+    // Ranked puts the earlier of equal items last, so for 'smallest first' with the earlier of
+    // equal items first, compare the keys one way round and the positions the other.
+    let (_, nth, _) = items.select_nth_unstable_by(n, |a, b| {
+        a.key
+            .partial_cmp(&b.key)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.index.cmp(&b.index))
+    });
+    Some(nth.item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn largest_of_empty_is_none() {
+        let empty: [i32; 0] = [];
+        assert_eq!(largest(&empty), None);
+        assert_eq!(top_k(&empty, 3), Vec::<&i32>::new());
+        assert_eq!(min_max(&empty), None);
+        assert_eq!(median(&empty), None);
+        assert_eq!(nth_element(&empty, 0), None);
+    }
+
+    #[test]
+    fn works_without_copy() {
+        let words: Vec<String> = ["pear", "fig", "plum", "apple"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(largest(&words), Some(&words[2]));
+        assert_eq!(min_max(&words), Some((&words[3], &words[2])));
+        assert_eq!(top_k(&words, 2), vec![&words[2], &words[0]]);
+        assert_eq!(largest_by_key(&words, |w| w.as_str()), Some(&words[2]));
+
+        // The longest word, with "pear" and "plum" tied and "pear" first.
+        assert_eq!(largest_by_key(&words, |w| w.len()), Some(&words[3]));
+        assert_eq!(
+            top_k_by_key(&words, 3, |w| w.len()),
+            vec![&words[3], &words[0], &words[2]]
+        );
+    }
+
+    #[test]
+    fn first_of_equal_items_wins() {
+        let pairs = [(1, 'a'), (3, 'b'), (2, 'c'), (3, 'd'), (1, 'e')];
+        assert_eq!(largest_by_key(&pairs, |p| p.0), Some(&(3, 'b')));
+        assert_eq!(
+            min_max_by_key(&pairs, |p| p.0),
+            Some((&(1, 'a'), &(3, 'b')))
+        );
+        assert_eq!(
+            top_k_by_key(&pairs, 4, |p| p.0),
+            vec![&(3, 'b'), &(3, 'd'), &(2, 'c'), &(1, 'a')]
+        );
+        assert_eq!(nth_element_by_key(&pairs, 0, |p| p.0), Some(&(1, 'a')));
+        assert_eq!(nth_element_by_key(&pairs, 1, |p| p.0), Some(&(1, 'e')));
+        assert_eq!(median_by_key(&pairs, |p| p.0), Some(&(2, 'c')));
+
+        let same = [(0, 'a'), (0, 'b')];
+        assert_eq!(min_max_by_key(&same, |p| p.0), Some((&(0, 'a'), &(0, 'a'))));
+    }
+
+    #[test]
+    fn top_k_keeps_the_largest() {
+        let list = [5, 1, 9, 3, 7, 9, 2];
+        assert_eq!(top_k(&list, 3), vec![&9, &9, &7]);
+        assert_eq!(top_k(&list, 0), Vec::<&i32>::new());
+        assert_eq!(top_k(&list, 100).len(), list.len());
+        assert_eq!(top_k(&list, 100), vec![&9, &9, &7, &5, &3, &2, &1]);
+    }
+
+    #[test]
+    fn min_max_odd_and_even_lengths() {
+        assert_eq!(min_max(&[4]), Some((&4, &4)));
+        assert_eq!(min_max(&[4, 2]), Some((&2, &4)));
+        assert_eq!(min_max(&[4, 2, 8]), Some((&2, &8)));
+        assert_eq!(min_max(&[4, 2, 8, -1]), Some((&-1, &8)));
+        assert_eq!(min_max(&[4, 2, 8, -1, 10]), Some((&-1, &10)));
+        assert_eq!(min_max(&[4, 2, 8, -1, -3]), Some((&-3, &8)));
+    }
+
+    #[test]
+    fn nth_element_and_median_match_sorting() {
+        let list = [
+            31, 4, 15, 9, 26, 5, 35, 8, 97, 93, 23, 84, 62, 64, 33, 83, 27, 95, 2, 88,
+        ];
+        let mut sorted = list.to_vec();
+        sorted.sort();
+        for (n, expected) in sorted.iter().enumerate() {
+            assert_eq!(nth_element(&list, n), Some(expected));
+        }
+        assert_eq!(nth_element(&list, list.len()), None);
+
+        // An even number of items, so the lower of the middle two.
+        assert_eq!(median(&list), Some(&sorted[9]));
+        assert_eq!(median(&list[1..]), Some(&33));
+        assert_eq!(median(&[3, 1, 2]), Some(&2));
+        assert_eq!(median(&[3, 1, 2, 4]), Some(&2));
+    }
+
+    #[test]
+    fn nan_is_skipped() {
+        let nan = f64::NAN;
+        assert_eq!(largest(&[nan, 1.0, nan, 3.0, 2.0]), Some(&3.0));
+        assert_eq!(largest(&[nan, nan]), None);
+        assert_eq!(
+            min_max(&[nan, 1.0, nan, -3.0, 2.0, nan]),
+            Some((&-3.0, &2.0))
+        );
+        assert_eq!(min_max(&[nan]), None);
+        assert_eq!(top_k(&[nan, 1.0, nan, 3.0], 3), vec![&3.0, &1.0]);
+        assert_eq!(nth_element(&[nan, 1.0, nan, 3.0], 1), Some(&3.0));
+        assert_eq!(nth_element(&[nan, 1.0, nan, 3.0], 2), None);
+
+        // Counting the NaNs would put the middle at 3.0.
+        assert_eq!(median(&[nan, nan, nan, 1.0, 2.0, 3.0]), Some(&2.0));
+
+        // Infinities are ordinary numbers.
+        let list = [1.0, f64::INFINITY, nan, f64::NEG_INFINITY];
+        assert_eq!(min_max(&list), Some((&f64::NEG_INFINITY, &f64::INFINITY)));
+    }
+
+    #[test]
+    fn nan_keys_are_skipped() {
+        let readings = [("a", 2.5), ("b", f32::NAN), ("c", 7.0), ("d", 0.5)];
+        assert_eq!(largest_by_key(&readings, |r| r.1), Some(&("c", 7.0)));
+        assert_eq!(
+            min_max_by_key(&readings, |r| r.1),
+            Some((&("d", 0.5), &("c", 7.0)))
+        );
+        assert_eq!(median_by_key(&readings, |r| r.1), Some(&("a", 2.5)));
+    }
+}
@@ -0,0 +1,69 @@
+// The Summary trait and the two types from main.rs that implement it, in the library so the
+// feed in feed.rs can use them.
+//
+// Summary has grown some methods with default implementations, so a type only has to provide
+// summarize() as before.  The feed uses the others to sort and filter items without knowing
+// their concrete types.
+
+// Define a trait which enforces method signatures.
+pub trait Summary {
+    fn summarize(&self) -> String;
+
+    // When the item was published, in seconds since the Unix epoch, if known.
+    fn published(&self) -> Option<u64> {
+        None
+    }
+
+    fn is_reply(&self) -> bool {
+        false
+    }
+
+    fn is_retweet(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewsArticle {
+    pub headline: String,
+    pub location: String,
+    pub author: String,
+    pub published: u64, // Seconds since the Unix epoch.
+}
+
+impl Summary for NewsArticle {
+    fn summarize(&self) -> String {
+        format!("{}, by {} ({})", self.headline, self.author, self.location)
+    }
+
+    fn published(&self) -> Option<u64> {
+        Some(self.published)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tweet {
+    pub username: String,
+    pub content: String,
+    pub reply: bool,
+    pub retweet: bool,
+    pub published: u64, // Seconds since the Unix epoch.
+}
+
+impl Summary for Tweet {
+    fn summarize(&self) -> String {
+        format!("{}: {}", self.username, self.content)
+    }
+
+    fn published(&self) -> Option<u64> {
+        Some(self.published)
+    }
+
+    fn is_reply(&self) -> bool {
+        self.reply
+    }
+
+    fn is_retweet(&self) -> bool {
+        self.retweet
+    }
+}
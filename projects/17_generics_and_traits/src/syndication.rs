@@ -0,0 +1,755 @@
+// Reading and writing NewsArticles and Tweets in the formats real feeds use.
+//
+//   RSS 2.0 and Atom (XML)  <->  Channel<NewsArticle>
+//   JSON Feed 1.1           <->  Channel<Tweet>
+//
+//   let channel = Channel::from_xml(&fs::read_to_string("news.rss")?)?;   // RSS or Atom
+//   fs::write("news.atom", channel.to_atom())?;
+//
+// How the fields map:
+//
+//   NewsArticle    RSS                               Atom
+//   headline       <title>                           <title>
+//   author         <dc:creator>, or <author>         <author><name>, or the feed's author
+//   location       <category domain="location">      <category scheme="location" term="..."/>
+//   published      <pubDate> (RFC 822)               <published>, or <updated> (RFC 3339)
+//
+//   Tweet          JSON Feed item
+//   username       authors[0].name (or author.name, from JSON Feed 1.0)
+//   content        content_text
+//   published      date_published (RFC 3339)
+//   reply/retweet  _tweet.reply/_tweet.retweet  (an extension, JSON Feed's keys starting with _
+//                  are for extensions; both default to false)
+//
+// A headline/content and a published time are required, author and location default to "".
+//
+// Malformed feeds give a SyndicationError naming the element at fault, as a path from the root
+// like rss/channel/item[2]/pubDate, with its line and column in the file where known.
+use crate::dates;
+use crate::summary::{NewsArticle, Tweet};
+use roxmltree::{Document, Node};
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+const JSON_FEED_VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyndicationError {
+    pub path: String, // The element at fault, rss/channel/item[2] or items[1].date_published.
+    pub position: Option<(u32, u32)>, // Line and column, both from 1.
+    pub message: String,
+}
+
+impl fmt::Display for SyndicationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(
+                f,
+                "{} (line {}, column {}): {}",
+                self.path, line, column, self.message
+            ),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+impl std::error::Error for SyndicationError {}
+
+// A feed's own details and its items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Channel<T> {
+    pub title: String,
+    pub link: String,
+    pub items: Vec<T>,
+}
+
+impl Channel<NewsArticle> {
+    // RSS or Atom, whichever the root element says it is.
+    pub fn from_xml(xml: &str) -> Result<Channel<NewsArticle>, SyndicationError> {
+        let doc = parse_xml(xml)?;
+        let root = doc.root_element();
+        if root.has_tag_name((ATOM_NS, "feed")) {
+            atom_channel(root)
+        } else {
+            rss_channel(root)
+        }
+    }
+
+    pub fn from_rss(xml: &str) -> Result<Channel<NewsArticle>, SyndicationError> {
+        rss_channel(parse_xml(xml)?.root_element())
+    }
+
+    pub fn from_atom(xml: &str) -> Result<Channel<NewsArticle>, SyndicationError> {
+        atom_channel(parse_xml(xml)?.root_element())
+    }
+
+    pub fn to_rss(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml += &format!(
+            "<rss version=\"2.0\" xmlns:dc=\"{}\">\n  <channel>\n",
+            DC_NS
+        );
+        xml += &text_element(4, "title", &self.title);
+        xml += &text_element(4, "link", &self.link);
+        xml += &text_element(4, "description", &self.title);
+        for article in &self.items {
+            xml += "    <item>\n";
+            xml += &text_element(6, "title", &article.headline);
+            if !article.author.is_empty() {
+                xml += &text_element(6, "dc:creator", &article.author);
+            }
+            if !article.location.is_empty() {
+                xml += &format!(
+                    "      <category domain=\"location\">{}</category>\n",
+                    escape(&article.location)
+                );
+            }
+            xml += &text_element(6, "pubDate", &dates::format_rfc822(article.published));
+            xml += "    </item>\n";
+        }
+        xml += "  </channel>\n</rss>\n";
+        xml
+    }
+
+    // Atom needs an id for the feed and each entry, and an updated time for the feed.  The ids
+    // are made from the link, and the feed was updated when its newest entry was published.
+    pub fn to_atom(&self) -> String {
+        let updated = self.items.iter().map(|a| a.published).max().unwrap_or(0);
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml += &format!("<feed xmlns=\"{}\">\n", ATOM_NS);
+        xml += &text_element(2, "title", &self.title);
+        xml += &format!("  <link href=\"{}\"/>\n", escape(&self.link));
+        xml += &text_element(2, "id", &self.link);
+        xml += &text_element(2, "updated", &dates::format_rfc3339(updated));
+        for (i, article) in self.items.iter().enumerate() {
+            let published = dates::format_rfc3339(article.published);
+            xml += "  <entry>\n";
+            xml += &text_element(4, "title", &article.headline);
+            xml += &text_element(4, "id", &format!("{}#{}", self.link, i + 1));
+            xml += &text_element(4, "published", &published);
+            xml += &text_element(4, "updated", &published);
+            if !article.author.is_empty() {
+                xml += "    <author>\n";
+                xml += &text_element(6, "name", &article.author);
+                xml += "    </author>\n";
+            }
+            if !article.location.is_empty() {
+                xml += &format!(
+                    "    <category scheme=\"location\" term=\"{}\"/>\n",
+                    escape(&article.location)
+                );
+            }
+            xml += "  </entry>\n";
+        }
+        xml += "</feed>\n";
+        xml
+    }
+}
+
+impl Channel<Tweet> {
+    pub fn from_json_feed(json: &str) -> Result<Channel<Tweet>, SyndicationError> {
+        let feed: Value = serde_json::from_str(json).map_err(|e| SyndicationError {
+            path: String::from("(document)"),
+            position: Some((e.line() as u32, e.column() as u32)),
+            message: e.to_string(),
+        })?;
+        let feed = feed
+            .as_object()
+            .ok_or_else(|| json_error("(document)", "expected an object"))?;
+
+        let version = json_str(feed, "", "version")?;
+        if !version.starts_with("https://jsonfeed.org/version/") {
+            return Err(json_error(
+                "version",
+                &format!("'{}' is not a JSON Feed version", version),
+            ));
+        }
+        let title = json_str(feed, "", "title")?.to_string();
+        let link = match feed.get("home_page_url") {
+            None | Some(Value::Null) => String::new(),
+            Some(_) => json_str(feed, "", "home_page_url")?.to_string(),
+        };
+        let items = match feed.get("items") {
+            Some(Value::Array(items)) => items,
+            Some(_) => return Err(json_error("items", "expected an array")),
+            None => return Err(json_error("(document)", "missing 'items'")),
+        };
+
+        let items = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| json_tweet(item, &format!("items[{}]", i)))
+            .collect::<Result<_, _>>()?;
+        Ok(Channel { title, link, items })
+    }
+
+    pub fn to_json_feed(&self) -> String {
+        let items: Vec<Value> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, tweet)| {
+                json!({
+                    "id": format!("{}#{}", self.link, i + 1),
+                    "content_text": tweet.content,
+                    "date_published": dates::format_rfc3339(tweet.published),
+                    "authors": [{ "name": tweet.username }],
+                    "_tweet": { "reply": tweet.reply, "retweet": tweet.retweet },
+                })
+            })
+            .collect();
+        let feed = json!({
+            "version": JSON_FEED_VERSION,
+            "title": self.title,
+            "home_page_url": self.link,
+            "items": items,
+        });
+        serde_json::to_string_pretty(&feed).expect("a Value always serializes")
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// XML
+////////////////////////////////////////////////////////////////////////////////
+
+fn parse_xml(xml: &str) -> Result<Document<'_>, SyndicationError> {
+    Document::parse(xml).map_err(|e| SyndicationError {
+        path: String::from("(document)"),
+        position: Some((e.pos().row, e.pos().col)),
+        message: e.to_string(),
+    })
+}
+
+// An error about 'node', e.g. rss/channel/item[2]/pubDate at line 14, column 7.
+fn xml_error(node: Node, message: &str) -> SyndicationError {
+    let pos = node.document().text_pos_at(node.range().start);
+    SyndicationError {
+        path: element_path(node),
+        position: Some((pos.row, pos.col)),
+        message: String::from(message),
+    }
+}
+
+// The names of 'node' and its ancestors, with [n] (from 1) after a name that more than one
+// element under the same parent has.
+fn element_path(node: Node) -> String {
+    let mut names: Vec<String> = node
+        .ancestors()
+        .filter(|n| n.is_element())
+        .map(|n| {
+            let name = n.tag_name().name();
+            let same_name = |s: &Node| s.is_element() && s.tag_name().name() == name;
+            let siblings = n
+                .parent()
+                .map_or(1, |p| p.children().filter(same_name).count());
+            if siblings > 1 {
+                let index = n.prev_siblings().filter(same_name).count();
+                format!("{}[{}]", name, index)
+            } else {
+                name.to_string()
+            }
+        })
+        .collect();
+    names.reverse();
+    names.join("/")
+}
+
+// The first child element called 'name', in no namespace for RSS and in the Atom namespace for
+// Atom (the namespace of 'parent').
+fn child<'a, 'input>(parent: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    let ns = parent.tag_name().namespace();
+    parent
+        .children()
+        .find(|n| n.is_element() && n.tag_name().name() == name && n.tag_name().namespace() == ns)
+}
+
+fn required_child<'a, 'input>(
+    parent: Node<'a, 'input>,
+    name: &str,
+) -> Result<Node<'a, 'input>, SyndicationError> {
+    child(parent, name).ok_or_else(|| xml_error(parent, &format!("missing <{}>", name)))
+}
+
+// All the text inside 'node', trimmed.  Text in CDATA sections is included.
+fn text(node: Node) -> String {
+    let text: String = node
+        .descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect();
+    text.trim().to_string()
+}
+
+fn required_text(parent: Node, name: &str) -> Result<String, SyndicationError> {
+    let node = required_child(parent, name)?;
+    let text = text(node);
+    if text.is_empty() {
+        return Err(xml_error(node, "is empty"));
+    }
+    Ok(text)
+}
+
+fn rss_channel(rss: Node) -> Result<Channel<NewsArticle>, SyndicationError> {
+    if !rss.has_tag_name("rss") || rss.attribute("version") != Some("2.0") {
+        return Err(xml_error(
+            rss,
+            "expected <rss version=\"2.0\"> or an Atom <feed>",
+        ));
+    }
+    let channel = required_child(rss, "channel")?;
+    Ok(Channel {
+        title: required_text(channel, "title")?,
+        link: child(channel, "link").map(text).unwrap_or_default(),
+        items: channel
+            .children()
+            .filter(|n| n.has_tag_name("item"))
+            .map(rss_article)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn rss_article(item: Node) -> Result<NewsArticle, SyndicationError> {
+    let creator = item.children().find(|n| n.has_tag_name((DC_NS, "creator")));
+    let author = match (creator, child(item, "author")) {
+        (Some(creator), _) => text(creator),
+        (None, Some(author)) => rss_author_name(&text(author)),
+        (None, None) => String::new(),
+    };
+    let location = item
+        .children()
+        .find(|n| n.has_tag_name("category") && n.attribute("domain") == Some("location"))
+        .map(text)
+        .unwrap_or_default();
+
+    let date = required_child(item, "pubDate")?;
+    let published = dates::parse_rfc822(&text(date)).map_err(|e| xml_error(date, &e))?;
+
+    Ok(NewsArticle {
+        headline: required_text(item, "title")?,
+        location,
+        author,
+        published,
+    })
+}
+
+// RSS's <author> is an email address, often with the name in brackets:
+// 'ann@example.com (Ann Smith)'.  The name if there is one, else the whole thing.
+fn rss_author_name(author: &str) -> String {
+    match (author.find('('), author.rfind(')')) {
+        (Some(open), Some(close)) if open < close => author[open + 1..close].trim().to_string(),
+        _ => author.to_string(),
+    }
+}
+
+fn atom_channel(feed: Node) -> Result<Channel<NewsArticle>, SyndicationError> {
+    if !feed.has_tag_name((ATOM_NS, "feed")) {
+        return Err(xml_error(feed, "expected an Atom <feed>"));
+    }
+    let feed_author = atom_author(feed);
+    let link = feed
+        .children()
+        .find(|n| {
+            n.has_tag_name((ATOM_NS, "link"))
+                && n.attribute("rel").is_none_or(|rel| rel == "alternate")
+        })
+        .and_then(|n| n.attribute("href"))
+        .unwrap_or_default()
+        .to_string();
+
+    Ok(Channel {
+        title: required_text(feed, "title")?,
+        link,
+        items: feed
+            .children()
+            .filter(|n| n.has_tag_name((ATOM_NS, "entry")))
+            .map(|entry| atom_article(entry, &feed_author))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn atom_author(parent: Node) -> Option<String> {
+    child(parent, "author")
+        .and_then(|author| child(author, "name"))
+        .map(text)
+}
+
+fn atom_article(
+    entry: Node,
+    feed_author: &Option<String>,
+) -> Result<NewsArticle, SyndicationError> {
+    let date = child(entry, "published")
+        .or_else(|| child(entry, "updated"))
+        .ok_or_else(|| xml_error(entry, "missing <published> or <updated>"))?;
+    let published = dates::parse_rfc3339(&text(date)).map_err(|e| xml_error(date, &e))?;
+
+    let location = entry
+        .children()
+        .find(|n| {
+            n.has_tag_name((ATOM_NS, "category")) && n.attribute("scheme") == Some("location")
+        })
+        .map(|category| {
+            category
+                .attribute("term")
+                .ok_or_else(|| xml_error(category, "missing the term attribute"))
+        })
+        .transpose()?
+        .unwrap_or_default()
+        .to_string();
+
+    Ok(NewsArticle {
+        headline: required_text(entry, "title")?,
+        location,
+        author: atom_author(entry)
+            .or_else(|| feed_author.clone())
+            .unwrap_or_default(),
+        published,
+    })
+}
+
+// <name>text</name> on its own line, 'indent' spaces in.
+fn text_element(indent: usize, name: &str, text: &str) -> String {
+    format!(
+        "{:indent$}<{name}>{}</{name}>\n",
+        "",
+        escape(text),
+        indent = indent,
+        name = name
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// JSON Feed
+////////////////////////////////////////////////////////////////////////////////
+
+fn json_error(path: &str, message: &str) -> SyndicationError {
+    SyndicationError {
+        path: path.to_string(),
+        position: None,
+        message: message.to_string(),
+    }
+}
+
+// 'parent' is the path to 'object', so the error for a missing title in the first item is
+// about items[0].title.
+fn json_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+fn json_str<'a>(
+    object: &'a Map<String, Value>,
+    parent: &str,
+    key: &str,
+) -> Result<&'a str, SyndicationError> {
+    match object.get(key) {
+        Some(Value::String(s)) => Ok(s),
+        Some(_) => Err(json_error(&json_path(parent, key), "expected a string")),
+        None => Err(json_error(
+            if parent.is_empty() {
+                "(document)"
+            } else {
+                parent
+            },
+            &format!("missing '{}'", key),
+        )),
+    }
+}
+
+fn json_bool(
+    object: &Map<String, Value>,
+    parent: &str,
+    key: &str,
+) -> Result<bool, SyndicationError> {
+    match object.get(key) {
+        Some(Value::Bool(b)) => Ok(*b),
+        None | Some(Value::Null) => Ok(false),
+        Some(_) => Err(json_error(
+            &json_path(parent, key),
+            "expected true or false",
+        )),
+    }
+}
+
+fn json_tweet(item: &Value, path: &str) -> Result<Tweet, SyndicationError> {
+    let item = item
+        .as_object()
+        .ok_or_else(|| json_error(path, "expected an object"))?;
+
+    let username = match (item.get("authors"), item.get("author")) {
+        (Some(Value::Array(authors)), _) if !authors.is_empty() => {
+            json_author_name(&authors[0], &format!("{}.authors[0]", path))?
+        }
+        (Some(Value::Array(_)), _) | (None, None) => String::new(),
+        (Some(_), _) => return Err(json_error(&json_path(path, "authors"), "expected an array")),
+        (None, Some(author)) => json_author_name(author, &json_path(path, "author"))?,
+    };
+
+    let date = json_str(item, path, "date_published")?;
+    let published = dates::parse_rfc3339(date)
+        .map_err(|e| json_error(&json_path(path, "date_published"), &e))?;
+
+    let (reply, retweet) = match item.get("_tweet") {
+        None | Some(Value::Null) => (false, false),
+        Some(Value::Object(extension)) => {
+            let path = json_path(path, "_tweet");
+            (
+                json_bool(extension, &path, "reply")?,
+                json_bool(extension, &path, "retweet")?,
+            )
+        }
+        Some(_) => return Err(json_error(&json_path(path, "_tweet"), "expected an object")),
+    };
+
+    Ok(Tweet {
+        username,
+        content: json_str(item, path, "content_text")?.to_string(),
+        reply,
+        retweet,
+        published,
+    })
+}
+
+fn json_author_name(author: &Value, path: &str) -> Result<String, SyndicationError> {
+    let author = author
+        .as_object()
+        .ok_or_else(|| json_error(path, "expected an object"))?;
+    match author.get("name") {
+        None => Ok(String::new()),
+        Some(_) => Ok(json_str(author, path, "name")?.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEWS_RSS: &str = include_str!("../fixtures/news.rss");
+    const NEWS_ATOM: &str = include_str!("../fixtures/news.atom");
+    const TWEETS_JSON: &str = include_str!("../fixtures/tweets.json");
+
+    fn article(headline: &str, author: &str, location: &str, published: u64) -> NewsArticle {
+        NewsArticle {
+            headline: String::from(headline),
+            location: String::from(location),
+            author: String::from(author),
+            published,
+        }
+    }
+
+    // What is in both news.rss and news.atom.
+    fn news() -> Channel<NewsArticle> {
+        Channel {
+            title: String::from("Weather News"),
+            link: String::from("https://news.example.com/"),
+            items: vec![
+                article(
+                    "Rain expected <again> & again",
+                    "Ann Smith",
+                    "Leeds",
+                    1_714_564_800,
+                ),
+                article("Sun at last", "Bob Jones", "", 1_714_651_200),
+                article("Fog on the Tyne", "", "Newcastle", 1_714_737_600),
+            ],
+        }
+    }
+
+    fn tweet(username: &str, content: &str, published: u64) -> Tweet {
+        Tweet {
+            username: String::from(username),
+            content: String::from(content),
+            reply: false,
+            retweet: false,
+            published,
+        }
+    }
+
+    fn error_for(result: Result<impl fmt::Debug, SyndicationError>) -> SyndicationError {
+        result.expect_err("the feed should be rejected")
+    }
+
+    #[test]
+    fn parses_rss() {
+        assert_eq!(Channel::from_rss(NEWS_RSS).unwrap(), news());
+        assert_eq!(Channel::from_xml(NEWS_RSS).unwrap(), news());
+    }
+
+    #[test]
+    fn parses_atom() {
+        assert_eq!(Channel::from_atom(NEWS_ATOM).unwrap(), news());
+        assert_eq!(Channel::from_xml(NEWS_ATOM).unwrap(), news());
+    }
+
+    #[test]
+    fn parses_json_feed() {
+        let channel = Channel::from_json_feed(TWEETS_JSON).unwrap();
+        assert_eq!(channel.title, "ebooks");
+        assert_eq!(channel.link, "https://social.example.com/ebooks");
+        assert_eq!(
+            channel.items,
+            vec![
+                tweet("ebooks", "blah blah blah", 1_714_564_800),
+                Tweet {
+                    reply: true,
+                    ..tweet("fans", "@ebooks blah?", 1_714_568_400)
+                },
+                Tweet {
+                    retweet: true,
+                    ..tweet("", "RT blah blah blah", 1_714_572_000)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn atom_entries_fall_back_to_the_feed_author() {
+        let xml = "<feed xmlns=\"http://www.w3.org/2005/Atom\">\
+                   <title>t</title><author><name>Ann</name></author>\
+                   <entry><title>a</title><updated>2024-05-01T12:00:00Z</updated></entry>\
+                   <entry><title>b</title><updated>2024-05-01T12:00:00Z</updated>\
+                   <author><name>Bob</name></author></entry></feed>";
+        let channel = Channel::from_atom(xml).unwrap();
+        let authors: Vec<&str> = channel.items.iter().map(|a| a.author.as_str()).collect();
+        assert_eq!(authors, vec!["Ann", "Bob"]);
+    }
+
+    #[test]
+    fn rss_and_atom_round_trip() {
+        let news = news();
+        assert_eq!(Channel::from_rss(&news.to_rss()).unwrap(), news);
+        assert_eq!(Channel::from_atom(&news.to_atom()).unwrap(), news);
+        // From one to the other and back.
+        let atom = Channel::from_rss(NEWS_RSS).unwrap().to_atom();
+        assert_eq!(Channel::from_atom(&atom).unwrap().to_rss(), news.to_rss());
+    }
+
+    #[test]
+    fn json_feed_round_trip() {
+        let channel = Channel::from_json_feed(TWEETS_JSON).unwrap();
+        let json = channel.to_json_feed();
+        assert!(json.contains("\"version\": \"https://jsonfeed.org/version/1.1\""));
+        assert_eq!(Channel::from_json_feed(&json).unwrap(), channel);
+    }
+
+    #[test]
+    fn bad_rss_points_at_the_element() {
+        let err = error_for(Channel::from_rss(include_str!("../fixtures/bad_date.rss")));
+        assert_eq!(err.path, "rss/channel/item[2]/pubDate");
+        assert_eq!(err.position, Some((15, 7)));
+        assert_eq!(
+            err.message,
+            "'Thu, 32 May 2024 12:00:00 GMT': day 32 out of range"
+        );
+        assert_eq!(
+            err.to_string(),
+            "rss/channel/item[2]/pubDate (line 15, column 7): \
+             'Thu, 32 May 2024 12:00:00 GMT': day 32 out of range"
+        );
+
+        let err = error_for(Channel::from_rss(include_str!(
+            "../fixtures/missing_title.rss"
+        )));
+        assert_eq!(err.path, "rss/channel/item");
+        assert_eq!(err.message, "missing <title>");
+        assert_eq!(err.position, Some((6, 5)));
+
+        let err = error_for(Channel::from_rss("<rss version=\"0.91\"><channel/></rss>"));
+        assert_eq!(err.path, "rss");
+        let err = error_for(Channel::from_rss("<rss version=\"2.0\"></rss>"));
+        assert_eq!(
+            (err.path.as_str(), err.message.as_str()),
+            ("rss", "missing <channel>")
+        );
+    }
+
+    #[test]
+    fn bad_atom_points_at_the_element() {
+        let err = error_for(Channel::from_atom(include_str!(
+            "../fixtures/bad_entry.atom"
+        )));
+        assert_eq!(err.path, "feed/entry[2]/category[2]");
+        assert_eq!(err.message, "missing the term attribute");
+        assert_eq!(err.position, Some((16, 5)));
+
+        let err = error_for(Channel::from_atom(
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\"><title>t</title><entry>\
+             <title>x</title></entry></feed>",
+        ));
+        assert_eq!(err.path, "feed/entry");
+        assert_eq!(err.message, "missing <published> or <updated>");
+
+        // An RSS document isn't an Atom feed.
+        let err = error_for(Channel::from_atom(NEWS_RSS));
+        assert_eq!(err.path, "rss");
+    }
+
+    #[test]
+    fn malformed_xml() {
+        let err = error_for(Channel::from_xml(
+            "<rss version=\"2.0\">\n  <channel>\n</rss>",
+        ));
+        assert_eq!(err.path, "(document)");
+        assert_eq!(err.position, Some((3, 1)));
+    }
+
+    #[test]
+    fn bad_json_feed_points_at_the_element() {
+        let err = error_for(Channel::from_json_feed(include_str!(
+            "../fixtures/bad_item.json"
+        )));
+        assert_eq!(err.path, "items[1]._tweet.retweet");
+        assert_eq!(err.message, "expected true or false");
+        assert_eq!(err.position, None);
+
+        let feed = |items: &str| {
+            format!(
+                r#"{{"version": "https://jsonfeed.org/version/1.1", "title": "t", "items": {}}}"#,
+                items
+            )
+        };
+        let err = error_for(Channel::from_json_feed(&feed("[{}]")));
+        assert_eq!(err.to_string(), "items[0]: missing 'date_published'");
+        let err = error_for(Channel::from_json_feed(&feed(
+            r#"[{"date_published": "yesterday", "content_text": "hi"}]"#,
+        )));
+        assert_eq!(err.path, "items[0].date_published");
+        let err = error_for(Channel::from_json_feed(&feed(
+            r#"[{"date_published": "2024-05-01T12:00:00Z", "content_text": 1}]"#,
+        )));
+        assert_eq!(err.to_string(), "items[0].content_text: expected a string");
+        let err = error_for(Channel::from_json_feed(&feed(r#"{}"#)));
+        assert_eq!(err.path, "items");
+
+        let err = error_for(Channel::from_json_feed(r#"{"version": "1", "title": "t"}"#));
+        assert_eq!(err.path, "version");
+        let err = error_for(Channel::from_json_feed("{\n  \"version\": \n}"));
+        assert_eq!(err.position, Some((3, 1)));
+    }
+
+    #[test]
+    fn rss_author_names() {
+        assert_eq!(rss_author_name("ann@example.com (Ann Smith)"), "Ann Smith");
+        assert_eq!(rss_author_name("ann@example.com"), "ann@example.com");
+        assert_eq!(rss_author_name(") odd ("), ") odd (");
+    }
+}
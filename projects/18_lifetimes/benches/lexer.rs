@@ -0,0 +1,52 @@
+// The lexer from src/lexer.rs, with tokens that borrow from the input, against the same tokens
+// each copied into a String.
+//
+// cargo bench --bench lexer
+//
+// The input is LINES lines of a made up config language, with every kind of token and a
+// comment, generated the same way every run.  The owning version is the same lexer with
+// Token::into_owned on each token, which is what a lexer that returns Strings has to do: one
+// allocation and copy per token.  Both collect their tokens into a Vec, as a parser would.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use lifetimes::lexer::{tokenize, OwnedToken, Token};
+
+const LINES: usize = 2_000;
+
+fn input() -> String {
+    (0..LINES)
+        .map(|n| {
+            format!(
+                "server_{} = {{ host: \"10.0.{}.{}\", port: {}, weight: {}.5e-1 }}  # line {}\n",
+                n,
+                n / 256,
+                n % 256,
+                8000 + n,
+                n % 10,
+                n
+            )
+        })
+        .collect()
+}
+
+fn borrowed(input: &str) -> Vec<Token<'_>> {
+    tokenize(input).map(|t| t.unwrap()).collect()
+}
+
+fn owned(input: &str) -> Vec<OwnedToken> {
+    tokenize(input).map(|t| t.unwrap().into_owned()).collect()
+}
+
+fn lex(c: &mut Criterion) {
+    let input = input();
+    assert_eq!(borrowed(&input).len(), owned(&input).len());
+
+    c.bench_function("lex borrowed tokens", |b| {
+        b.iter(|| black_box(borrowed(black_box(&input))))
+    });
+    c.bench_function("lex owned tokens", |b| {
+        b.iter(|| black_box(owned(black_box(&input))))
+    });
+}
+
+criterion_group!(benches, lex);
+criterion_main!(benches);
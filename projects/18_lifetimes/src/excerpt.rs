@@ -0,0 +1,407 @@
+// ImportantExcerpt from main.rs, found properly.  main.rs takes everything up to the first '.',
+// which goes wrong on "Mr. Smith", "3.14" or a sentence that ends in '?'.
+//
+// Nothing here copies the text.  Each ImportantExcerpt holds a slice of the document and where
+// that slice starts in it, in bytes, so &document[e.start..e.end()] == e.part.  That's why the
+// excerpts can't outlive the document, the same as in main.rs:
+//
+//   let document = String::from("Call me Ishmael.  Some years ago...");
+//   let first = sentences(&document).next().unwrap();
+//   assert_eq!(first.part, "Call me Ishmael.");
+//   drop(document);           // Won't compile while 'first' is still used after this.
+//
+// Paragraphs are separated by blank lines, and a sentence never carries on into the next
+// paragraph, so a heading with no full stop is a sentence of its own.
+//
+// A sentence ends at a run of terminators, '.', '!', '?', '…' and the like in other scripts,
+// along with any closing quotes or brackets straight after them: He said "Stop!" is one
+// sentence.  But only if what follows is whitespace (so not "3.14" or "e.g."), and the next word
+// doesn't start with a lower case letter ("Stop!" she said), and a '.' doesn't follow one of the
+// abbreviations below or a single capital letter, which is taken to be an initial (J. R. R.
+// Tolkien).  The full-width terminators used with Chinese and Japanese, '。', '！' and '？',
+// need no whitespace after them, as those languages don't put spaces between sentences.
+
+// A '.' after one of these is part of the abbreviation.  They're normally followed by a name,
+// or are in the middle of a sentence, so they're taken to never end one.  Abbreviations like
+// "etc." that often do end a sentence are left to the lower case rule.  Case matters, so "no."
+// can end a sentence but "No. 5" can't.
+const ABBREVIATIONS: &[&str] = &[
+    "Mr", "Mrs", "Ms", "Dr", "Prof", "Rev", "Hon", "St", "Mt", "Jr", "Sr", "Capt", "Gen", "Lt",
+    "Col", "Sgt", "No", "Nos", "Fig", "Vol", "vs", "e.g", "i.e", "cf", "viz",
+];
+
+const TERMINATORS: &[char] = &[
+    '.', '!', '?', '…', '‼', '⁇', '⁈', '⁉', '؟', '।', '॥', '。', '！', '？', '｡',
+];
+
+const FULL_WIDTH_TERMINATORS: &[char] = &['。', '！', '？', '｡'];
+
+const CLOSERS: &[char] = &[
+    '"', '\'', '”', '’', '»', '›', ')', ']', '}', '）', '」', '』',
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportantExcerpt<'a> {
+    pub part: &'a str,
+    // The byte offset of 'part' in the document it came from.
+    pub start: usize,
+}
+
+impl<'a> ImportantExcerpt<'a> {
+    // The byte offset just after 'part' in the document.
+    pub fn end(&self) -> usize {
+        self.start + self.part.len()
+    }
+
+    // Runs of letters and digits, with apostrophes inside words kept ("don't").
+    pub fn words(&self) -> impl Iterator<Item = &'a str> {
+        self.part
+            .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '’')
+            .map(|w| w.trim_matches(|c| c == '\'' || c == '’'))
+            .filter(|w| !w.is_empty())
+    }
+
+    // The fraction of the words that are one of the keywords, ignoring case.  0 with no words.
+    pub fn keyword_density(&self, keywords: &[&str]) -> f64 {
+        let keywords: Vec<String> = keywords.iter().map(|k| k.to_lowercase()).collect();
+        let (mut words, mut matches) = (0, 0);
+        for word in self.words() {
+            words += 1;
+            if keywords.contains(&word.to_lowercase()) {
+                matches += 1;
+            }
+        }
+        if words == 0 {
+            0.0
+        } else {
+            matches as f64 / words as f64
+        }
+    }
+}
+
+// The paragraphs of 'text', with the whitespace around each trimmed off.
+pub fn paragraphs(text: &str) -> Paragraphs<'_> {
+    Paragraphs { text, pos: 0 }
+}
+
+pub struct Paragraphs<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for Paragraphs<'a> {
+    type Item = ImportantExcerpt<'a>;
+
+    fn next(&mut self) -> Option<ImportantExcerpt<'a>> {
+        let mut first_line = None;
+        let mut end = self.pos;
+        for line in self.text[self.pos..].split_inclusive('\n') {
+            let line_start = self.pos;
+            self.pos += line.len();
+            if line.trim().is_empty() {
+                if first_line.is_some() {
+                    break;
+                }
+            } else {
+                first_line.get_or_insert(line_start);
+                end = self.pos;
+            }
+        }
+        first_line.map(|start| trimmed(self.text, start, end))
+    }
+}
+
+// The sentences of 'text', in order, with the whitespace between them left out.
+pub fn sentences(text: &str) -> impl Iterator<Item = ImportantExcerpt<'_>> {
+    paragraphs(text).flat_map(move |paragraph| Sentences {
+        text,
+        pos: paragraph.start,
+        end: paragraph.end(),
+    })
+}
+
+// The sentences in text[pos..end], one paragraph.
+struct Sentences<'a> {
+    text: &'a str,
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Iterator for Sentences<'a> {
+    type Item = ImportantExcerpt<'a>;
+
+    fn next(&mut self) -> Option<ImportantExcerpt<'a>> {
+        let paragraph = &self.text[..self.end];
+        let start = self.end - paragraph[self.pos..].trim_start().len();
+        if start == self.end {
+            return None;
+        }
+
+        let mut chars = paragraph[start..]
+            .char_indices()
+            .map(|(i, c)| (start + i, c))
+            .peekable();
+        while let Some((i, c)) = chars.next() {
+            if !TERMINATORS.contains(&c) {
+                continue;
+            }
+
+            // The whole run of terminators, and any closing quotes after it.
+            let mut full_width = FULL_WIDTH_TERMINATORS.contains(&c);
+            let mut single_stop = c == '.';
+            while let Some(&(_, next)) = chars.peek() {
+                if TERMINATORS.contains(&next) {
+                    full_width |= FULL_WIDTH_TERMINATORS.contains(&next);
+                    single_stop = false;
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            while let Some(&(_, next)) = chars.peek() {
+                if CLOSERS.contains(&next) {
+                    single_stop = false;
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let sentence_end = chars.peek().map_or(self.end, |&(j, _)| j);
+
+            if ends_sentence(paragraph, start, i, sentence_end, full_width, single_stop) {
+                self.pos = sentence_end;
+                return Some(ImportantExcerpt {
+                    part: &self.text[start..sentence_end],
+                    start,
+                });
+            }
+        }
+
+        // No terminator at the end of the paragraph.
+        self.pos = self.end;
+        Some(ImportantExcerpt {
+            part: &self.text[start..self.end],
+            start,
+        })
+    }
+}
+
+// Whether the terminators starting at 'terminator' and ending at 'end' end the sentence that
+// starts at 'start'.
+fn ends_sentence(
+    paragraph: &str,
+    start: usize,
+    terminator: usize,
+    end: usize,
+    full_width: bool,
+    single_stop: bool,
+) -> bool {
+    let after = &paragraph[end..];
+    if after.is_empty() || full_width {
+        return true;
+    }
+    if !after.starts_with(char::is_whitespace) {
+        return false;
+    }
+    if after.trim_start().starts_with(char::is_lowercase) {
+        return false;
+    }
+    if single_stop {
+        let word = paragraph[start..terminator]
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or("")
+            .trim_start_matches(|c: char| !c.is_alphanumeric());
+        let mut letters = word.chars();
+        let initial =
+            matches!((letters.next(), letters.next()), (Some(c), None) if c.is_uppercase());
+        if initial || ABBREVIATIONS.contains(&word) {
+            return false;
+        }
+    }
+    true
+}
+
+// text[start..end] with the whitespace at either end left out.
+fn trimmed(text: &str, start: usize, end: usize) -> ImportantExcerpt<'_> {
+    let part = &text[start..end];
+    let leading = part.len() - part.trim_start().len();
+    ImportantExcerpt {
+        part: part.trim(),
+        start: start + leading,
+    }
+}
+
+// How longest ranks the excerpts.
+#[derive(Debug, Clone, Copy)]
+pub enum Rank<'k> {
+    // The most characters.  Characters, not bytes, so "naïve" is as long as "naive".
+    Length,
+    // The highest keyword_density.
+    KeywordDensity(&'k [&'k str]),
+}
+
+// The longest function from main.rs for any number of excerpts, and more than one way of
+// measuring them.  The first wins a tie, and None if there are no excerpts.
+//
+// The keywords are only needed while ranking, so like 'y' in another_example their lifetime
+// has nothing to do with the result's.  The excerpt returned borrows from the document only.
+pub fn longest<'a, I>(excerpts: I, rank: Rank) -> Option<ImportantExcerpt<'a>>
+where
+    I: IntoIterator<Item = ImportantExcerpt<'a>>,
+{
+    let score = |e: &ImportantExcerpt| match rank {
+        Rank::Length => e.part.chars().count() as f64,
+        Rank::KeywordDensity(keywords) => e.keyword_density(keywords),
+    };
+
+    let mut best: Option<(f64, ImportantExcerpt<'a>)> = None;
+    for excerpt in excerpts {
+        let s = score(&excerpt);
+        match best {
+            Some((best_score, _)) if s <= best_score => {}
+            _ => best = Some((s, excerpt)),
+        }
+    }
+    best.map(|(_, excerpt)| excerpt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(text: &str) -> Vec<&str> {
+        sentences(text).map(|e| e.part).collect()
+    }
+
+    #[test]
+    fn offsets_index_the_document() {
+        let text = "  First one.  Second one?\n\n\tThird, in a new paragraph!  ";
+        let found: Vec<_> = sentences(text).collect();
+        assert_eq!(found.len(), 3);
+        for e in &found {
+            assert_eq!(&text[e.start..e.end()], e.part);
+        }
+        assert_eq!(
+            found[0],
+            ImportantExcerpt {
+                part: "First one.",
+                start: 2
+            }
+        );
+        assert_eq!(found[2].part, "Third, in a new paragraph!");
+    }
+
+    #[test]
+    fn splits_on_any_terminator() {
+        assert_eq!(
+            parts("Really?! Yes. Wait... No! The end"),
+            vec!["Really?!", "Yes.", "Wait...", "No!", "The end"]
+        );
+        assert_eq!(parts("Hmm… maybe. Fine…"), vec!["Hmm… maybe.", "Fine…"]);
+        assert_eq!(parts(""), Vec::<&str>::new());
+        assert_eq!(parts(" \n\n "), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn abbreviations_and_initials() {
+        assert_eq!(
+            parts("Mr. Smith met Dr. Jones at No. 5 St. James Street. They spoke."),
+            vec![
+                "Mr. Smith met Dr. Jones at No. 5 St. James Street.",
+                "They spoke."
+            ]
+        );
+        assert_eq!(
+            parts("J. R. R. Tolkien wrote it. It is long."),
+            vec!["J. R. R. Tolkien wrote it.", "It is long."]
+        );
+        assert_eq!(
+            parts("Bring fruit, e.g. Apples. Or pears, etc. Then go."),
+            vec!["Bring fruit, e.g. Apples.", "Or pears, etc.", "Then go."]
+        );
+        assert_eq!(
+            parts("Pi is 3.14 or so. He said no. Ok."),
+            vec!["Pi is 3.14 or so.", "He said no.", "Ok."]
+        );
+    }
+
+    #[test]
+    fn quotes_stay_with_their_sentence() {
+        assert_eq!(
+            parts("He said \"Stop!\" Then he left. 'Why?' she asked."),
+            vec!["He said \"Stop!\"", "Then he left.", "'Why?' she asked."]
+        );
+        assert_eq!(
+            parts("“Stop!” she said. (See above.) Done."),
+            vec!["“Stop!” she said.", "(See above.)", "Done."]
+        );
+    }
+
+    #[test]
+    fn unicode_punctuation() {
+        assert_eq!(
+            parts("今日は晴れです。明日は雨？はい！"),
+            vec!["今日は晴れです。", "明日は雨？", "はい！"]
+        );
+        assert_eq!(parts("«Oui.» Non‼ Bien."), vec!["«Oui.»", "Non‼", "Bien."]);
+        assert_eq!(parts("هل أنت بخير؟ نعم."), vec!["هل أنت بخير؟", "نعم."]);
+
+        // Offsets are in bytes, and still on character boundaries.
+        let text = "Été. Ça va.";
+        let second = sentences(text).nth(1).unwrap();
+        assert_eq!(second.start, 7);
+        assert_eq!(&text[second.start..second.end()], "Ça va.");
+    }
+
+    #[test]
+    fn paragraphs_split_on_blank_lines() {
+        let text = "Title\n\nFirst line\nsecond line.\r\n  \r\nLast.\n";
+        let found: Vec<_> = paragraphs(text).map(|e| e.part).collect();
+        assert_eq!(found, vec!["Title", "First line\nsecond line.", "Last."]);
+
+        // A heading with no full stop is still a sentence of its own.
+        assert_eq!(
+            parts(text),
+            vec!["Title", "First line\nsecond line.", "Last."]
+        );
+    }
+
+    #[test]
+    fn longest_by_length() {
+        let text = "Short. A bit longer. Longest of them all. Also seventeen.";
+        assert_eq!(
+            longest(sentences(text), Rank::Length).unwrap().part,
+            "Longest of them all."
+        );
+        assert_eq!(longest(Vec::new(), Rank::Length), None);
+
+        // Characters, not bytes, and the first wins a tie.
+        let text = "Naïve. Naive.";
+        assert_eq!(longest(sentences(text), Rank::Length).unwrap().start, 0);
+    }
+
+    #[test]
+    fn longest_by_keyword_density() {
+        let text = "The cat sat on the mat. Cats and dogs. A CAT, a cat! Nothing here.";
+        let keywords = ["cat", "dog"];
+        let best = longest(sentences(text), Rank::KeywordDensity(&keywords)).unwrap();
+        assert_eq!(best.part, "A CAT, a cat!");
+        assert_eq!(best.keyword_density(&keywords), 0.5);
+
+        let none = ImportantExcerpt {
+            part: "...",
+            start: 0,
+        };
+        assert_eq!(none.keyword_density(&keywords), 0.0);
+        assert_eq!(
+            ImportantExcerpt {
+                part: "Don't 'quote' me",
+                start: 0
+            }
+            .words()
+            .collect::<Vec<_>>(),
+            vec!["Don't", "quote", "me"]
+        );
+    }
+}
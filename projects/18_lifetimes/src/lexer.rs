@@ -0,0 +1,531 @@
+// A lexer whose tokens borrow from the input, like longest in main.rs returns one of its inputs
+// rather than a copy.  Token<'a> holds a &'a str slice of the input, so lexing allocates nothing
+// and the tokens can't outlive the input:
+//
+//   let input = String::from("port = 8080  # the default");
+//   let tokens: Vec<Token> = tokenize(&input).collect::<Result<_, _>>().unwrap();
+//   assert_eq!(tokens[0].text, "port");
+//   assert_eq!(tokens[2].number_value(), Some(8080.0));
+//
+// Where tokens are needed after the input has gone, into_owned copies the text into an
+// OwnedToken.  benches/lexer.rs compares the two: cargo bench --bench lexer.
+//
+// It's meant as the front end for small config or query languages, so it knows nothing about
+// keywords or grammar:
+// -    Identifiers: a letter or '_' and then letters, digits and '_'.  Letters from any script.
+// -    Numbers: 42, 3.14, 1e-9.  "1." is the number 1 and then a '.', so 1..5 works as a range.
+// -    Strings: in "double" or 'single' quotes, with the escapes \n \r \t \0 \\ \" \' and \u{..}.
+//      A string can't run over the end of a line.  The token's text includes the quotes and the
+//      escapes as written, string_value gives what they stand for.
+// -    Punctuation: one of the characters in PUNCTUATION, or one of the pairs in
+//      TWO_CHAR_PUNCTUATION, which are one token, so "<=" is not '<' and then '='.
+// Whitespace and comments, from '#' or "//" to the end of the line, are skipped.
+//
+// Errors don't stop the lexer.  It returns the error for the text at fault, skips past it and
+// carries on, so every error in the input can be reported at once:
+// -    An unexpected character is skipped on its own.
+// -    A number run into letters, 12ab, is skipped up to the end of the letters.
+// -    A string with a bad escape is skipped to its closing quote, and an unterminated one to
+//      the end of the line.
+use std::borrow::Cow;
+use std::fmt;
+
+const PUNCTUATION: &str = "+-*/%=<>!&|^~.,;:()[]{}@$?";
+
+const TWO_CHAR_PUNCTUATION: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "->", "=>", "::", "..", "+=", "-=", "*=", "/=",
+];
+
+// Where a token or error is in the input.  start and end are byte offsets, so
+// &input[span.start..span.end] is its text.  line and column are where it starts, both from 1,
+// with the column counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Ident,
+    Number,
+    Str,
+    Punct,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+impl<'a> Token<'a> {
+    // The value of a Number token.
+    pub fn number_value(&self) -> Option<f64> {
+        match self.kind {
+            TokenKind::Number => self.text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    // What a Str token stands for, without its quotes and with its escapes replaced.  This only
+    // needs to allocate if there are escapes, otherwise it borrows from the input too.
+    pub fn string_value(&self) -> Option<Cow<'a, str>> {
+        if self.kind != TokenKind::Str {
+            return None;
+        }
+        let inner = &self.text[1..self.text.len() - 1];
+        if !inner.contains('\\') {
+            return Some(Cow::Borrowed(inner));
+        }
+
+        let mut value = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+            // The lexer has checked the escapes, so these can't fail.
+            match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some('0') => value.push('\0'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().skip(1).take_while(|&c| c != '}').collect();
+                    value.extend(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32));
+                }
+                Some(c) => value.push(c),
+                None => {}
+            }
+        }
+        Some(Cow::Owned(value))
+    }
+
+    pub fn into_owned(self) -> OwnedToken {
+        OwnedToken {
+            kind: self.kind,
+            text: self.text.to_string(),
+            span: self.span,
+        }
+    }
+}
+
+// A Token that owns its text, so it doesn't borrow from the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedToken {
+    pub kind: TokenKind,
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexErrorKind {
+    UnexpectedChar(char),
+    InvalidNumber,
+    UnterminatedString,
+    // The character after the '\'.
+    InvalidEscape(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span, // The text at fault, for an InvalidEscape just the escape.
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.span.line, self.span.column)?;
+        match self.kind {
+            LexErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            LexErrorKind::InvalidNumber => write!(f, "invalid number"),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            LexErrorKind::InvalidEscape(c) => write!(f, "invalid escape \\{}", c),
+        }
+    }
+}
+
+impl std::error::Error for LexError {}
+
+pub fn tokenize(input: &str) -> Lexer<'_> {
+    Lexer::new(input)
+}
+
+// All the tokens and all the errors, for reporting every error at once.
+pub fn lex(input: &str) -> (Vec<Token<'_>>, Vec<LexError>) {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for result in tokenize(input) {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(error) => errors.push(error),
+        }
+    }
+    (tokens, errors)
+}
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Lexer<'a> {
+        Lexer {
+            input,
+            pos: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    // The character 'n' characters on from the current one.
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.input[self.pos..].chars().nth(n)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_nth(0)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn bump_while<F: Fn(char) -> bool>(&mut self, f: F) {
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            self.bump_while(char::is_whitespace);
+            if self.input[self.pos..].starts_with('#') || self.input[self.pos..].starts_with("//") {
+                self.bump_while(|c| c != '\n');
+            } else {
+                return;
+            }
+        }
+    }
+
+    // A Span from 'start' to the current position.
+    fn span_from(&self, start: Span) -> Span {
+        Span {
+            end: self.pos,
+            ..start
+        }
+    }
+
+    fn number(&mut self) -> Result<TokenKind, LexErrorKind> {
+        let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+
+        self.bump_while(|c| c.is_ascii_digit());
+        if self.peek() == Some('.') && is_digit(self.peek_nth(1)) {
+            self.bump();
+            self.bump_while(|c| c.is_ascii_digit());
+        }
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            let sign = matches!(self.peek_nth(1), Some('+') | Some('-'));
+            if is_digit(self.peek_nth(if sign { 2 } else { 1 })) {
+                self.bump();
+                if sign {
+                    self.bump();
+                }
+                self.bump_while(|c| c.is_ascii_digit());
+            }
+        }
+
+        if self.peek().is_some_and(is_ident_char) {
+            self.bump_while(|c| is_ident_char(c) || c == '.');
+            return Err(LexErrorKind::InvalidNumber);
+        }
+        Ok(TokenKind::Number)
+    }
+
+    // After the opening quote.
+    fn string(&mut self, quote: char) -> Result<TokenKind, (LexErrorKind, Option<Span>)> {
+        let mut bad_escape = None;
+        loop {
+            match self.peek() {
+                None | Some('\n') => return Err((LexErrorKind::UnterminatedString, None)),
+                Some(c) if c == quote => {
+                    self.bump();
+                    break;
+                }
+                Some('\\') => {
+                    let escape = self.here();
+                    self.bump();
+                    let valid = match self.peek() {
+                        Some('n') | Some('r') | Some('t') | Some('0') | Some('\\') | Some('"')
+                        | Some('\'') => {
+                            self.bump();
+                            true
+                        }
+                        Some('u') => {
+                            self.bump();
+                            self.unicode_escape()
+                        }
+                        // The end of the line or input, left for the next time round.
+                        None | Some('\n') => continue,
+                        Some(_) => {
+                            self.bump();
+                            false
+                        }
+                    };
+                    if !valid && bad_escape.is_none() {
+                        let c = self.input[escape.start + 1..]
+                            .chars()
+                            .next()
+                            .unwrap_or('\\');
+                        bad_escape = Some((c, self.span_from(escape)));
+                    }
+                }
+                Some(_) => {
+                    self.bump();
+                }
+            }
+        }
+        match bad_escape {
+            Some((c, span)) => Err((LexErrorKind::InvalidEscape(c), Some(span))),
+            None => Ok(TokenKind::Str),
+        }
+    }
+
+    // After the 'u' of \u{...}.  Between 1 and 6 hex digits that make a char.
+    fn unicode_escape(&mut self) -> bool {
+        if self.peek() != Some('{') {
+            return false;
+        }
+        self.bump();
+        let start = self.pos;
+        self.bump_while(|c| c.is_ascii_hexdigit());
+        let hex = &self.input[start..self.pos];
+        if self.peek() != Some('}') {
+            return false;
+        }
+        self.bump();
+        hex.len() <= 6
+            && u32::from_str_radix(hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .is_some()
+    }
+
+    // A zero length Span at the current position.
+    fn here(&self) -> Span {
+        Span {
+            start: self.pos,
+            end: self.pos,
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Result<Token<'a>, LexError>> {
+        self.skip_whitespace_and_comments();
+        let start = self.here();
+        let c = self.bump()?;
+
+        let kind = if is_ident_start(c) {
+            self.bump_while(is_ident_char);
+            Ok(TokenKind::Ident)
+        } else if c.is_ascii_digit() {
+            self.number().map_err(|kind| (kind, None))
+        } else if c == '"' || c == '\'' {
+            self.string(c)
+        } else if TWO_CHAR_PUNCTUATION
+            .iter()
+            .any(|pair| self.input[start.start..].starts_with(pair))
+        {
+            self.bump();
+            Ok(TokenKind::Punct)
+        } else if PUNCTUATION.contains(c) {
+            Ok(TokenKind::Punct)
+        } else {
+            Err((LexErrorKind::UnexpectedChar(c), None))
+        };
+
+        let span = self.span_from(start);
+        Some(match kind {
+            Ok(kind) => Ok(Token {
+                kind,
+                text: &self.input[span.start..span.end],
+                span,
+            }),
+            Err((kind, error_span)) => Err(LexError {
+                kind,
+                span: error_span.unwrap_or(span),
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds_and_text(input: &str) -> Vec<(TokenKind, &str)> {
+        let (tokens, errors) = lex(input);
+        assert_eq!(errors, vec![]);
+        tokens.iter().map(|t| (t.kind, t.text)).collect()
+    }
+
+    #[test]
+    fn tokens_borrow_from_the_input() {
+        let input = String::from("name = \"lexer\"");
+        let (tokens, _) = lex(&input);
+        let text = tokens[2].text;
+        assert_eq!(text, "\"lexer\"");
+        assert!(std::ptr::eq(text, &input[7..]));
+        assert_eq!(tokens[2].string_value(), Some(Cow::Borrowed("lexer")));
+    }
+
+    #[test]
+    fn token_kinds() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds_and_text("select _id, größe from t where n >= 3.5e-2 && s != 'x'; # done"),
+            vec![
+                (Ident, "select"),
+                (Ident, "_id"),
+                (Punct, ","),
+                (Ident, "größe"),
+                (Ident, "from"),
+                (Ident, "t"),
+                (Ident, "where"),
+                (Ident, "n"),
+                (Punct, ">="),
+                (Number, "3.5e-2"),
+                (Punct, "&&"),
+                (Ident, "s"),
+                (Punct, "!="),
+                (Str, "'x'"),
+                (Punct, ";"),
+            ]
+        );
+        assert_eq!(
+            kinds_and_text("1..5 x.y 2. // comment\n"),
+            vec![
+                (Number, "1"),
+                (Punct, ".."),
+                (Number, "5"),
+                (Ident, "x"),
+                (Punct, "."),
+                (Ident, "y"),
+                (Number, "2"),
+                (Punct, "."),
+            ]
+        );
+        assert_eq!(kinds_and_text(""), vec![]);
+        assert_eq!(kinds_and_text("  # only a comment"), vec![]);
+    }
+
+    #[test]
+    fn spans() {
+        let input = "a = 1\n  größe = \"x\"\n\tb";
+        let (tokens, _) = lex(input);
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.span.line, t.span.column))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![(1, 1), (1, 3), (1, 5), (2, 3), (2, 9), (2, 11), (3, 2)]
+        );
+        for t in &tokens {
+            assert_eq!(&input[t.span.start..t.span.end], t.text);
+        }
+    }
+
+    #[test]
+    fn string_values() {
+        let value = |input| lex(input).0[0].string_value().unwrap().into_owned();
+        assert_eq!(value(r#""a\tb\n""#), "a\tb\n");
+        assert_eq!(value(r#"'it\'s "quoted"'"#), "it's \"quoted\"");
+        assert_eq!(value(r#""\u{1F600} \u{e9}\\""#), "😀 é\\");
+        assert_eq!(lex("42").0[0].string_value(), None);
+        assert_eq!(lex("42").0[0].number_value(), Some(42.0));
+        assert_eq!(lex("x").0[0].number_value(), None);
+    }
+
+    #[test]
+    fn recovers_from_errors() {
+        let input = "a ` b 12ab 1e c \"bad \\q escape\" d \"open\ne 'x\\u{110000}' f";
+        let (tokens, errors) = lex(input);
+        let names: Vec<_> = tokens.iter().map(|t| t.text).collect();
+        assert_eq!(names, vec!["a", "b", "c", "d", "e", "f"]);
+
+        let kinds: Vec<_> = errors.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LexErrorKind::UnexpectedChar('`'),
+                LexErrorKind::InvalidNumber,
+                LexErrorKind::InvalidNumber,
+                LexErrorKind::InvalidEscape('q'),
+                LexErrorKind::UnterminatedString,
+                LexErrorKind::InvalidEscape('u'),
+            ]
+        );
+        let text = |e: &LexError| &input[e.span.start..e.span.end];
+        assert_eq!(text(&errors[1]), "12ab");
+        assert_eq!(text(&errors[2]), "1e");
+        assert_eq!(text(&errors[3]), "\\q");
+        assert_eq!(text(&errors[4]), "\"open");
+        assert_eq!(text(&errors[5]), "\\u{110000}");
+        assert_eq!((errors[5].span.line, errors[5].span.column), (2, 5));
+    }
+
+    #[test]
+    fn error_messages() {
+        let (_, errors) = lex("ok\n  \"never closed");
+        assert_eq!(
+            errors[0].to_string(),
+            "line 2, column 3: unterminated string"
+        );
+        let (_, errors) = lex("`");
+        assert_eq!(
+            errors[0].to_string(),
+            "line 1, column 1: unexpected character '`'"
+        );
+    }
+
+    #[test]
+    fn owned_tokens_outlive_the_input() {
+        let owned: Vec<OwnedToken> = {
+            let input = String::from("x = 1");
+            tokenize(&input).map(|t| t.unwrap().into_owned()).collect()
+        };
+        assert_eq!(owned[0].text, "x");
+        assert_eq!(owned[2].kind, TokenKind::Number);
+        assert_eq!(owned[2].span.column, 5);
+    }
+}
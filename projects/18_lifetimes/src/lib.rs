@@ -0,0 +1,3 @@
+// The longer examples from main.rs, in a library so they can have tests of their own.
+pub mod excerpt;
+pub mod lexer;
@@ -1,7 +1,9 @@
 fn main() {
     test_longest();
     test_lifetime_within_struct();    
+    test_excerpts();
     test_longest2();
+    test_lexer();
 }
 
 // Lifetimes can be explicitly annotated and are used to help rust prevent dangling references.
@@ -51,6 +53,21 @@ fn another_example<'a>(x: &'a str, y: &str) -> &'a str {
     x
 } 
 
+// A more practical use of returning a reference into the input is a lexer, where every token is
+// a slice of the text being lexed.  See src/lexer.rs.
+use lifetimes::lexer;
+
+fn test_lexer() {
+    let config = String::from("name = \"demo\"\nport = 80_80  # oops\nratio = 0.5");
+    let (tokens, errors) = lexer::lex(&config);
+    for token in &tokens {
+        println!("{}:{} {:?} {}", token.span.line, token.span.column, token.kind, token.text);
+    }
+    for error in &errors {
+        println!("error: {}", error);
+    }
+}
+
 fn test_longest() {
     let string1 = String::from("long string is long");
 
@@ -75,6 +92,22 @@ fn test_lifetime_within_struct() {
     println!("except: {}", i.part);
 }
 
+// Splitting on '.' goes wrong for "Mr. Martyn Brown", and a sentence could end with '?' or '!'.
+// src/excerpt.rs splits a document into sentences and paragraphs, still without copying it, and
+// has a longest that ranks any number of excerpts.
+use lifetimes::excerpt::{self, Rank};
+
+fn test_excerpts() {
+    let novel = String::from("Call me Mr. Ishmael.  Some years ago... never mind how long!\n\n\
+        Whenever I find myself growing grim about the mouth, I go to sea.");
+    for sentence in excerpt::sentences(&novel) {
+        println!("{:>3}: {}", sentence.start, sentence.part);
+    }
+    if let Some(e) = excerpt::longest(excerpt::sentences(&novel), Rank::KeywordDensity(&["sea"])) {
+        println!("most about the sea: {}", e.part);
+    }
+}
+
 // Lifetime Elision
 // Rust uses three rules built into the compiler it will use if lifetime annotations are not 
 // provided.  If all three fail and lifetimes cannot be inferred there will be a compiler error.
@@ -102,6 +135,7 @@ impl<'a> ImportantExcerpt<'a> {
 
 // Static livetime (use sparingly)
 #[allow(dead_code)]
+#[allow(clippy::needless_return)]
 fn example_static() -> &'static str {
     let s: &'static str = "This will last for the programs durarion";
     return s;
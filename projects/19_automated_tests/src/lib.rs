@@ -79,14 +79,15 @@ mod tests {
 
     #[test]
     fn larger_can_hold_smaller() {
-        let larger = Rectangle { length: 8, width: 7 };
-        let smaller = Rectangle { length: 5, width: 1 };
+        let larger = Rectangle::new(8.0, 7.0).unwrap();
+        let smaller = Rectangle::new(5.0, 1.0).unwrap();
 
         // This calls panic! for a boolean of false.
         assert!(larger.can_hold(&smaller));        
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)] // It's meant to fail, to show the message.
     fn providing_custom_failure_messages() {
         assert!(
             23 == 25, 
@@ -123,23 +124,19 @@ mod tests {
     }
 }
 
-#[derive(Debug)] // For debug formatting so we can use with with assert!()
-pub struct Rectangle {
-    length: u32,
-    width: u32
-}
-
-impl Rectangle {
-    pub fn can_hold(&self, other: &Rectangle) -> bool {
-        self.length > other.length && self.width > other.width
-    }
-}
+// Rectangle (with #[derive(Debug)] for debug formatting so we can use with with assert!()) now
+// lives in src/shapes.rs, along with the other shapes and the Shape trait.  Its constructor checks
+// the dimensions, so the fields are private.  The unit tests for it are in shapes.rs too.
+pub mod shapes;
+pub use shapes::Rectangle;
 
 #[allow(dead_code)]
 fn test_should_panic() {
     panic!("just for demonstrating should_panic")
 }
 
+// Panics if the sum overflows, in a release build as well as a debug one, where '+' would wrap
+// around without a word.  The numeric crate has checked_add etc. for the other ways of handling it.
 pub fn add_stuff(val1: i32, val2: i32) -> i32 {
-    val1 + val2
+    numeric::try_add(val1, val2).unwrap_or_else(|overflow| panic!("add_stuff: {}", overflow))
 }
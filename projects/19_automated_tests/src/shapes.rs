@@ -0,0 +1,731 @@
+// Rectangle from src/lib.rs, grown into a small 2D geometry library.
+//
+// Every shape implements Shape, so they can be measured and compared through &dyn Shape:
+//
+//   let r = Rectangle::new(4.0, 3.0)?;
+//   let c = Circle::new(Point::new(5.0, 1.0), 1.5)?;
+//   assert_eq!(r.area(), 12.0);
+//   assert!(r.intersects(&c));
+//
+// The shapes can only be made through their constructors, which check the dimensions, so a shape
+// always has an area greater than 0: no negative, NaN or infinite lengths, no triangle with its
+// corners in a line, no polygon that crosses itself.
+//
+// The shapes are closed, so a point on the edge is inside the shape, and two shapes that only
+// touch intersect.  Points exactly on an edge are subject to floating point rounding, as ever.
+use std::f64::consts::PI;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub const fn new(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    pub fn distance(&self, other: Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+
+    fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeError {
+    // A length or radius that is 0, negative, NaN or infinite.
+    InvalidDimension,
+    // A corner with a NaN or infinite coordinate.
+    InvalidPoint,
+    TooFewVertices,
+    // All the corners in a line, so there's no area, or two corners in the same place.
+    Degenerate,
+    SelfIntersecting,
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            ShapeError::InvalidDimension => "dimensions must be finite and greater than 0",
+            ShapeError::InvalidPoint => "coordinates must be finite",
+            ShapeError::TooFewVertices => "a polygon needs at least 3 vertices",
+            ShapeError::Degenerate => "the shape has no area",
+            ShapeError::SelfIntersecting => "the polygon's edges cross",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+// The exact edge of a shape.  intersects compares two shapes by their outlines, so any two
+// shapes can be compared without every pair of types needing its own code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outline {
+    Circle { center: Point, radius: f64 },
+    Polygon(Vec<Point>),
+}
+
+// Debug so that any shape, even a Box<dyn Shape>, can be used with assert!().
+pub trait Shape: fmt::Debug {
+    fn area(&self) -> f64;
+
+    fn perimeter(&self) -> f64;
+
+    // The smallest Rectangle, with sides parallel to the axes, that the shape fits in.
+    fn bounding_box(&self) -> Rectangle;
+
+    fn contains_point(&self, point: Point) -> bool;
+
+    fn outline(&self) -> Outline;
+
+    // Whether the shapes overlap or touch.
+    fn intersects(&self, other: &dyn Shape) -> bool {
+        outlines_intersect(&self.outline(), &other.outline())
+    }
+}
+
+// A rectangle with its sides parallel to the axes: 'length' along x and 'width' along y from its
+// bottom left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rectangle {
+    corner: Point,
+    length: f64,
+    width: f64,
+}
+
+fn check_dimension(d: f64) -> Result<f64, ShapeError> {
+    if d.is_finite() && d > 0.0 {
+        Ok(d)
+    } else {
+        Err(ShapeError::InvalidDimension)
+    }
+}
+
+fn check_point(p: Point) -> Result<Point, ShapeError> {
+    if p.is_finite() {
+        Ok(p)
+    } else {
+        Err(ShapeError::InvalidPoint)
+    }
+}
+
+impl Rectangle {
+    // A rectangle with its bottom left corner at the origin.
+    pub fn new(length: f64, width: f64) -> Result<Rectangle, ShapeError> {
+        Rectangle::with_corner(Point::new(0.0, 0.0), length, width)
+    }
+
+    pub fn with_corner(corner: Point, length: f64, width: f64) -> Result<Rectangle, ShapeError> {
+        let corner = check_point(corner)?;
+        let (length, width) = (check_dimension(length)?, check_dimension(width)?);
+        // The far corner has to be finite too.
+        check_point(Point::new(corner.x + length, corner.y + width))?;
+        Ok(Rectangle {
+            corner,
+            length,
+            width,
+        })
+    }
+
+    pub fn corner(&self) -> Point {
+        self.corner
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    // Whether 'other' would fit inside this rectangle without touching its sides, wherever the
+    // two are.  So a rectangle can't hold itself.
+    pub fn can_hold(&self, other: &Rectangle) -> bool {
+        self.length > other.length && self.width > other.width
+    }
+
+    // The corners, anticlockwise from the bottom left.
+    pub fn vertices(&self) -> [Point; 4] {
+        let Point { x, y } = self.corner;
+        [
+            Point::new(x, y),
+            Point::new(x + self.length, y),
+            Point::new(x + self.length, y + self.width),
+            Point::new(x, y + self.width),
+        ]
+    }
+}
+
+impl Shape for Rectangle {
+    fn area(&self) -> f64 {
+        self.length * self.width
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * (self.length + self.width)
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        *self
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        let Point { x, y } = self.corner;
+        (x..=x + self.length).contains(&point.x) && (y..=y + self.width).contains(&point.y)
+    }
+
+    fn outline(&self) -> Outline {
+        Outline::Polygon(self.vertices().to_vec())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    center: Point,
+    radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: Point, radius: f64) -> Result<Circle, ShapeError> {
+        let center = check_point(center)?;
+        let radius = check_dimension(radius)?;
+        check_point(Point::new(center.x + radius, center.y + radius))?;
+        check_point(Point::new(center.x - radius, center.y - radius))?;
+        Ok(Circle { center, radius })
+    }
+
+    pub fn center(&self) -> Point {
+        self.center
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl Shape for Circle {
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * PI * self.radius
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let r = self.radius;
+        Rectangle {
+            corner: Point::new(self.center.x - r, self.center.y - r),
+            length: 2.0 * r,
+            width: 2.0 * r,
+        }
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        self.center.distance(point) <= self.radius
+    }
+
+    fn outline(&self) -> Outline {
+        Outline::Circle {
+            center: self.center,
+            radius: self.radius,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    vertices: [Point; 3],
+}
+
+impl Triangle {
+    pub fn new(a: Point, b: Point, c: Point) -> Result<Triangle, ShapeError> {
+        let vertices = [check_point(a)?, check_point(b)?, check_point(c)?];
+        if signed_area(&vertices) == 0.0 {
+            return Err(ShapeError::Degenerate);
+        }
+        Ok(Triangle { vertices })
+    }
+
+    pub fn vertices(&self) -> [Point; 3] {
+        self.vertices
+    }
+}
+
+impl Shape for Triangle {
+    fn area(&self) -> f64 {
+        signed_area(&self.vertices).abs()
+    }
+
+    fn perimeter(&self) -> f64 {
+        perimeter(&self.vertices)
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        bounding_box(&self.vertices)
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        polygon_contains(&self.vertices, point)
+    }
+
+    fn outline(&self) -> Outline {
+        Outline::Polygon(self.vertices.to_vec())
+    }
+}
+
+// A simple polygon: its edges join each vertex to the next and the last to the first, and don't
+// cross.  It can be concave, and the vertices can go either way round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<Point>) -> Result<Polygon, ShapeError> {
+        if vertices.len() < 3 {
+            return Err(ShapeError::TooFewVertices);
+        }
+        for &v in &vertices {
+            check_point(v)?;
+        }
+        if signed_area(&vertices) == 0.0 || edges(&vertices).any(|(a, b)| a == b) {
+            return Err(ShapeError::Degenerate);
+        }
+
+        // Each edge against every other edge that doesn't share a vertex with it.  Neighbouring
+        // edges always meet at their shared vertex, so they only count if one folds back along
+        // the other.
+        let n = vertices.len();
+        let edge = |i: usize| (vertices[i], vertices[(i + 1) % n]);
+        for i in 0..n {
+            let (a, b) = edge(i);
+            let next = vertices[(i + 2) % n];
+            if cross(a, b, next) == 0.0
+                && (b.x - a.x) * (next.x - b.x) + (b.y - a.y) * (next.y - b.y) < 0.0
+            {
+                return Err(ShapeError::SelfIntersecting);
+            }
+            for j in i + 2..n {
+                if i == 0 && j == n - 1 {
+                    continue;
+                }
+                let (c, d) = edge(j);
+                if segments_intersect(a, b, c, d) {
+                    return Err(ShapeError::SelfIntersecting);
+                }
+            }
+        }
+        Ok(Polygon { vertices })
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+}
+
+impl Shape for Polygon {
+    fn area(&self) -> f64 {
+        signed_area(&self.vertices).abs()
+    }
+
+    fn perimeter(&self) -> f64 {
+        perimeter(&self.vertices)
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        bounding_box(&self.vertices)
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        polygon_contains(&self.vertices, point)
+    }
+
+    fn outline(&self) -> Outline {
+        Outline::Polygon(self.vertices.clone())
+    }
+}
+
+// Twice the area of the triangle o, a, b, positive if they go anticlockwise, 0 if they are in a
+// line.
+fn cross(o: Point, a: Point, b: Point) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+// The shoelace formula, positive if the vertices go anticlockwise.
+fn signed_area(vertices: &[Point]) -> f64 {
+    let n = vertices.len();
+    let twice: f64 = (0..n)
+        .map(|i| {
+            let (a, b) = (vertices[i], vertices[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    twice / 2.0
+}
+
+fn perimeter(vertices: &[Point]) -> f64 {
+    let n = vertices.len();
+    (0..n)
+        .map(|i| vertices[i].distance(vertices[(i + 1) % n]))
+        .sum()
+}
+
+fn bounding_box(vertices: &[Point]) -> Rectangle {
+    let (mut min, mut max) = (vertices[0], vertices[0]);
+    for v in vertices {
+        min = Point::new(min.x.min(v.x), min.y.min(v.y));
+        max = Point::new(max.x.max(v.x), max.y.max(v.y));
+    }
+    Rectangle {
+        corner: min,
+        length: max.x - min.x,
+        width: max.y - min.y,
+    }
+}
+
+// Whether p is on the segment a-b, given that the three are in a line.
+fn within_segment(a: Point, b: Point, p: Point) -> bool {
+    p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+}
+
+fn on_segment(a: Point, b: Point, p: Point) -> bool {
+    cross(a, b, p) == 0.0 && within_segment(a, b, p)
+}
+
+// Whether segment a-b and segment c-d cross or touch.
+fn segments_intersect(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+    on_segment(c, d, a) || on_segment(c, d, b) || on_segment(a, b, c) || on_segment(a, b, d)
+}
+
+fn distance_to_segment(a: Point, b: Point, p: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_squared = dx * dx + dy * dy;
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / length_squared).clamp(0.0, 1.0);
+    p.distance(Point::new(a.x + t * dx, a.y + t * dy))
+}
+
+fn edges(vertices: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    let n = vertices.len();
+    (0..n).map(move |i| (vertices[i], vertices[(i + 1) % n]))
+}
+
+// On an edge, or inside by the even-odd rule: a ray from the point crosses the edges an odd
+// number of times.
+fn polygon_contains(vertices: &[Point], p: Point) -> bool {
+    let mut inside = false;
+    for (a, b) in edges(vertices) {
+        if on_segment(a, b, p) {
+            return true;
+        }
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn outlines_intersect(a: &Outline, b: &Outline) -> bool {
+    match (a, b) {
+        (
+            Outline::Circle {
+                center: c1,
+                radius: r1,
+            },
+            Outline::Circle {
+                center: c2,
+                radius: r2,
+            },
+        ) => c1.distance(*c2) <= r1 + r2,
+        (Outline::Circle { center, radius }, Outline::Polygon(vertices))
+        | (Outline::Polygon(vertices), Outline::Circle { center, radius }) => {
+            polygon_contains(vertices, *center)
+                || edges(vertices).any(|(a, b)| distance_to_segment(a, b, *center) <= *radius)
+        }
+        (Outline::Polygon(p), Outline::Polygon(q)) => {
+            edges(p).any(|(a, b)| edges(q).any(|(c, d)| segments_intersect(a, b, c, d)))
+                || polygon_contains(p, q[0])
+                || polygon_contains(q, p[0])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn p(x: f64, y: f64) -> Point {
+        Point::new(x, y)
+    }
+
+    #[test]
+    fn constructors_check_dimensions() {
+        assert!(Rectangle::new(2.0, 3.0).is_ok());
+        for bad in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(Rectangle::new(*bad, 1.0), Err(ShapeError::InvalidDimension));
+            assert_eq!(Rectangle::new(1.0, *bad), Err(ShapeError::InvalidDimension));
+            assert_eq!(
+                Circle::new(p(0.0, 0.0), *bad),
+                Err(ShapeError::InvalidDimension)
+            );
+        }
+        assert_eq!(
+            Rectangle::with_corner(p(f64::NAN, 0.0), 1.0, 1.0),
+            Err(ShapeError::InvalidPoint)
+        );
+        assert_eq!(
+            Rectangle::with_corner(p(f64::MAX, 0.0), f64::MAX, 1.0),
+            Err(ShapeError::InvalidPoint)
+        );
+        assert_eq!(
+            Triangle::new(p(0.0, 0.0), p(1.0, 1.0), p(2.0, 2.0)),
+            Err(ShapeError::Degenerate)
+        );
+        assert_eq!(
+            ShapeError::InvalidDimension.to_string(),
+            "dimensions must be finite and greater than 0"
+        );
+    }
+
+    #[test]
+    fn polygon_checks() {
+        assert_eq!(
+            Polygon::new(vec![p(0.0, 0.0), p(1.0, 0.0)]),
+            Err(ShapeError::TooFewVertices)
+        );
+        assert_eq!(
+            Polygon::new(vec![p(0.0, 0.0), p(1.0, 0.0), p(2.0, 0.0), p(3.0, 0.0)]),
+            Err(ShapeError::Degenerate)
+        );
+        assert_eq!(
+            Polygon::new(vec![p(0.0, 0.0), p(1.0, 0.0), p(1.0, 0.0), p(0.0, 1.0)]),
+            Err(ShapeError::Degenerate)
+        );
+        // A bow tie.
+        assert_eq!(
+            Polygon::new(vec![p(0.0, 0.0), p(3.0, 2.0), p(3.0, 0.0), p(0.0, 1.0)]),
+            Err(ShapeError::SelfIntersecting)
+        );
+        // An edge that goes back along the one before it.
+        assert_eq!(
+            Polygon::new(vec![p(0.0, 0.0), p(2.0, 0.0), p(1.0, 0.0), p(1.0, 1.0)]),
+            Err(ShapeError::SelfIntersecting)
+        );
+        // Concave and clockwise is fine.
+        assert!(Polygon::new(vec![p(0.0, 0.0), p(1.0, 2.0), p(2.0, 0.0), p(1.0, 1.0)]).is_ok());
+    }
+
+    #[test]
+    fn measurements() {
+        let r = Rectangle::with_corner(p(1.0, 1.0), 4.0, 3.0).unwrap();
+        assert_eq!((r.area(), r.perimeter()), (12.0, 14.0));
+        assert_eq!(r.bounding_box(), r);
+
+        let c = Circle::new(p(1.0, 2.0), 2.0).unwrap();
+        assert_eq!(c.area(), 4.0 * PI);
+        assert_eq!(c.perimeter(), 4.0 * PI);
+        assert_eq!(
+            c.bounding_box(),
+            Rectangle::with_corner(p(-1.0, 0.0), 4.0, 4.0).unwrap()
+        );
+
+        let t = Triangle::new(p(0.0, 0.0), p(4.0, 0.0), p(0.0, 3.0)).unwrap();
+        assert_eq!((t.area(), t.perimeter()), (6.0, 12.0));
+        assert_eq!(t.bounding_box(), Rectangle::new(4.0, 3.0).unwrap());
+
+        // An L shape.
+        let l = Polygon::new(vec![
+            p(0.0, 0.0),
+            p(2.0, 0.0),
+            p(2.0, 1.0),
+            p(1.0, 1.0),
+            p(1.0, 2.0),
+            p(0.0, 2.0),
+        ])
+        .unwrap();
+        assert_eq!((l.area(), l.perimeter()), (3.0, 8.0));
+        assert_eq!(l.bounding_box(), Rectangle::new(2.0, 2.0).unwrap());
+    }
+
+    #[test]
+    fn contains_points() {
+        let l = Polygon::new(vec![
+            p(0.0, 0.0),
+            p(2.0, 0.0),
+            p(2.0, 1.0),
+            p(1.0, 1.0),
+            p(1.0, 2.0),
+            p(0.0, 2.0),
+        ])
+        .unwrap();
+        assert!(l.contains_point(p(0.5, 1.5)));
+        assert!(l.contains_point(p(1.5, 0.5)));
+        assert!(!l.contains_point(p(1.5, 1.5)));
+        // On the edges and corners.
+        assert!(l.contains_point(p(1.0, 1.5)));
+        assert!(l.contains_point(p(2.0, 1.0)));
+
+        let c = Circle::new(p(0.0, 0.0), 1.0).unwrap();
+        assert!(c.contains_point(p(0.0, 1.0)));
+        assert!(!c.contains_point(p(0.8, 0.8)));
+
+        let r = Rectangle::new(1.0, 1.0).unwrap();
+        assert!(r.contains_point(p(1.0, 0.5)));
+        assert!(!r.contains_point(p(1.0, 1.5)));
+    }
+
+    #[test]
+    fn intersections() {
+        let r = Rectangle::new(4.0, 3.0).unwrap();
+        let c = Circle::new(p(5.0, 1.0), 1.5).unwrap();
+        assert!(r.intersects(&c));
+        // Touching the corner.
+        assert!(r.intersects(&Circle::new(p(5.0, 4.0), 2.0_f64.sqrt()).unwrap()));
+        assert!(!r.intersects(&Circle::new(p(5.0, 4.0), 1.4).unwrap()));
+
+        // One shape wholly inside the other, so no edges cross.
+        let inner = Triangle::new(p(1.0, 1.0), p(2.0, 1.0), p(1.0, 2.0)).unwrap();
+        assert!(r.intersects(&inner));
+        assert!(inner.intersects(&r));
+        assert!(Circle::new(p(2.0, 1.5), 10.0).unwrap().intersects(&r));
+
+        let far = Polygon::new(vec![p(10.0, 10.0), p(11.0, 10.0), p(10.0, 11.0)]).unwrap();
+        assert!(!r.intersects(&far));
+
+        let shapes: Vec<Box<dyn Shape>> = vec![Box::new(r), Box::new(c), Box::new(far)];
+        let total: f64 = shapes.iter().map(|s| s.area()).sum();
+        assert_eq!(total, 12.0 + 2.25 * PI + 0.5);
+    }
+
+    fn coordinate() -> impl Strategy<Value = f64> {
+        -100.0..100.0
+    }
+
+    fn dimension() -> impl Strategy<Value = f64> {
+        0.01..100.0
+    }
+
+    fn point() -> impl Strategy<Value = Point> {
+        (coordinate(), coordinate()).prop_map(|(x, y)| Point::new(x, y))
+    }
+
+    fn rectangle() -> impl Strategy<Value = Rectangle> {
+        (point(), dimension(), dimension())
+            .prop_map(|(corner, l, w)| Rectangle::with_corner(corner, l, w).unwrap())
+    }
+
+    fn circle() -> impl Strategy<Value = Circle> {
+        (point(), dimension()).prop_map(|(center, r)| Circle::new(center, r).unwrap())
+    }
+
+    fn triangle() -> impl Strategy<Value = Triangle> {
+        (point(), point(), point())
+            .prop_filter_map("corners in a line", |(a, b, c)| Triangle::new(a, b, c).ok())
+    }
+
+    // Any of the shapes.  Polygons are made as stars, with a vertex at each of a set of angles
+    // around a center, at different distances, which can't cross itself.
+    fn shape() -> impl Strategy<Value = Box<dyn Shape>> {
+        let star = (point(), prop::collection::vec(1.0..50.0f64, 3..12)).prop_map(
+            |(center, distances)| {
+                let n = distances.len() as f64;
+                let vertices = distances
+                    .iter()
+                    .enumerate()
+                    .map(|(i, d)| {
+                        let angle = 2.0 * PI * i as f64 / n;
+                        Point::new(center.x + d * angle.cos(), center.y + d * angle.sin())
+                    })
+                    .collect();
+                Polygon::new(vertices).unwrap()
+            },
+        );
+        prop_oneof![
+            rectangle().prop_map(|s| Box::new(s) as Box<dyn Shape>),
+            circle().prop_map(|s| Box::new(s) as Box<dyn Shape>),
+            triangle().prop_map(|s| Box::new(s) as Box<dyn Shape>),
+            star.prop_map(|s| Box::new(s) as Box<dyn Shape>),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(test_support::fixed_seed(19))]
+
+        #[test]
+        fn can_hold_is_irreflexive(r in rectangle()) {
+            prop_assert!(!r.can_hold(&r));
+        }
+
+        #[test]
+        fn can_hold_is_asymmetric(a in rectangle(), b in rectangle()) {
+            prop_assert!(!(a.can_hold(&b) && b.can_hold(&a)));
+        }
+
+        #[test]
+        fn can_hold_is_transitive(a in rectangle(), b in rectangle(), c in rectangle()) {
+            if a.can_hold(&b) && b.can_hold(&c) {
+                prop_assert!(a.can_hold(&c));
+            }
+        }
+
+        #[test]
+        fn a_larger_rectangle_can_hold_a_smaller_one(r in rectangle(), grow in 0.01..10.0f64) {
+            let larger = Rectangle::with_corner(r.corner(), r.length() + grow, r.width() + grow)
+                .unwrap();
+            prop_assert!(larger.can_hold(&r));
+            prop_assert!(!r.can_hold(&larger));
+        }
+
+        #[test]
+        fn measurements_are_positive(s in shape()) {
+            prop_assert!(s.area() > 0.0);
+            prop_assert!(s.perimeter() > 0.0);
+        }
+
+        #[test]
+        fn the_bounding_box_holds_the_shape(s in shape(), q in point()) {
+            let bounds = s.bounding_box();
+            prop_assert!(bounds.area() >= s.area() * (1.0 - 1e-9));
+            if s.contains_point(q) {
+                prop_assert!(bounds.contains_point(q));
+            }
+            prop_assert!(s.intersects(&bounds));
+        }
+
+        #[test]
+        fn intersects_is_symmetric_and_reflexive(a in shape(), b in shape()) {
+            prop_assert_eq!(a.intersects(&*b), b.intersects(&*a));
+            prop_assert!(a.intersects(&*a));
+        }
+
+        #[test]
+        fn a_shared_point_means_they_intersect(a in shape(), b in shape(), q in point()) {
+            if a.contains_point(q) && b.contains_point(q) {
+                prop_assert!(a.intersects(&*b));
+            }
+        }
+
+        #[test]
+        fn a_rectangle_is_a_polygon(r in rectangle(), q in point()) {
+            let polygon = Polygon::new(r.vertices().to_vec()).unwrap();
+            prop_assert!((polygon.area() - r.area()).abs() <= r.area() * 1e-9);
+            prop_assert!((polygon.perimeter() - r.perimeter()).abs() <= r.perimeter() * 1e-9);
+            prop_assert_eq!(polygon.contains_point(q), r.contains_point(q));
+        }
+    }
+}
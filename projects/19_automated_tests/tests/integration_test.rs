@@ -3,6 +3,7 @@
 // 
 // Also see comments in src/lib.rs
 
+#[allow(clippy::single_component_path_imports)]
 use automated_tests;
 
 mod common;
@@ -0,0 +1,46 @@
+// cargo test --test properties
+//
+// add_stuff's example in integration_test.rs as properties, which check it for many inputs
+// rather than one.  proptest makes up the inputs, here from a generator in ../test_support, and
+// if a property fails it shrinks the input to the simplest one that still fails.
+//
+// Also see comments in src/lib.rs
+
+use automated_tests::add_stuff;
+use proptest::prelude::*;
+use test_support::generators::i32_pair_without_overflow;
+
+mod common;
+
+proptest! {
+    #![proptest_config(test_support::fixed_seed(19))]
+
+    #[test]
+    fn add_stuff_is_addition((a, b) in i32_pair_without_overflow()) {
+        common::setup();
+        prop_assert_eq!(add_stuff(a, b) as i64, a as i64 + b as i64);
+    }
+
+    #[test]
+    fn add_stuff_is_commutative((a, b) in i32_pair_without_overflow()) {
+        prop_assert_eq!(add_stuff(a, b), add_stuff(b, a));
+    }
+
+    #[test]
+    fn zero_changes_nothing(a: i32) {
+        prop_assert_eq!(add_stuff(a, 0), a);
+        prop_assert_eq!(add_stuff(0, a), a);
+    }
+
+    #[test]
+    fn adding_and_taking_away((a, b) in i32_pair_without_overflow()) {
+        prop_assert_eq!(add_stuff(a, b) - b, a);
+    }
+}
+
+// Checked in every build, not just debug ones: cargo test --release --test properties.
+#[test]
+#[should_panic(expected = "add_stuff: 2147483647 + 1 overflows i32")]
+fn overflow_panics() {
+    add_stuff(i32::MAX, 1);
+}
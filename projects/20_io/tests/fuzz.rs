@@ -0,0 +1,49 @@
+// cargo test --test fuzz
+// FUZZ_CASES=100000 cargo test --release --test fuzz
+//
+// Fuzz targets for the public functions that take outside input: the query and file contents
+// given to search, and the command line given to Config::new.  Anything at all should give an
+// answer, never a panic.
+use io::{search, search_case_insensitive, Config};
+use proptest::prelude::*;
+use test_support::fuzz::mutated_text;
+use test_support::generators::args;
+
+const CONTENTS: &[&str] = &[
+    "Rust:\nsafe, fast, productive.\nPick three.\nDuct tape",
+    "I'm nobody! Who are you?\r\nAre you nobody, too?\r\n",
+    "\n\n\nÉtoile\tİstanbul ΣΊΣΥΦΟΣ straße\n",
+];
+
+proptest! {
+    #![proptest_config(test_support::fuzz_config(20))]
+
+    #[test]
+    fn search_any_text(query in mutated_text(&["duct", "rUsT", "", "ß"]), contents in mutated_text(CONTENTS)) {
+        for line in search(&query, &contents) {
+            prop_assert!(line.contains(query.as_str()));
+            prop_assert!(!line.contains('\n'));
+        }
+        // to_lowercase can change a line's length, 'İ' is 2 bytes but "i̇" is 3, which mustn't
+        // upset anything.
+        for line in search_case_insensitive(&query, &contents) {
+            prop_assert!(line.to_lowercase().contains(&query.to_lowercase()));
+        }
+    }
+
+    #[test]
+    fn config_from_any_args(args in args()) {
+        match Config::new(&args) {
+            Ok(config) => {
+                prop_assert!(args.len() >= 3);
+                prop_assert_eq!(&config.query, &args[1]);
+                prop_assert_eq!(&config.filename, &args[2]);
+                prop_assert_eq!(config.case_sensitive, std::env::var("CASE_INSENSITIVE").is_err());
+            }
+            Err(message) => {
+                prop_assert!(args.len() < 3);
+                prop_assert_eq!(message, "not enough arguments");
+            }
+        }
+    }
+}
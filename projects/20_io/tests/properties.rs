@@ -0,0 +1,50 @@
+// cargo test --test properties
+//
+// The search examples in src/lib.rs, as properties that hold for any query and contents.  The
+// generators are from ../test_support.
+use io::{search, search_case_insensitive};
+use proptest::prelude::*;
+use test_support::generators::needle_and_haystack;
+
+proptest! {
+    #![proptest_config(test_support::fixed_seed(20))]
+
+    #[test]
+    fn search_finds_exactly_the_lines_with_the_query((query, lines) in needle_and_haystack()) {
+        let contents = lines.join("\n");
+        let expected: Vec<&str> = contents.lines().filter(|l| l.contains(query.as_str())).collect();
+        prop_assert_eq!(search(&query, &contents), expected);
+    }
+
+    #[test]
+    fn results_are_lines_of_the_contents_in_order((query, lines) in needle_and_haystack()) {
+        let contents = lines.join("\n");
+        let all: Vec<&str> = contents.lines().collect();
+        let mut rest = all.iter();
+        for found in search_case_insensitive(&query, &contents) {
+            // The same slice of 'contents', not a copy, and after the one before.
+            prop_assert!(rest.any(|line| line.as_ptr() == found.as_ptr()));
+        }
+    }
+
+    #[test]
+    fn case_insensitive_finds_at_least_as_much((query, lines) in needle_and_haystack()) {
+        let contents = lines.join("\n");
+        let sensitive = search(&query, &contents);
+        let insensitive = search_case_insensitive(&query, &contents);
+        for line in &sensitive {
+            prop_assert!(insensitive.contains(line));
+        }
+        // Not to_uppercase, which can't always be undone: "ß" is "SS", and that's "ss".
+        prop_assert_eq!(
+            search_case_insensitive(&query.to_lowercase(), &contents),
+            insensitive
+        );
+    }
+
+    #[test]
+    fn the_empty_query_finds_every_line(lines in test_support::generators::lines()) {
+        let contents = lines.join("\n");
+        prop_assert_eq!(search("", &contents).len(), contents.lines().count());
+    }
+}
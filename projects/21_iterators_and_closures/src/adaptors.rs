@@ -0,0 +1,623 @@
+// More iterator adaptors, added to every iterator by an extension trait.
+//
+// We can't add methods to the standard library's Iterator trait, but we can define our own trait
+// with Iterator as its supertrait and implement it for every iterator at once (a 'blanket
+// implementation').  Once IteratorExt is in scope any iterator has these methods:
+//
+//   use iterators_and_closures::adaptors::IteratorExt;
+//   let pairs: Vec<Vec<u32>> = Counter::new().chunks_exact(2).collect();   // [[1, 2], [3, 4]]
+//
+// Each adaptor is a struct holding the iterator it adapts, with its own Iterator implementation,
+// just like Map or Filter in the standard library.  Like them they are lazy and do nothing until
+// consumed.
+//
+// size_hint: where the number of items follows from the number the inner iterator has left
+// (chunks_exact, windows, interleave, cartesian_product) the hint is worked out from the inner
+// hint, so it is exact whenever that is (e.g. for Counter).  The rest depend on the values
+// themselves, so their hints give the tightest bounds that are always true.
+use std::collections::VecDeque;
+use std::iter::Peekable;
+
+pub trait IteratorExt: Iterator + Sized {
+    // Vecs of exactly 'size' items.  The items left over at the end, fewer than 'size', are
+    // not yielded but can be had from remainder().  Panics if 'size' is 0.
+    fn chunks_exact(self, size: usize) -> ChunksExact<Self> {
+        assert!(size != 0, "chunk size must be greater than 0");
+        ChunksExact {
+            iter: self,
+            size,
+            remainder: Vec::new(),
+        }
+    }
+
+    // Every run of 'size' consecutive items, overlapping: [1, 2, 3], [2, 3, 4]...
+    // Panics if 'size' is 0.
+    fn windows(self, size: usize) -> Windows<Self>
+    where
+        Self::Item: Clone,
+    {
+        assert!(size != 0, "window size must be greater than 0");
+        Windows {
+            iter: self,
+            size,
+            window: VecDeque::with_capacity(size),
+            started: false,
+        }
+    }
+
+    // One item from each in turn.  Once either runs out, the rest of the other.
+    fn interleave<J>(self, other: J) -> Interleave<Self, J::IntoIter>
+    where
+        J: IntoIterator<Item = Self::Item>,
+    {
+        Interleave {
+            a: self,
+            b: other.into_iter(),
+            next_from_a: true,
+        }
+    }
+
+    // Drops each item whose key is the same as the item before it.
+    fn dedup_by_key<K, F>(self, key: F) -> DedupByKey<Self, K, F>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+    {
+        DedupByKey {
+            iter: self,
+            key,
+            last: None,
+        }
+    }
+
+    // Runs of consecutive items with the same key, as (key, items).  Unlike SQL's GROUP BY, a key
+    // that appears in two separate runs gives two groups.
+    fn group_by<K, F>(self, key: F) -> GroupBy<Self, K, F>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+    {
+        GroupBy {
+            iter: self,
+            key,
+            next: None,
+        }
+    }
+
+    // Calls 'f' with the iterator to build each item of the new iterator, for when each one
+    // needs a varying number of the old items.  Stops when 'f' returns None.
+    fn batching<B, F>(self, f: F) -> Batching<Self, F>
+    where
+        F: FnMut(&mut Self) -> Option<B>,
+    {
+        Batching { iter: self, f }
+    }
+
+    // Like take_while, but the first item that fails 'accept' is left in the iterator rather
+    // than thrown away.  Works on a Peekable, by reference, so the iterator can be used again:
+    //
+    //   let mut iter = Counter::new().peekable();
+    //   let small: Vec<u32> = iter.peeking_take_while(|&n| n < 3).collect();   // [1, 2]
+    //   assert_eq!(iter.next(), Some(3));
+    fn peeking_take_while<P>(&mut self, accept: P) -> PeekingTakeWhile<'_, Self, P>
+    where
+        Self: PeekingNext,
+        P: FnMut(&Self::Item) -> bool,
+    {
+        PeekingTakeWhile { iter: self, accept }
+    }
+
+    // Every pair (a, b) with 'a' from this iterator and 'b' from 'other'.  'other' is cloned to
+    // go round it again for each 'a'.
+    fn cartesian_product<J>(self, other: J) -> CartesianProduct<Self, J::IntoIter>
+    where
+        Self::Item: Clone,
+        J: IntoIterator,
+        J::IntoIter: Clone,
+    {
+        let other = other.into_iter();
+        CartesianProduct {
+            a: self,
+            a_item: None,
+            b: other.clone(),
+            b_start: other,
+        }
+    }
+}
+
+impl<I: Iterator> IteratorExt for I {}
+
+// Saturating on the lower bound and None on overflow for the upper, like the standard adaptors.
+fn add_hints(a: (usize, Option<usize>), b: (usize, Option<usize>)) -> (usize, Option<usize>) {
+    let upper = match (a.1, b.1) {
+        (Some(x), Some(y)) => x.checked_add(y),
+        _ => None,
+    };
+    (a.0.saturating_add(b.0), upper)
+}
+
+#[derive(Clone, Debug)]
+pub struct ChunksExact<I: Iterator> {
+    iter: I,
+    size: usize,
+    remainder: Vec<I::Item>,
+}
+
+impl<I: Iterator> ChunksExact<I> {
+    // The items that didn't make a full chunk, once the iterator is finished.
+    pub fn remainder(&self) -> &[I::Item] {
+        &self.remainder
+    }
+}
+
+impl<I: Iterator> Iterator for ChunksExact<I> {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk: Vec<I::Item> = self.iter.by_ref().take(self.size).collect();
+        if chunk.len() == self.size {
+            Some(chunk)
+        } else {
+            // Calls after the end find nothing, and mustn't lose the remainder already found.
+            if !chunk.is_empty() {
+                self.remainder = chunk;
+            }
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        (lower / self.size, upper.map(|upper| upper / self.size))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Windows<I: Iterator> {
+    iter: I,
+    size: usize,
+    window: VecDeque<I::Item>,
+    started: bool, // Once the first window is full, each new item makes one more window.
+}
+
+impl<I: Iterator> Iterator for Windows<I>
+where
+    I::Item: Clone,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.started {
+            let item = self.iter.next()?;
+            self.window.pop_front();
+            self.window.push_back(item);
+        } else {
+            while self.window.len() < self.size {
+                self.window.push_back(self.iter.next()?);
+            }
+            self.started = true;
+        }
+        Some(self.window.iter().cloned().collect())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        if self.started {
+            (lower, upper)
+        } else {
+            // The first window needs 'size' items (some may already be in 'window' if an
+            // earlier call ran out), then one window per item after that.
+            let needed = self.size - 1 - self.window.len().min(self.size - 1);
+            (
+                lower.saturating_sub(needed),
+                upper.map(|upper| upper.saturating_sub(needed)),
+            )
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Interleave<I, J> {
+    a: I,
+    b: J,
+    next_from_a: bool,
+}
+
+impl<I, J> Iterator for Interleave<I, J>
+where
+    I: Iterator,
+    J: Iterator<Item = I::Item>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.next_from_a = !self.next_from_a;
+        if self.next_from_a {
+            self.b.next().or_else(|| self.a.next())
+        } else {
+            self.a.next().or_else(|| self.b.next())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        add_hints(self.a.size_hint(), self.b.size_hint())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DedupByKey<I, K, F> {
+    iter: I,
+    key: F,
+    last: Option<K>, // The key of the last item yielded.
+}
+
+impl<I, K, F> Iterator for DedupByKey<I, K, F>
+where
+    I: Iterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        for item in self.iter.by_ref() {
+            let key = (self.key)(&item);
+            if self.last.as_ref() != Some(&key) {
+                self.last = Some(key);
+                return Some(item);
+            }
+        }
+        None
+    }
+
+    // All the rest could be duplicates of the last one, unless there hasn't been a last one.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        let lower = if self.last.is_none() && lower > 0 {
+            1
+        } else {
+            0
+        };
+        (lower, upper)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GroupBy<I: Iterator, K, F> {
+    iter: I,
+    key: F,
+    next: Option<(K, I::Item)>, // The first item of the next group, already taken from 'iter'.
+}
+
+impl<I, K, F> Iterator for GroupBy<I, K, F>
+where
+    I: Iterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+{
+    type Item = (K, Vec<I::Item>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, first) = match self.next.take() {
+            Some(next) => next,
+            None => {
+                let item = self.iter.next()?;
+                ((self.key)(&item), item)
+            }
+        };
+
+        let mut group = vec![first];
+        for item in self.iter.by_ref() {
+            let item_key = (self.key)(&item);
+            if item_key == key {
+                group.push(item);
+            } else {
+                self.next = Some((item_key, item));
+                break;
+            }
+        }
+        Some((key, group))
+    }
+
+    // At least one group if there are any items left, at most one group per item.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let held = self.next.is_some() as usize;
+        let (lower, upper) = self.iter.size_hint();
+        let lower = if held + lower > 0 { 1 } else { 0 };
+        (lower, upper.and_then(|upper| upper.checked_add(held)))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Batching<I, F> {
+    iter: I,
+    f: F,
+}
+
+impl<I, B, F> Iterator for Batching<I, F>
+where
+    I: Iterator,
+    F: FnMut(&mut I) -> Option<B>,
+{
+    type Item = B;
+
+    fn next(&mut self) -> Option<B> {
+        (self.f)(&mut self.iter)
+    }
+
+    // 'f' can use any number of items per batch, including none, so there's nothing to go on.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+// An iterator that can hand over its next item only if the caller wants it.
+pub trait PeekingNext: Iterator {
+    fn peeking_next<F>(&mut self, accept: F) -> Option<Self::Item>
+    where
+        F: FnOnce(&Self::Item) -> bool;
+}
+
+impl<I: Iterator> PeekingNext for Peekable<I> {
+    fn peeking_next<F>(&mut self, accept: F) -> Option<I::Item>
+    where
+        F: FnOnce(&I::Item) -> bool,
+    {
+        self.next_if(accept)
+    }
+}
+
+pub struct PeekingTakeWhile<'a, I, P> {
+    iter: &'a mut I,
+    accept: P,
+}
+
+impl<I, P> Iterator for PeekingTakeWhile<'_, I, P>
+where
+    I: PeekingNext,
+    P: FnMut(&I::Item) -> bool,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.iter.peeking_next(&mut self.accept)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CartesianProduct<I: Iterator, J> {
+    a: I,
+    a_item: Option<I::Item>, // The 'a' being paired with what is left of 'b'.
+    b: J,
+    b_start: J, // A fresh copy of 'b' for the next 'a'.
+}
+
+impl<I, J> Iterator for CartesianProduct<I, J>
+where
+    I: Iterator,
+    I::Item: Clone,
+    J: Iterator + Clone,
+{
+    type Item = (I::Item, J::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(a) = &self.a_item {
+                if let Some(b) = self.b.next() {
+                    return Some((a.clone(), b));
+                }
+            }
+            self.a_item = Some(self.a.next()?);
+            self.b = self.b_start.clone();
+        }
+    }
+
+    // Whatever is left of 'b' for the current 'a', then all of 'b' for each 'a' still to come.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_lower, a_upper) = self.a.size_hint();
+        let (b_lower, b_upper) = self.b_start.size_hint();
+        let current = if self.a_item.is_some() {
+            self.b.size_hint()
+        } else {
+            (0, Some(0))
+        };
+
+        let rest_lower = a_lower.saturating_mul(b_lower);
+        let rest_upper = match (a_upper, b_upper) {
+            (Some(a), Some(b)) => a.checked_mul(b),
+            _ => None,
+        };
+        add_hints(current, (rest_lower, rest_upper))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::Counter;
+
+    // Checks size_hint against the real number of items left, before every call to next.
+    fn assert_exact_hints<I: Iterator + Clone>(mut iter: I) {
+        loop {
+            let left = iter.clone().count();
+            assert_eq!(iter.size_hint(), (left, Some(left)));
+            if iter.next().is_none() {
+                break;
+            }
+        }
+    }
+
+    // For adaptors that can only give bounds.
+    fn assert_hints_hold<I: Iterator + Clone>(mut iter: I) {
+        loop {
+            let left = iter.clone().count();
+            let (lower, upper) = iter.size_hint();
+            assert!(lower <= left, "lower bound {} > {}", lower, left);
+            assert!(
+                upper.is_none_or(|upper| left <= upper),
+                "upper bound {:?} < {}",
+                upper,
+                left
+            );
+            if iter.next().is_none() {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn counter_has_exact_hints() {
+        assert_exact_hints(Counter::new());
+    }
+
+    #[test]
+    fn chunks_exact() {
+        let mut chunks = Counter::new().chunks_exact(2);
+        assert_eq!(
+            chunks.by_ref().collect::<Vec<_>>(),
+            vec![vec![1, 2], vec![3, 4]]
+        );
+        assert_eq!(chunks.remainder(), &[5]);
+        assert_eq!(chunks.next(), None);
+        assert_eq!(chunks.remainder(), &[5]);
+
+        assert_exact_hints(Counter::new().chunks_exact(2));
+        assert_exact_hints(Counter::new().chunks_exact(5));
+        assert_exact_hints(Counter::new().chunks_exact(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be greater than 0")]
+    fn chunks_of_zero_panic() {
+        Counter::new().chunks_exact(0);
+    }
+
+    #[test]
+    fn windows() {
+        let windows: Vec<Vec<u32>> = Counter::new().windows(3).collect();
+        assert_eq!(windows, vec![vec![1, 2, 3], vec![2, 3, 4], vec![3, 4, 5]]);
+        assert_eq!(Counter::new().windows(6).next(), None);
+
+        for size in 1..=6 {
+            assert_exact_hints(Counter::new().windows(size));
+        }
+    }
+
+    #[test]
+    fn interleave() {
+        let items: Vec<u32> = Counter::new().interleave(Counter::new().skip(3)).collect();
+        assert_eq!(items, vec![1, 4, 2, 5, 3, 4, 5]);
+        let items: Vec<u32> = Counter::new().skip(3).interleave(Counter::new()).collect();
+        assert_eq!(items, vec![4, 1, 5, 2, 3, 4, 5]);
+
+        assert_exact_hints(Counter::new().interleave(Counter::new().skip(3)));
+        assert_exact_hints(Counter::new().interleave(vec![]));
+    }
+
+    #[test]
+    fn dedup_by_key() {
+        // Keys 0, 1, 1, 2, 2.
+        let items: Vec<u32> = Counter::new().dedup_by_key(|n| n / 2).collect();
+        assert_eq!(items, vec![1, 2, 4]);
+        let items: Vec<u32> = Counter::new().dedup_by_key(|_| ()).collect();
+        assert_eq!(items, vec![1]);
+
+        assert_hints_hold(Counter::new().dedup_by_key(|n| n / 2));
+        assert_hints_hold(Counter::new().dedup_by_key(|_| ()));
+        assert_eq!(
+            Counter::new().dedup_by_key(|n| *n).size_hint(),
+            (1, Some(5))
+        );
+    }
+
+    #[test]
+    fn group_by() {
+        let groups: Vec<(u32, Vec<u32>)> = Counter::new().group_by(|n| n / 2).collect();
+        assert_eq!(groups, vec![(0, vec![1]), (1, vec![2, 3]), (2, vec![4, 5])]);
+
+        // Separate runs of the same key are separate groups.
+        let groups: Vec<(bool, Vec<u32>)> = Counter::new().group_by(|&n| n == 3).collect();
+        assert_eq!(
+            groups,
+            vec![(false, vec![1, 2]), (true, vec![3]), (false, vec![4, 5])]
+        );
+
+        assert_hints_hold(Counter::new().group_by(|n| n / 2));
+        assert_hints_hold(Counter::new().group_by(|_| ()));
+    }
+
+    #[test]
+    fn batching() {
+        // Pairs added together, the last one on its own.
+        let sums: Vec<u32> = Counter::new()
+            .batching(|iter| iter.next().map(|a| a + iter.next().unwrap_or(0)))
+            .collect();
+        assert_eq!(sums, vec![3, 7, 5]);
+
+        // Each batch as long as its first item, e.g. [1], [2, 3].
+        let batches: Vec<Vec<u32>> = Counter::new()
+            .batching(|iter| {
+                let first = iter.next()?;
+                Some(
+                    std::iter::once(first)
+                        .chain(iter.take(first as usize - 1))
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(batches, vec![vec![1], vec![2, 3], vec![4, 5]]);
+
+        assert_hints_hold(Counter::new().batching(|iter| iter.next()));
+    }
+
+    #[test]
+    fn peeking_take_while() {
+        let mut iter = Counter::new().peekable();
+        let small: Vec<u32> = iter.peeking_take_while(|&n| n < 3).collect();
+        assert_eq!(small, vec![1, 2]);
+        // Unlike take_while, 3 is still there.
+        assert_eq!(iter.next(), Some(3));
+
+        let none: Vec<u32> = iter.peeking_take_while(|&n| n > 10).collect();
+        assert!(none.is_empty());
+        assert_eq!(iter.collect::<Vec<_>>(), vec![4, 5]);
+
+        let mut iter = Counter::new().peekable();
+        let take = iter.peeking_take_while(|&n| n < 3);
+        assert_eq!(take.size_hint(), (0, Some(5)));
+    }
+
+    #[test]
+    fn cartesian_product() {
+        let pairs: Vec<(u32, u32)> = Counter::new()
+            .take(2)
+            .cartesian_product(Counter::new().skip(2))
+            .collect();
+        assert_eq!(pairs, vec![(1, 3), (1, 4), (1, 5), (2, 3), (2, 4), (2, 5)]);
+        assert_eq!(
+            Counter::new().cartesian_product(Vec::<u32>::new()).next(),
+            None
+        );
+
+        assert_exact_hints(Counter::new().cartesian_product(Counter::new()));
+        assert_exact_hints(Counter::new().cartesian_product(Counter::new().skip(5)));
+        assert_exact_hints(Counter::new().skip(5).cartesian_product(Counter::new()));
+    }
+
+    #[test]
+    fn adaptors_chain_together() {
+        let sum: u32 = Counter::new()
+            .windows(2)
+            .map(|pair| pair[0] * pair[1])
+            .interleave(Counter::new())
+            .chunks_exact(3)
+            .map(|chunk| chunk.iter().sum::<u32>())
+            .sum();
+        // 2, 1, 6, 2, 12, 3, 20, 4, 5 in threes: 9 + 17 + 29.
+        assert_eq!(sum, 55);
+    }
+}
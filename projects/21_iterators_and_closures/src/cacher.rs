@@ -0,0 +1,220 @@
+// The book's Cacher with its two problems fixed (see main.rs):
+// 1).  It remembered one value whatever the argument, so value(2) after value(1) returned the
+//      result for 1.  Here the results are kept in a HashMap keyed by the argument.
+// 2).  It only worked for Fn(u32) -> u32.  Here the argument type K and result type V are generic
+//      parameters too.  K is the HashMap key so it needs Eq + Hash, and Clone because value()
+//      keeps a copy of it as well as passing it to the closure.  V is Clone so value() can hand
+//      back a copy and keep the original.
+//
+// A HashMap grows with every new argument, so a Cacher can be given a capacity.  Once it is full
+// the least recently used result is thrown away to make room (LRU eviction).
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub hits: u64,      // Answered from the cache.
+    pub misses: u64,    // The closure had to be called.
+    pub evictions: u64, // Results thrown away to stay within the capacity.
+}
+
+impl CacheStats {
+    // The fraction of calls answered from the cache, 0.0 before any calls.
+    pub fn hit_rate(&self) -> f64 {
+        let calls = self.hits + self.misses;
+        if calls == 0 {
+            0.0
+        } else {
+            self.hits as f64 / calls as f64
+        }
+    }
+}
+
+pub struct Cacher<F, K, V>
+where
+    F: Fn(K) -> V,
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    calculation: F,
+    values: HashMap<K, (V, u64)>, // The result and when it was last used.
+    // The keys by when they were last used, oldest first, so the LRU key is the first one.
+    last_used: BTreeMap<u64, K>,
+    clock: u64, // Goes up by one on every call.
+    capacity: Option<usize>,
+    stats: CacheStats,
+}
+
+impl<F, K, V> Cacher<F, K, V>
+where
+    F: Fn(K) -> V,
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    // Keeps every result.
+    pub fn new(calculation: F) -> Cacher<F, K, V> {
+        Cacher {
+            calculation,
+            values: HashMap::new(),
+            last_used: BTreeMap::new(),
+            clock: 0,
+            capacity: None,
+            stats: CacheStats::default(),
+        }
+    }
+
+    // Keeps at most 'capacity' results.  With a capacity of 0 nothing is kept and every call
+    // runs the closure.
+    pub fn with_capacity(calculation: F, capacity: usize) -> Cacher<F, K, V> {
+        Cacher {
+            capacity: Some(capacity),
+            ..Cacher::new(calculation)
+        }
+    }
+
+    pub fn value(&mut self, arg: K) -> V {
+        self.clock += 1;
+        let now = self.clock;
+
+        if let Some((v, used)) = self.values.get_mut(&arg) {
+            self.stats.hits += 1;
+            // Move the key to the back of the queue.
+            self.last_used.remove(used);
+            self.last_used.insert(now, arg);
+            *used = now;
+            return v.clone();
+        }
+
+        self.stats.misses += 1;
+        let v = (self.calculation)(arg.clone());
+        if self.capacity == Some(0) {
+            return v;
+        }
+        if Some(self.values.len()) == self.capacity {
+            self.evict();
+        }
+        self.last_used.insert(now, arg.clone());
+        self.values.insert(arg, (v.clone(), now));
+        v
+    }
+
+    fn evict(&mut self) {
+        let oldest = self.last_used.keys().next().copied();
+        if let Some(key) = oldest.and_then(|used| self.last_used.remove(&used)) {
+            self.values.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+
+    pub fn contains(&self, arg: &K) -> bool {
+        self.values.contains_key(arg)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    // Forgets every result, the statistics are kept.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.last_used.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn each_argument_gets_its_own_result() {
+        let mut c = Cacher::new(|a: u32| a * 10);
+        assert_eq!(c.value(1), 10);
+        assert_eq!(c.value(2), 20);
+        assert_eq!(c.value(1), 10);
+        assert_eq!(c.len(), 2);
+    }
+
+    #[test]
+    fn the_closure_runs_once_per_argument() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|s: String| {
+            calls.set(calls.get() + 1);
+            s.len()
+        });
+
+        for word in &["apple", "pear", "apple", "apple", "pear", "fig"] {
+            c.value(word.to_string());
+        }
+        assert_eq!(calls.get(), 3);
+        assert_eq!(
+            c.stats(),
+            CacheStats {
+                hits: 3,
+                misses: 3,
+                evictions: 0
+            }
+        );
+        assert_eq!(c.stats().hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn the_least_recently_used_result_is_evicted() {
+        let mut c = Cacher::with_capacity(|a: u32| a + 1, 2);
+        c.value(1);
+        c.value(2);
+        c.value(1); // 2 is now the least recently used.
+        c.value(3);
+
+        assert!(c.contains(&1));
+        assert!(!c.contains(&2));
+        assert!(c.contains(&3));
+        assert_eq!(c.len(), 2);
+        assert_eq!(c.stats().evictions, 1);
+
+        c.value(2);
+        assert!(!c.contains(&1));
+        assert_eq!(c.stats().misses, 4);
+    }
+
+    #[test]
+    fn capacity_zero_caches_nothing() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::with_capacity(
+            |a: u32| {
+                calls.set(calls.get() + 1);
+                a
+            },
+            0,
+        );
+        assert_eq!(c.value(7), 7);
+        assert_eq!(c.value(7), 7);
+        assert_eq!(calls.get(), 2);
+        assert!(c.is_empty());
+        assert_eq!(c.stats().hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn clear_forgets_results_but_keeps_stats() {
+        let mut c = Cacher::new(|a: (u8, u8)| a.0 + a.1);
+        c.value((1, 2));
+        c.value((1, 2));
+        c.clear();
+        assert!(!c.contains(&(1, 2)));
+        c.value((1, 2));
+        assert_eq!(c.stats().misses, 2);
+        assert_eq!(c.stats().hits, 1);
+    }
+}
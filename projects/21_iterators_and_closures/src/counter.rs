@@ -0,0 +1,38 @@
+// Implementing iterators on our own types by implementing the Iterator trait.
+// Counts from 1 to 5.
+#[derive(Clone)]
+pub struct Counter {
+    count: u32,
+}
+
+impl Counter {
+    pub fn new() -> Counter {
+        Counter { count: 0 }
+    }
+}
+
+impl Default for Counter {
+    fn default() -> Counter {
+        Counter::new()
+    }
+}
+
+impl Iterator for Counter {
+    type Item = u32; // Associated type.
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.count < 5 {
+            self.count += 1;
+            Some(self.count)
+        } else {
+            None
+        }
+    }
+
+    // Optional, the default is (0, None): at least 0 items and no idea of the most.  We know
+    // exactly how many are left, and adaptors like those in adaptors.rs can pass that on.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = (5 - self.count) as usize;
+        (left, Some(left))
+    }
+}
@@ -0,0 +1,487 @@
+// shoes_in_my_size from main.rs grown into a small in-memory query engine.
+//
+// A Query says which shoes to match and is built up from simple predicates with and, or and not
+// (or &, | and !):
+//
+//   let query = Query::size_between(9, 11) & Query::style_contains("boot") & !Query::size(10);
+//
+// Inventory::search runs a query and then sorts and pages the matches, all with iterator
+// adaptors over the shoes rather than loops.  count_by_style adds up the matches per style.
+//
+// An inventory can be saved to and loaded from a CSV file (a 'size,style' header, then one shoe
+// per line) or a JSON file (an array of {"size": 10, "style": "boot"} objects).  The format is
+// picked from the file's extension.
+use serde_derive::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, Not};
+use std::path::Path;
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct Shoe {
+    pub size: u32,
+    pub style: String,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Query {
+    All,
+    SizeBetween(u32, u32), // Inclusive at both ends.
+    StyleContains(String), // Ignoring case.
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+}
+
+impl Query {
+    pub fn size(size: u32) -> Query {
+        Query::SizeBetween(size, size)
+    }
+
+    pub fn size_between(min: u32, max: u32) -> Query {
+        Query::SizeBetween(min, max)
+    }
+
+    pub fn style_contains(text: &str) -> Query {
+        Query::StyleContains(text.to_string())
+    }
+
+    pub fn and(self, other: Query) -> Query {
+        Query::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Query) -> Query {
+        Query::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn matches(&self, shoe: &Shoe) -> bool {
+        match self {
+            Query::All => true,
+            Query::SizeBetween(min, max) => *min <= shoe.size && shoe.size <= *max,
+            Query::StyleContains(text) => shoe.style.to_lowercase().contains(&text.to_lowercase()),
+            Query::And(a, b) => a.matches(shoe) && b.matches(shoe),
+            Query::Or(a, b) => a.matches(shoe) || b.matches(shoe),
+            Query::Not(query) => !query.matches(shoe),
+        }
+    }
+}
+
+impl BitAnd for Query {
+    type Output = Query;
+
+    fn bitand(self, other: Query) -> Query {
+        self.and(other)
+    }
+}
+
+impl BitOr for Query {
+    type Output = Query;
+
+    fn bitor(self, other: Query) -> Query {
+        self.or(other)
+    }
+}
+
+impl Not for Query {
+    type Output = Query;
+
+    fn not(self) -> Query {
+        Query::Not(Box::new(self))
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Sort {
+    Unsorted, // The order the shoes were added.
+    SizeAscending,
+    SizeDescending,
+    StyleAscending,
+    StyleDescending,
+}
+
+// Pages are numbered from 1.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Page {
+    pub number: usize,
+    pub per_page: usize,
+}
+
+impl Page {
+    // Every match on one page.
+    pub fn all() -> Page {
+        Page {
+            number: 1,
+            per_page: usize::MAX,
+        }
+    }
+
+    pub fn new(number: usize, per_page: usize) -> Result<Page, &'static str> {
+        if number == 0 {
+            return Err("pages are numbered from 1");
+        }
+        if per_page == 0 {
+            return Err("a page must hold at least one shoe");
+        }
+        Ok(Page { number, per_page })
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct SearchResults<'a> {
+    pub shoes: Vec<&'a Shoe>, // The shoes on the page asked for.
+    pub total: usize,         // The number of matches on all pages.
+    pub page: Page,
+}
+
+impl SearchResults<'_> {
+    pub fn pages(&self) -> usize {
+        self.total.div_ceil(self.page.per_page)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FileFormat {
+    Csv,
+    Json,
+}
+
+impl FileFormat {
+    pub fn from_path(path: &Path) -> Result<FileFormat, String> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        match extension.to_ascii_lowercase().as_str() {
+            "csv" => Ok(FileFormat::Csv),
+            "json" => Ok(FileFormat::Json),
+            _ => Err(format!("{}: expected a .csv or .json file", path.display())),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Inventory {
+    shoes: Vec<Shoe>,
+}
+
+impl Inventory {
+    pub fn new() -> Inventory {
+        Inventory { shoes: Vec::new() }
+    }
+
+    pub fn add(&mut self, shoe: Shoe) {
+        self.shoes.push(shoe);
+    }
+
+    pub fn len(&self) -> usize {
+        self.shoes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shoes.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Shoe> {
+        self.shoes.iter()
+    }
+
+    // The matching shoes in the order they were added, without sorting or paging.
+    // The shoes borrow from the inventory, not the query, so they can outlive the query.
+    pub fn filter<'a, 'q>(&'a self, query: &'q Query) -> impl Iterator<Item = &'a Shoe> + 'q
+    where
+        'a: 'q,
+    {
+        self.shoes.iter().filter(move |shoe| query.matches(shoe))
+    }
+
+    pub fn search(&self, query: &Query, sort: Sort, page: Page) -> SearchResults<'_> {
+        let mut matches: Vec<&Shoe> = self.filter(query).collect();
+
+        // sort_by is stable, so shoes that compare equal stay in the order they were added.
+        match sort {
+            Sort::Unsorted => {}
+            Sort::SizeAscending => matches.sort_by_key(|shoe| shoe.size),
+            Sort::SizeDescending => matches.sort_by_key(|shoe| Reverse(shoe.size)),
+            Sort::StyleAscending => matches.sort_by(|a, b| a.style.cmp(&b.style)),
+            Sort::StyleDescending => matches.sort_by(|a, b| b.style.cmp(&a.style)),
+        }
+
+        let total = matches.len();
+        let shoes = matches
+            .into_iter()
+            .skip((page.number - 1).saturating_mul(page.per_page))
+            .take(page.per_page)
+            .collect();
+        SearchResults { shoes, total, page }
+    }
+
+    // The number of matching shoes of each style, in alphabetical order of style.
+    pub fn count_by_style(&self, query: &Query) -> BTreeMap<String, usize> {
+        self.filter(query)
+            .fold(BTreeMap::new(), |mut counts, shoe| {
+                *counts.entry(shoe.style.clone()).or_insert(0) += 1;
+                counts
+            })
+    }
+
+    pub fn from_csv<R: Read>(reader: R) -> Result<Inventory, String> {
+        csv::Reader::from_reader(reader)
+            .deserialize()
+            .enumerate()
+            .map(|(i, shoe)| shoe.map_err(|e| format!("row {}: {}", i + 1, e)))
+            .collect()
+    }
+
+    pub fn to_csv<W: Write>(&self, writer: W) -> Result<(), String> {
+        let mut writer = csv::Writer::from_writer(writer);
+        for shoe in &self.shoes {
+            writer.serialize(shoe).map_err(|e| e.to_string())?;
+        }
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    pub fn from_json<R: Read>(reader: R) -> Result<Inventory, String> {
+        let shoes: Vec<Shoe> = serde_json::from_reader(reader).map_err(|e| e.to_string())?;
+        Ok(Inventory { shoes })
+    }
+
+    pub fn to_json<W: Write>(&self, writer: W) -> Result<(), String> {
+        serde_json::to_writer_pretty(writer, &self.shoes).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Inventory, String> {
+        let format = FileFormat::from_path(path)?;
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let reader = BufReader::new(file);
+        match format {
+            FileFormat::Csv => Inventory::from_csv(reader),
+            FileFormat::Json => Inventory::from_json(reader),
+        }
+        .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let format = FileFormat::from_path(path)?;
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        match format {
+            FileFormat::Csv => self.to_csv(&mut writer),
+            FileFormat::Json => self.to_json(&mut writer),
+        }
+        .and_then(|_| writer.flush().map_err(|e| e.to_string()))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl FromIterator<Shoe> for Inventory {
+    fn from_iter<I: IntoIterator<Item = Shoe>>(iter: I) -> Inventory {
+        Inventory {
+            shoes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Inventory {
+    type Item = &'a Shoe;
+    type IntoIter = std::slice::Iter<'a, Shoe>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.shoes.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn shoe(size: u32, style: &str) -> Shoe {
+        Shoe {
+            size,
+            style: String::from(style),
+        }
+    }
+
+    fn inventory() -> Inventory {
+        vec![
+            shoe(10, "sneaker"),
+            shoe(13, "sandal"),
+            shoe(10, "boot"),
+            shoe(9, "Ankle Boot"),
+            shoe(11, "sneaker"),
+            shoe(12, "boot"),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn sizes(shoes: &[&Shoe]) -> Vec<u32> {
+        shoes.iter().map(|shoe| shoe.size).collect()
+    }
+
+    #[test]
+    fn simple_predicates() {
+        let inventory = inventory();
+        let matches: Vec<&Shoe> = inventory.filter(&Query::size(10)).collect();
+        assert_eq!(matches, vec![&shoe(10, "sneaker"), &shoe(10, "boot")]);
+
+        let matches: Vec<&Shoe> = inventory.filter(&Query::size_between(11, 12)).collect();
+        assert_eq!(sizes(&matches), vec![11, 12]);
+
+        // Case doesn't matter on either side.
+        let matches: Vec<&Shoe> = inventory.filter(&Query::style_contains("BOOT")).collect();
+        assert_eq!(sizes(&matches), vec![10, 9, 12]);
+
+        assert_eq!(inventory.filter(&Query::All).count(), 6);
+        assert_eq!(inventory.filter(&Query::size_between(12, 11)).count(), 0);
+    }
+
+    #[test]
+    fn combined_predicates() {
+        let inventory = inventory();
+        let boots_9_to_11 = Query::size_between(9, 11) & Query::style_contains("boot");
+        let matches: Vec<&Shoe> = inventory.filter(&boots_9_to_11).collect();
+        assert_eq!(sizes(&matches), vec![10, 9]);
+
+        let sandals_or_13 = Query::style_contains("sandal") | Query::size(12);
+        let matches: Vec<&Shoe> = inventory.filter(&sandals_or_13).collect();
+        assert_eq!(sizes(&matches), vec![13, 12]);
+
+        let not_sneakers = !Query::style_contains("sneaker");
+        assert_eq!(inventory.filter(&not_sneakers).count(), 4);
+
+        // The methods build the same queries as the operators.
+        assert_eq!(
+            Query::size(1).and(Query::size(2)).or(Query::All),
+            (Query::size(1) & Query::size(2)) | Query::All
+        );
+    }
+
+    #[test]
+    fn sorting() {
+        let inventory = inventory();
+        let results = inventory.search(&Query::All, Sort::SizeAscending, Page::all());
+        assert_eq!(sizes(&results.shoes), vec![9, 10, 10, 11, 12, 13]);
+        // Stable: the two 10s are in the order they were added.
+        assert_eq!(results.shoes[1].style, "sneaker");
+
+        let results = inventory.search(&Query::All, Sort::SizeDescending, Page::all());
+        assert_eq!(sizes(&results.shoes), vec![13, 12, 11, 10, 10, 9]);
+
+        let results = inventory.search(&Query::size(10), Sort::StyleAscending, Page::all());
+        assert_eq!(results.shoes, vec![&shoe(10, "boot"), &shoe(10, "sneaker")]);
+
+        let results = inventory.search(&Query::size(10), Sort::StyleDescending, Page::all());
+        assert_eq!(results.shoes, vec![&shoe(10, "sneaker"), &shoe(10, "boot")]);
+
+        let results = inventory.search(&Query::All, Sort::Unsorted, Page::all());
+        assert_eq!(sizes(&results.shoes), vec![10, 13, 10, 9, 11, 12]);
+    }
+
+    #[test]
+    fn pagination() {
+        let inventory = inventory();
+        let page = |number| {
+            inventory.search(
+                &Query::All,
+                Sort::SizeAscending,
+                Page::new(number, 4).unwrap(),
+            )
+        };
+
+        let first = page(1);
+        assert_eq!(sizes(&first.shoes), vec![9, 10, 10, 11]);
+        assert_eq!(first.total, 6);
+        assert_eq!(first.pages(), 2);
+        assert_eq!(sizes(&page(2).shoes), vec![12, 13]);
+        assert!(page(3).shoes.is_empty());
+        assert_eq!(page(3).total, 6);
+
+        let none = inventory.search(&Query::size(1), Sort::Unsorted, Page::new(1, 4).unwrap());
+        assert_eq!(none.pages(), 0);
+        assert_eq!(
+            inventory
+                .search(&Query::All, Sort::Unsorted, Page::all())
+                .pages(),
+            1
+        );
+
+        assert_eq!(Page::new(0, 10), Err("pages are numbered from 1"));
+        assert_eq!(Page::new(1, 0), Err("a page must hold at least one shoe"));
+    }
+
+    #[test]
+    fn counts_by_style() {
+        let inventory = inventory();
+        let counts = inventory.count_by_style(&Query::All);
+        let expected: Vec<(&str, usize)> = vec![
+            ("Ankle Boot", 1),
+            ("boot", 2),
+            ("sandal", 1),
+            ("sneaker", 2),
+        ];
+        assert_eq!(
+            counts
+                .iter()
+                .map(|(style, n)| (style.as_str(), *n))
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        let counts = inventory.count_by_style(&Query::size_between(10, 11));
+        assert_eq!(counts.get("sneaker"), Some(&2));
+        assert_eq!(counts.get("boot"), Some(&1));
+        assert_eq!(counts.len(), 2);
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let inventory = inventory();
+        let mut csv = Vec::new();
+        inventory.to_csv(&mut csv).unwrap();
+        let text = String::from_utf8(csv.clone()).unwrap();
+        assert!(text.starts_with("size,style\n10,sneaker\n"));
+        assert_eq!(Inventory::from_csv(&csv[..]).unwrap(), inventory);
+
+        // Commas in a style are quoted.
+        let awkward: Inventory = vec![shoe(8, "boot, \"hiking\"")].into_iter().collect();
+        let mut csv = Vec::new();
+        awkward.to_csv(&mut csv).unwrap();
+        assert_eq!(Inventory::from_csv(&csv[..]).unwrap(), awkward);
+
+        let err = Inventory::from_csv("size,style\n10,boot\nbig,boot\n".as_bytes()).unwrap_err();
+        assert!(err.starts_with("row 2:"), "{}", err);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let inventory = inventory();
+        let mut json = Vec::new();
+        inventory.to_json(&mut json).unwrap();
+        assert_eq!(Inventory::from_json(&json[..]).unwrap(), inventory);
+
+        let inventory = Inventory::from_json(r#"[{"size": 7, "style": "slipper"}]"#.as_bytes());
+        assert_eq!(inventory.unwrap().iter().next(), Some(&shoe(7, "slipper")));
+        assert!(Inventory::from_json(r#"[{"size": "seven"}]"#.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn load_and_save_files() {
+        let dir = env::temp_dir().join(format!("iterators_and_closures-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let inventory = inventory();
+
+        for name in &["shoes.csv", "shoes.JSON"] {
+            let path = dir.join(name);
+            inventory.save(&path).unwrap();
+            assert_eq!(Inventory::load(&path).unwrap(), inventory);
+        }
+
+        let err = inventory.save(&dir.join("shoes.txt")).unwrap_err();
+        assert!(err.ends_with("expected a .csv or .json file"), "{}", err);
+        assert!(Inventory::load(&dir.join("missing.csv")).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,6 @@
+// The longer examples from main.rs, in a library so they can have tests of their own.
+pub mod adaptors;
+pub mod cacher;
+pub mod counter;
+pub mod inventory;
+pub mod memoizer;
@@ -1,10 +1,12 @@
 // Closures - anonymous functions that capture their environment.
+#![allow(clippy::useless_vec)] // The iterator examples below are about iterating over a Vec.
 fn main() {
     let simulated_random_number = 7;
     let simulated_user_specified_value = 10;
     generate_workout(simulated_user_specified_value, simulated_random_number);
     generate_workout_using_closure(simulated_user_specified_value, simulated_random_number);
     generate_workout_with_lazy_evaluation(simulated_user_specified_value, simulated_random_number);
+    expensive_calculation_shared_between_threads(simulated_user_specified_value);
 
     closure_with_forced_move();
 
@@ -15,9 +17,11 @@ fn main() {
     methods_that_consume_iterator();
     methods_that_produce_other_iterators();
     using_the_collect_method();
+    querying_an_inventory();
 
     calling_next_directly_on_our_own_type();
     using_other_iterator_trait_methods();
+    using_our_own_iterator_adaptors();
 }
 
 use std::thread;
@@ -105,39 +109,38 @@ fn closures_look_like_functions() {
 // fields.
 // Each closure 'instance' has its own unique anonymous type; even if two closures use the same
 // signature, their types are considered different.
-struct Cacher<T>
-    where T: Fn(u32) -> u32 // The trait bounds on T specify that its a closure using the Fn trait.
-{
-    calculation: T,     // This is like a function pointer, in this case with a signature taking a
-                        // u32 and returning a u32...  T: Fn(u32) -> u32                         
-    value: Option<u32>
-}
-
+//
+// The book's version looked like this:
+//
+//   struct Cacher<T>
+//       where T: Fn(u32) -> u32 // The trait bounds on T specify that its a closure using the Fn trait.
+//   {
+//       calculation: T,     // This is like a function pointer, in this case with a signature taking a
+//                           // u32 and returning a u32...  T: Fn(u32) -> u32
+//       value: Option<u32>
+//   }
+//
 // When code using Cacher asks for the result of the closure, the Cacher will execute the closure at
 // that time and store the result within a Some variant in the value field.  If the code asked for
 // the result of the closure again, the Cacher will return the result but not execute the closure
-// again.
-impl<T> Cacher<T>
-    where T: Fn(u32) -> u32 // Like a function pointer
-{
-    fn new(calculation: T) -> Cacher<T> {
-        Cacher {
-            calculation,
-            value: None // The value before we execute the closure will be None.
-        }
-    }
-
-    fn value(&mut self, arg: u32) -> u32 {
-        match self.value {
-            Some(v) => v, // Check if we have already made the calculation.
-            None => {
-                let v = (self.calculation)(arg); // Fn(u32) -> u32 (a bit like a c++ member fn ptr).
-                self.value = Some(v); // Store the result.
-                v
-            }
-        }
-    }
-}
+// again:
+//
+//   fn value(&mut self, arg: u32) -> u32 {
+//       match self.value {
+//           Some(v) => v, // Check if we have already made the calculation.
+//           None => {
+//               let v = (self.calculation)(arg); // Fn(u32) -> u32 (a bit like a c++ member fn ptr).
+//               self.value = Some(v); // Store the result.
+//               v
+//           }
+//       }
+//   }
+//
+// The Cacher used below is generic over the argument and result types and keeps one result per
+// argument in a HashMap, see src/cacher.rs.
+use iterators_and_closures::cacher::Cacher;
+use iterators_and_closures::memoizer::Memoizer;
+use std::sync::Arc;
 
 fn generate_workout_with_lazy_evaluation(intensity: u32, random_number: u32) {
 
@@ -161,27 +164,46 @@ fn generate_workout_with_lazy_evaluation(intensity: u32, random_number: u32) {
     }
 }
 
+// Cacher needs '&mut self' so it can't be shared between threads.  Memoizer can (see
+// src/memoizer.rs): all four threads ask for the same intensity at once but the slow calculation
+// only runs once, the other three wait for its result.
+fn expensive_calculation_shared_between_threads(intensity: u32) {
+    let memoizer = Arc::new(Memoizer::new(simulated_expensive_calculation));
+
+    let handles: Vec<_> = (0..4).map(|n| {
+        let memoizer = Arc::clone(&memoizer);
+        thread::spawn(move || {
+            println!("Thread {} got {}", n, memoizer.get(intensity));
+        })
+    }).collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    println!("Calculated {} time(s)", memoizer.stats().misses);
+}
+
 #[test]
 #[allow(unused_variables)]
 fn call_with_different_values() {
 
-    // One problem with that above code is that the code assumes it will get the same value for the
+    // One problem with the book's code is that the code assumes it will get the same value for the
     // parameter 'arg' to the 'value' method.
-    // The test below will fail.
+    // The test below failed with it, v2 was the cached result for 1.
     let mut c = Cacher::new(|a| a);
 
     let v1 = c.value(1);
     let v2 = c.value(2); // See implementation of Cacher::value.  
-    // A potential resolution to this would be to store a hash map and return the value if its 
-    // present.
+    // The resolution is to store a hash map and return the value if its present, which Cacher now
+    // does.
 
-    assert_eq!(v2, 2);  // FAILS!
+    assert_eq!(v2, 2);
 }
 
-// Another problem with the aboce code is that we are tied into one parameter of u32 and a return
+// Another problem with the book's code is that we are tied into one parameter of u32 and a return
 // value of u32.
-// To address this we can introduce more generic parameters to increase the flexibility of the 
-// Cacher functionality.
+// To address this Cacher has more generic parameters, Cacher<F, K, V>, to increase the
+// flexibility of the Cacher functionality.
 
 // Capturing the environment with closures.
 // ----------------------------------------
@@ -339,11 +361,8 @@ fn using_the_collect_method() {
     assert_eq!(v2, vec![2, 3, 4]);
 }
 
-#[derive(PartialEq, Debug)]
-struct Shoe {
-    size: u32,
-    style: String
-}
+// Shoe lives in src/inventory.rs, which builds a query engine on the same idea.
+use iterators_and_closures::inventory::Shoe;
 
 #[allow(dead_code)]
 fn shoes_in_my_size(shoes: Vec<Shoe>, shoe_size: u32) -> Vec<Shoe> {
@@ -358,6 +377,23 @@ fn shoes_in_my_size(shoes: Vec<Shoe>, shoe_size: u32) -> Vec<Shoe> {
         .collect()
 }
 
+// The same filter as shoes_in_my_size, and more, with a Query.
+use iterators_and_closures::inventory::{Inventory, Page, Query, Sort};
+
+fn querying_an_inventory() {
+    let inventory: Inventory = vec![
+        Shoe { size: 10, style: String::from("sneaker") },
+        Shoe { size: 13, style: String::from("sandal") },
+        Shoe { size: 10, style: String::from("boot") },
+        Shoe { size: 11, style: String::from("ankle boot") },
+    ].into_iter().collect();
+
+    let query = Query::size_between(10, 11) & !Query::style_contains("sneaker");
+    let results = inventory.search(&query, Sort::SizeDescending, Page::all());
+    println!("{} of {} shoes match: {:?}", results.total, inventory.len(), results.shoes);
+    println!("Shoes by style: {:?}", inventory.count_by_style(&Query::All));
+}
+
 #[test]
 fn filters_by_size() {
     let shoes = vec![
@@ -377,28 +413,8 @@ fn filters_by_size() {
 }
 
 // Implementing iterators on our own types by implementing the Iterator trait.
-struct Counter {
-    count: u32
-}
-
-impl Counter {
-    fn new() -> Counter {
-        Counter { count: 0 }
-    }
-}
-
-impl Iterator for Counter {
-    type Item = u32; // Associated type.
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.count < 5 {
-            self.count += 1;
-            Some(self.count)
-        } else {
-            None
-        }
-    }
-}
+// Counter lives in src/counter.rs so the adaptors in src/adaptors.rs can be tested with it.
+use iterators_and_closures::counter::Counter;
 
 fn calling_next_directly_on_our_own_type() {
 
@@ -420,3 +436,19 @@ fn using_other_iterator_trait_methods() {
     
     assert_eq!(18, sum);
 }
+
+// Our own adaptors, added to every iterator by the IteratorExt trait in src/adaptors.rs.
+use iterators_and_closures::adaptors::IteratorExt;
+
+fn using_our_own_iterator_adaptors() {
+    let windows: Vec<Vec<u32>> = Counter::new().windows(2).collect();
+    assert_eq!(windows, vec![vec![1, 2], vec![2, 3], vec![3, 4], vec![4, 5]]);
+
+    let groups: Vec<(bool, Vec<u32>)> = Counter::new().group_by(|x| x % 2 == 0).collect();
+    println!("Grouped by evenness: {:?}", groups);
+
+    let mut counter = Counter::new().peekable();
+    let small: Vec<u32> = counter.peeking_take_while(|&x| x < 3).collect();
+    assert_eq!(small, vec![1, 2]);
+    assert_eq!(counter.next(), Some(3));
+}
@@ -0,0 +1,376 @@
+// A Cacher that can be shared between threads.
+//
+// Cacher needs '&mut self' for every call, so one thread at a time.  Memoizer takes '&self' and
+// is Send + Sync, so it can go in an Arc and be used by many threads at once:
+// 1).  Sharded locks.  The results are split over several HashMaps, each behind its own Mutex,
+//      and a key's hash picks the shard.  Threads asking for keys in different shards don't wait
+//      for each other.
+// 2).  Time to live.  A result can be given an expiry time, after which the next call runs the
+//      closure again.
+// 3).  Single flight.  While one thread is running the closure for a key, other threads asking
+//      for the same key wait for its result instead of running the closure themselves.  So an
+//      expensive calculation is only ever in progress once per key.
+//
+// The closure is never run with a lock held, so a slow calculation only holds up the callers
+// waiting for that same key.
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::cacher::CacheStats;
+
+const DEFAULT_SHARDS: usize = 16;
+
+enum Slot<V> {
+    Ready { value: V, expires: Option<Instant> },
+    Pending(Arc<Flight<V>>), // The closure is running for this key.
+}
+
+type Shard<K, V> = Mutex<HashMap<K, Slot<V>>>;
+
+pub struct Memoizer<F, K, V>
+where
+    F: Fn(K) -> V,
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    calculation: F,
+    shards: Vec<Shard<K, V>>,
+    hasher: RandomState,
+    ttl: Option<Duration>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64, // Expired results replaced or purged.
+}
+
+impl<F, K, V> Memoizer<F, K, V>
+where
+    F: Fn(K) -> V,
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    // Keeps results forever, in 16 shards.
+    pub fn new(calculation: F) -> Memoizer<F, K, V> {
+        Memoizer {
+            calculation,
+            shards: (0..DEFAULT_SHARDS)
+                .map(|_| Mutex::new(HashMap::new()))
+                .collect(),
+            hasher: RandomState::new(),
+            ttl: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    // Results are recalculated once they are older than 'ttl'.
+    pub fn with_ttl(mut self, ttl: Duration) -> Memoizer<F, K, V> {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    // More shards means less waiting for locks, 0 is taken as 1.
+    pub fn with_shards(mut self, shards: usize) -> Memoizer<F, K, V> {
+        self.shards = (0..shards.max(1))
+            .map(|_| Mutex::new(HashMap::new()))
+            .collect();
+        self
+    }
+
+    fn shard(&self, key: &K) -> &Shard<K, V> {
+        &self.shards[self.hasher.hash_one(key) as usize % self.shards.len()]
+    }
+
+    pub fn get(&self, key: K) -> V {
+        let shard = self.shard(&key);
+        loop {
+            let mut map = lock(shard);
+            match map.get(&key) {
+                Some(Slot::Ready { value, expires }) if !is_expired(*expires) => {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return value.clone();
+                }
+                Some(Slot::Ready { .. }) => {
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+                Some(Slot::Pending(flight)) => {
+                    let flight = Arc::clone(flight);
+                    drop(map);
+                    match flight.wait() {
+                        Some(value) => {
+                            self.hits.fetch_add(1, Ordering::Relaxed);
+                            return value;
+                        }
+                        None => continue, // The closure panicked, have another go.
+                    }
+                }
+                None => {}
+            }
+
+            // This thread runs the closure, anyone else asking for 'key' meanwhile will wait.
+            let flight = Arc::new(Flight::new());
+            map.insert(key.clone(), Slot::Pending(Arc::clone(&flight)));
+            drop(map);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+
+            let mut guard = AbandonOnPanic {
+                shard,
+                key: key.clone(),
+                flight: Arc::clone(&flight),
+                armed: true,
+            };
+            let value = (self.calculation)(key.clone());
+            guard.armed = false;
+
+            let expires = self.ttl.map(|ttl| Instant::now() + ttl);
+            lock(shard).insert(
+                key,
+                Slot::Ready {
+                    value: value.clone(),
+                    expires,
+                },
+            );
+            flight.finish(FlightState::Done(value.clone()));
+            return value;
+        }
+    }
+
+    // Removes every expired result.  They are replaced when asked for anyway, this just frees
+    // the memory of ones that won't be asked for again.
+    pub fn purge_expired(&self) {
+        for shard in &self.shards {
+            lock(shard).retain(|_, slot| match slot {
+                Slot::Ready { expires, .. } if is_expired(*expires) => {
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                    false
+                }
+                _ => true,
+            });
+        }
+    }
+
+    // The number of results held, including expired ones not yet replaced or purged.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                lock(shard)
+                    .values()
+                    .filter(|slot| matches!(slot, Slot::Ready { .. }))
+                    .count()
+            })
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Callers that waited for another thread's result count as hits.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn is_expired(expires: Option<Instant>) -> bool {
+    expires.is_some_and(|expires| Instant::now() >= expires)
+}
+
+// The closure never runs with a lock held, so a lock can only be poisoned by a panic in our own
+// code between lock and unlock.  The maps are still consistent then, so carry on.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+enum FlightState<V> {
+    Running,
+    Done(V),
+    Abandoned, // The closure panicked.
+}
+
+// One run of the closure that other threads can wait on.
+struct Flight<V> {
+    state: Mutex<FlightState<V>>,
+    finished: Condvar,
+}
+
+impl<V: Clone> Flight<V> {
+    fn new() -> Flight<V> {
+        Flight {
+            state: Mutex::new(FlightState::Running),
+            finished: Condvar::new(),
+        }
+    }
+
+    fn finish(&self, state: FlightState<V>) {
+        *lock(&self.state) = state;
+        self.finished.notify_all();
+    }
+
+    // The result, or None if the closure panicked.
+    fn wait(&self) -> Option<V> {
+        let mut state = lock(&self.state);
+        loop {
+            match &*state {
+                FlightState::Running => {
+                    state = self
+                        .finished
+                        .wait(state)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                }
+                FlightState::Done(value) => return Some(value.clone()),
+                FlightState::Abandoned => return None,
+            }
+        }
+    }
+}
+
+// If the closure panics, this removes the Pending slot and wakes the waiting threads so they can
+// try again, rather than leaving them waiting forever.
+struct AbandonOnPanic<'a, K: Eq + Hash, V: Clone> {
+    shard: &'a Shard<K, V>,
+    key: K,
+    flight: Arc<Flight<V>>,
+    armed: bool,
+}
+
+impl<K: Eq + Hash, V: Clone> Drop for AbandonOnPanic<'_, K, V> {
+    fn drop(&mut self) {
+        if self.armed {
+            lock(self.shard).remove(&self.key);
+            self.flight.finish(FlightState::Abandoned);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Barrier;
+    use std::thread;
+
+    // Runs 'get' for each key in 'keys' on its own thread, all starting together.
+    fn get_from_threads<F>(memoizer: &Arc<Memoizer<F, u32, u32>>, keys: &[u32]) -> Vec<u32>
+    where
+        F: Fn(u32) -> u32 + Send + Sync + 'static,
+    {
+        let barrier = Arc::new(Barrier::new(keys.len()));
+        let handles: Vec<_> = keys
+            .iter()
+            .map(|&key| {
+                let memoizer = Arc::clone(memoizer);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    memoizer.get(key)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    }
+
+    fn slow_square(calls: &Arc<AtomicUsize>) -> impl Fn(u32) -> u32 + Send + Sync + 'static {
+        let calls = Arc::clone(calls);
+        move |n| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(100));
+            n * n
+        }
+    }
+
+    #[test]
+    fn concurrent_callers_share_one_calculation() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let memoizer = Arc::new(Memoizer::new(slow_square(&calls)));
+
+        let results = get_from_threads(&memoizer, &[7; 8]);
+        assert_eq!(results, vec![49; 8]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(memoizer.stats().misses, 1);
+        assert_eq!(memoizer.stats().hits, 7);
+    }
+
+    #[test]
+    fn each_key_is_calculated_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let memoizer = Arc::new(Memoizer::new(slow_square(&calls)).with_shards(2));
+
+        let keys = [1, 2, 3, 1, 2, 3, 1, 2, 3, 4];
+        let results = get_from_threads(&memoizer, &keys);
+        assert_eq!(results, vec![1, 4, 9, 1, 4, 9, 1, 4, 9, 16]);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // All cached now.
+        get_from_threads(&memoizer, &keys);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(memoizer.len(), 4);
+    }
+
+    #[test]
+    fn expired_results_are_recalculated() {
+        let calls = AtomicUsize::new(0);
+        let memoizer = Memoizer::new(|n: u32| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            n + 1
+        })
+        .with_ttl(Duration::from_millis(50));
+
+        assert_eq!(memoizer.get(1), 2);
+        assert_eq!(memoizer.get(1), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        thread::sleep(Duration::from_millis(80));
+        assert_eq!(memoizer.get(1), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(memoizer.stats().evictions, 1);
+
+        memoizer.get(2);
+        thread::sleep(Duration::from_millis(80));
+        memoizer.purge_expired();
+        assert!(memoizer.is_empty());
+    }
+
+    #[test]
+    fn a_panicking_calculation_is_retried() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let started = Arc::new(Barrier::new(2));
+        let first_started = Arc::clone(&started);
+        let memoizer = Arc::new(Memoizer::new(move |n: u32| {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                first_started.wait();
+                thread::sleep(Duration::from_millis(50));
+                panic!("first call fails");
+            }
+            n * 2
+        }));
+
+        let first = {
+            let memoizer = Arc::clone(&memoizer);
+            thread::spawn(move || memoizer.get(5))
+        };
+        // Once the spawned thread is inside the closure, so it is the first call.
+        started.wait();
+        // Waits on the first call, sees it fail, and runs the closure itself.
+        assert_eq!(memoizer.get(5), 10);
+        assert!(first.join().is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        assert_send_sync(&Memoizer::new(|s: String| s.len()));
+    }
+}
@@ -12,10 +12,18 @@
 /// # Examples
 /// ```
 /// let arg = 5;
-/// let answer = my_crate::add_one(arg);
+/// let answer = cargo_and_crates_io::add_one(arg);
 ///
 /// assert_eq!(6, answer);
 /// ```
+///
+/// # Panics
+/// If `x` is `i32::MAX`, in a release build as well as a debug one.
+/// ```should_panic
+/// cargo_and_crates_io::add_one(i32::MAX);
+/// ```
+// Where x + 1 would wrap around to i32::MIN in a release build.  The numeric crate has
+// checked_add_one etc. for the other ways of handling it.
 pub fn add_one(x: i32) -> i32 {
-    x + 1
+    numeric::checked_add_one(x).expect("add_one overflowed")
 }
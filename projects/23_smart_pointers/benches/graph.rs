@@ -0,0 +1,192 @@
+// The arena graph from src/arena.rs against the same graph built from Rc<RefCell<T>>.
+//
+// cargo bench --bench graph
+//
+// Both graphs have NODES nodes with up to three edges each, picked by a fixed formula so every
+// run builds the same graph.  Edges only lead to higher numbered nodes, so the graph is acyclic
+// and topological sort has the whole graph to order, and the edge to the next node means a
+// traversal from node 0 reaches every node.
+//
+// The Rc version owns its nodes in a Vec and links them with Weak, the way it has to be done to
+// avoid leaking cycles in a general graph (see doubly_linked_list.rs), so a traversal pays for a
+// RefCell borrow and a Weak upgrade at every step.
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use smart_pointers::arena::{Graph, GraphId};
+
+const NODES: usize = 10_000;
+
+fn edges(from: usize) -> Vec<usize> {
+    let to = [
+        from + 1,
+        from + 1 + (from * 31 + 7) % 100,
+        from + 1 + (from * 17 + 3) % 1000,
+    ];
+    to.iter().copied().filter(|&to| to < NODES).collect()
+}
+
+fn arena_graph() -> (Graph<usize>, Vec<GraphId<usize>>) {
+    let mut graph = Graph::new();
+    let ids: Vec<_> = (0..NODES).map(|n| graph.add_node(n)).collect();
+    for from in 0..NODES {
+        for to in edges(from) {
+            graph.add_edge(ids[from], ids[to]).unwrap();
+        }
+    }
+    (graph, ids)
+}
+
+struct RcNode {
+    value: usize,
+    edges: Vec<Weak<RefCell<RcNode>>>,
+}
+
+fn rc_graph() -> Vec<Rc<RefCell<RcNode>>> {
+    let nodes: Vec<_> = (0..NODES)
+        .map(|value| {
+            Rc::new(RefCell::new(RcNode {
+                value,
+                edges: Vec::new(),
+            }))
+        })
+        .collect();
+    for (from, node) in nodes.iter().enumerate() {
+        for to in edges(from) {
+            node.borrow_mut().edges.push(Rc::downgrade(&nodes[to]));
+        }
+    }
+    nodes
+}
+
+// The same as Graph::bfs, visited flags indexed by the node's value.
+fn rc_bfs(start: &Rc<RefCell<RcNode>>) -> Vec<usize> {
+    let mut order = Vec::new();
+    let mut visited = vec![false; NODES];
+    let mut queue = VecDeque::new();
+    visited[start.borrow().value] = true;
+    queue.push_back(Rc::clone(start));
+
+    while let Some(node) = queue.pop_front() {
+        let node = node.borrow();
+        order.push(node.value);
+        for edge in &node.edges {
+            let next = edge.upgrade().unwrap();
+            let value = next.borrow().value;
+            if !visited[value] {
+                visited[value] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    order
+}
+
+// The same as Graph::dfs.
+fn rc_dfs(start: &Rc<RefCell<RcNode>>) -> Vec<usize> {
+    let mut order = Vec::new();
+    let mut visited = vec![false; NODES];
+    let mut stack = vec![Rc::clone(start)];
+
+    while let Some(node) = stack.pop() {
+        let node = node.borrow();
+        if visited[node.value] {
+            continue;
+        }
+        visited[node.value] = true;
+        order.push(node.value);
+        for edge in node.edges.iter().rev() {
+            let next = edge.upgrade().unwrap();
+            if !visited[next.borrow().value] {
+                stack.push(next);
+            }
+        }
+    }
+    order
+}
+
+// The same as Graph::topological_sort, incoming edge counts indexed by the node's value.
+fn rc_topological_sort(nodes: &[Rc<RefCell<RcNode>>]) -> Result<Vec<usize>, &'static str> {
+    let mut incoming = vec![0usize; NODES];
+    for node in nodes {
+        for edge in &node.borrow().edges {
+            incoming[edge.upgrade().unwrap().borrow().value] += 1;
+        }
+    }
+
+    let mut ready: VecDeque<_> = nodes
+        .iter()
+        .filter(|node| incoming[node.borrow().value] == 0)
+        .map(Rc::clone)
+        .collect();
+    let mut order = Vec::with_capacity(nodes.len());
+
+    while let Some(node) = ready.pop_front() {
+        let node = node.borrow();
+        order.push(node.value);
+        for edge in &node.edges {
+            let next = edge.upgrade().unwrap();
+            let value = next.borrow().value;
+            incoming[value] -= 1;
+            if incoming[value] == 0 {
+                ready.push_back(next);
+            }
+        }
+    }
+
+    if order.len() == nodes.len() {
+        Ok(order)
+    } else {
+        Err("the graph has a cycle")
+    }
+}
+
+fn build(c: &mut Criterion) {
+    c.bench_function("build arena graph", |b| b.iter(|| black_box(arena_graph())));
+    c.bench_function("build Rc<RefCell> graph", |b| {
+        b.iter(|| black_box(rc_graph()))
+    });
+}
+
+fn traverse(c: &mut Criterion) {
+    let (graph, ids) = arena_graph();
+    let nodes = rc_graph();
+    let values = |order: Vec<GraphId<usize>>| -> Vec<usize> {
+        order
+            .into_iter()
+            .map(|id| *graph.get(id).unwrap())
+            .collect()
+    };
+    // Both versions must do the same work, and the sort must not stop early at a cycle.
+    assert_eq!(graph.bfs(ids[0]).len(), NODES);
+    assert_eq!(values(graph.bfs(ids[0])), rc_bfs(&nodes[0]));
+    assert_eq!(values(graph.dfs(ids[0])), rc_dfs(&nodes[0]));
+    let sorted = graph
+        .topological_sort()
+        .expect("the bench graph is acyclic");
+    assert_eq!(Ok(values(sorted)), rc_topological_sort(&nodes));
+
+    c.bench_function("bfs arena graph", |b| {
+        b.iter(|| black_box(graph.bfs(ids[0])))
+    });
+    c.bench_function("bfs Rc<RefCell> graph", |b| {
+        b.iter(|| black_box(rc_bfs(&nodes[0])))
+    });
+    c.bench_function("dfs arena graph", |b| {
+        b.iter(|| black_box(graph.dfs(ids[0])))
+    });
+    c.bench_function("dfs Rc<RefCell> graph", |b| {
+        b.iter(|| black_box(rc_dfs(&nodes[0])))
+    });
+    c.bench_function("topological sort arena graph", |b| {
+        b.iter(|| black_box(graph.topological_sort()))
+    });
+    c.bench_function("topological sort Rc<RefCell> graph", |b| {
+        b.iter(|| black_box(rc_topological_sort(&nodes)))
+    });
+}
+
+criterion_group!(benches, build, traverse);
+criterion_main!(benches);
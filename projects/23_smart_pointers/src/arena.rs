@@ -0,0 +1,439 @@
+// An arena: every node lives in one Vec and is referred to by its index, a NodeId, instead of an
+// Rc<RefCell<T>>.
+//
+// Compared with MutList style Rc<RefCell<T>> graphs:
+// 1).  No reference counting and no runtime borrow checks.  A '&mut Arena' is checked by the
+//      compiler like any other '&mut', so there is no double borrow to panic on.
+// 2).  Cycles are fine: the nodes are owned by the arena, not by each other, and are all freed
+//      when the arena is dropped.  Nothing needs to be Weak.
+// 3).  The nodes sit together in memory, which is friendlier to the cache than one heap
+//      allocation per node.  See benches/graph.rs.
+//
+// The catch is that a NodeId is just a number and can outlive its node.  So every slot has a
+// generation that goes up each time the slot is freed, and a NodeId remembers the generation it
+// was made with.  Looking up a stale id (its node was removed, perhaps with a new node now in the
+// same slot) gives None instead of the wrong node.  A slot whose generation has run out is never
+// used again, rather than wrapping round to generations that old ids still have.
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+// The type parameter stops an id from one Arena<T> being used with an Arena<U>.
+pub struct NodeId<T> {
+    index: u32,
+    generation: u32,
+    arena: PhantomData<fn() -> T>,
+}
+
+impl<T> NodeId<T> {
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+// Written out rather than derived, a derive would only implement these when T does.
+impl<T> Clone for NodeId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NodeId<T> {}
+
+impl<T> PartialEq for NodeId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for NodeId<T> {}
+
+impl<T> Hash for NodeId<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for NodeId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NodeId({}v{})", self.index, self.generation)
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>, // None while the slot is free.
+}
+
+pub struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>, // Free slots, reused before the Vec grows.
+    len: usize,
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Arena {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> NodeId<T> {
+        self.len += 1;
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index as usize].value = Some(value);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                u32::try_from(self.slots.len() - 1).expect("more than u32::MAX slots in the arena")
+            }
+        };
+        NodeId {
+            index,
+            generation: self.slots[index as usize].generation,
+            arena: PhantomData,
+        }
+    }
+
+    // Returns the value, or None if 'id' is stale.
+    pub fn remove(&mut self, id: NodeId<T>) -> Option<T> {
+        self.get(id)?;
+        let slot = &mut self.slots[id.index()];
+        // Every id made for this slot so far is now stale.
+        if let Some(generation) = slot.generation.checked_add(1) {
+            slot.generation = generation;
+            self.free.push(id.index);
+        }
+        self.len -= 1;
+        slot.value.take()
+    }
+
+    pub fn get(&self, id: NodeId<T>) -> Option<&T> {
+        match self.slots.get(id.index()) {
+            Some(slot) if slot.generation == id.generation => slot.value.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, id: NodeId<T>) -> Option<&mut T> {
+        match self.slots.get_mut(id.index()) {
+            Some(slot) if slot.generation == id.generation => slot.value.as_mut(),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, id: NodeId<T>) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The live values and their ids, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId<T>, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.value.as_ref().map(|value| {
+                let id = NodeId {
+                    index: index as u32,
+                    generation: slot.generation,
+                    arena: PhantomData,
+                };
+                (id, value)
+            })
+        })
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena::new()
+    }
+}
+
+// A directed graph stored in an Arena.  Edges are kept as NodeIds in the node they start from.
+pub struct GraphNode<T> {
+    pub value: T,
+    edges: Vec<NodeId<GraphNode<T>>>,
+}
+
+pub type GraphId<T> = NodeId<GraphNode<T>>;
+
+pub struct Graph<T> {
+    nodes: Arena<GraphNode<T>>,
+}
+
+impl<T> Graph<T> {
+    pub fn new() -> Self {
+        Graph {
+            nodes: Arena::new(),
+        }
+    }
+
+    pub fn add_node(&mut self, value: T) -> GraphId<T> {
+        self.nodes.insert(GraphNode {
+            value,
+            edges: Vec::new(),
+        })
+    }
+
+    pub fn add_edge(&mut self, from: GraphId<T>, to: GraphId<T>) -> Result<(), &'static str> {
+        if !self.nodes.contains(to) {
+            return Err("the edge's end node doesn't exist");
+        }
+        let node = self
+            .nodes
+            .get_mut(from)
+            .ok_or("the edge's start node doesn't exist")?;
+        node.edges.push(to);
+        Ok(())
+    }
+
+    // Removes the node and every edge to or from it.  O(edges), every node is checked.
+    pub fn remove_node(&mut self, id: GraphId<T>) -> Option<T> {
+        let node = self.nodes.remove(id)?;
+        for slot in self.nodes.slots.iter_mut() {
+            if let Some(other) = slot.value.as_mut() {
+                other.edges.retain(|&edge| edge != id);
+            }
+        }
+        Some(node.value)
+    }
+
+    pub fn get(&self, id: GraphId<T>) -> Option<&T> {
+        self.nodes.get(id).map(|node| &node.value)
+    }
+
+    pub fn get_mut(&mut self, id: GraphId<T>) -> Option<&mut T> {
+        self.nodes.get_mut(id).map(|node| &mut node.value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // In the order the edges were added.  Empty for a stale id.
+    pub fn neighbors(&self, id: GraphId<T>) -> &[GraphId<T>] {
+        self.nodes.get(id).map_or(&[], |node| &node.edges)
+    }
+
+    // Visited flags indexed by slot, cheaper than a HashSet<NodeId>.
+    fn visited(&self) -> Vec<bool> {
+        vec![false; self.nodes.slots.len()]
+    }
+
+    // Breadth first: 'start', then everything one edge away, then two edges away...
+    pub fn bfs(&self, start: GraphId<T>) -> Vec<GraphId<T>> {
+        let mut order = Vec::new();
+        if !self.nodes.contains(start) {
+            return order;
+        }
+        let mut visited = self.visited();
+        let mut queue = VecDeque::new();
+        visited[start.index()] = true;
+        queue.push_back(start);
+
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            for &next in self.neighbors(id) {
+                if !visited[next.index()] {
+                    visited[next.index()] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        order
+    }
+
+    // Depth first, each node before the nodes it leads to, following edges in the order they
+    // were added.  With an explicit stack, a long path can't overflow the call stack.
+    pub fn dfs(&self, start: GraphId<T>) -> Vec<GraphId<T>> {
+        let mut order = Vec::new();
+        if !self.nodes.contains(start) {
+            return order;
+        }
+        let mut visited = self.visited();
+        let mut stack = vec![start];
+
+        while let Some(id) = stack.pop() {
+            if visited[id.index()] {
+                continue;
+            }
+            visited[id.index()] = true;
+            order.push(id);
+            // Reversed so the first edge is followed first.
+            stack.extend(
+                self.neighbors(id)
+                    .iter()
+                    .rev()
+                    .filter(|next| !visited[next.index()]),
+            );
+        }
+        order
+    }
+
+    // Every node, each one before all the nodes its edges lead to (Kahn's algorithm).  Ties are
+    // broken by slot order so the result is always the same for the same graph.
+    pub fn topological_sort(&self) -> Result<Vec<GraphId<T>>, &'static str> {
+        let mut incoming = vec![0usize; self.nodes.slots.len()];
+        for (_, node) in self.nodes.iter() {
+            for edge in &node.edges {
+                incoming[edge.index()] += 1;
+            }
+        }
+
+        let mut ready: VecDeque<GraphId<T>> = self
+            .nodes
+            .iter()
+            .map(|(id, _)| id)
+            .filter(|id| incoming[id.index()] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.len());
+
+        while let Some(id) = ready.pop_front() {
+            order.push(id);
+            for &next in self.neighbors(id) {
+                incoming[next.index()] -= 1;
+                if incoming[next.index()] == 0 {
+                    ready.push_back(next);
+                }
+            }
+        }
+
+        if order.len() == self.len() {
+            Ok(order)
+        } else {
+            Err("the graph has a cycle")
+        }
+    }
+}
+
+impl<T> Default for Graph<T> {
+    fn default() -> Self {
+        Graph::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_ids_are_detected() {
+        let mut arena = Arena::new();
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+        assert_eq!(arena.remove(a), Some("a"));
+        assert_eq!(arena.remove(a), None);
+
+        // The new node reuses a's slot, but a's id doesn't see it.
+        let c = arena.insert("c");
+        assert_eq!(c.index(), a.index());
+        assert_ne!(c, a);
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.get(c), Some(&"c"));
+        assert_eq!(arena.get(b), Some(&"b"));
+        assert_eq!(arena.len(), 2);
+
+        *arena.get_mut(c).unwrap() = "see";
+        let values: Vec<&str> = arena.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, vec!["see", "b"]);
+    }
+
+    #[test]
+    fn a_slot_is_retired_when_its_generation_runs_out() {
+        let mut arena = Arena::new();
+        let a = arena.insert("a");
+        arena.slots[a.index()].generation = u32::MAX;
+        let a = arena.iter().next().unwrap().0;
+
+        assert_eq!(arena.remove(a), Some("a"));
+        let b = arena.insert("b");
+        assert_ne!(b.index(), a.index());
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.remove(a), None);
+        assert_eq!(arena.len(), 1);
+    }
+
+    // a -> b -> d
+    // |         ^
+    // +--> c ---+
+    fn diamond() -> (Graph<char>, Vec<GraphId<char>>) {
+        let mut graph = Graph::new();
+        let ids: Vec<_> = "abcd".chars().map(|c| graph.add_node(c)).collect();
+        for &(from, to) in &[(0, 1), (0, 2), (1, 3), (2, 3)] {
+            graph.add_edge(ids[from], ids[to]).unwrap();
+        }
+        (graph, ids)
+    }
+
+    fn values(graph: &Graph<char>, ids: &[GraphId<char>]) -> String {
+        ids.iter().map(|&id| *graph.get(id).unwrap()).collect()
+    }
+
+    #[test]
+    fn traversals() {
+        let (graph, ids) = diamond();
+        assert_eq!(values(&graph, &graph.bfs(ids[0])), "abcd");
+        assert_eq!(values(&graph, &graph.dfs(ids[0])), "abdc");
+        assert_eq!(values(&graph, &graph.bfs(ids[2])), "cd");
+        assert_eq!(values(&graph, &graph.topological_sort().unwrap()), "abcd");
+    }
+
+    #[test]
+    fn cycles_are_fine_for_traversal_but_not_sorting() {
+        let (mut graph, ids) = diamond();
+        graph.add_edge(ids[3], ids[0]).unwrap();
+        assert_eq!(values(&graph, &graph.bfs(ids[1])), "bdac");
+        assert_eq!(values(&graph, &graph.dfs(ids[3])), "dabc");
+        assert_eq!(graph.topological_sort(), Err("the graph has a cycle"));
+    }
+
+    #[test]
+    fn removing_a_node_removes_its_edges() {
+        let (mut graph, ids) = diamond();
+        assert_eq!(graph.remove_node(ids[1]), Some('b'));
+        assert_eq!(graph.neighbors(ids[0]), &[ids[2]]);
+        assert_eq!(values(&graph, &graph.bfs(ids[0])), "acd");
+        assert!(graph.bfs(ids[1]).is_empty());
+        assert_eq!(
+            graph.add_edge(ids[1], ids[3]),
+            Err("the edge's start node doesn't exist")
+        );
+
+        // A new node in b's old slot isn't reachable through b's old edges.
+        let e = graph.add_node('e');
+        assert_eq!(e.index(), ids[1].index());
+        assert_eq!(values(&graph, &graph.bfs(ids[0])), "acd");
+        assert_eq!(values(&graph, &graph.topological_sort().unwrap()), "aecd");
+    }
+
+    #[test]
+    fn a_long_path_does_not_overflow_the_stack() {
+        let mut graph = Graph::new();
+        let ids: Vec<_> = (0..100_000).map(|n| graph.add_node(n)).collect();
+        for pair in ids.windows(2) {
+            graph.add_edge(pair[0], pair[1]).unwrap();
+        }
+        assert_eq!(graph.dfs(ids[0]).len(), 100_000);
+        assert_eq!(graph.topological_sort().unwrap(), ids);
+    }
+}
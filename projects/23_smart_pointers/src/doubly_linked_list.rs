@@ -0,0 +1,343 @@
+// A doubly linked list with Rc<RefCell<T>> and Weak<T>.
+//
+// Each node points forwards to the next node and backwards to the previous one.  If both
+// pointers were Rc, every pair of neighbours would be a reference cycle: each keeps the other's
+// strong_count above 0, so neither is ever freed, even after the list is dropped.
+//
+// Instead only the forward pointers own their node:
+//
+//   head ==> [a] ==> [b] ==> [c]      ==> Rc   (strong, owns the node)
+//            [a] <-- [b] <-- [c] <-- tail   <-- Weak (doesn't keep the node alive)
+//
+// so every node has a strong_count of exactly 1, and dropping the head frees the whole list.
+// The nodes are in a RefCell so their pointers can be changed through an Rc.
+use std::cell::{Ref, RefCell};
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::rc::{Rc, Weak};
+
+type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+type WeakLink<T> = Weak<RefCell<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+    prev: WeakLink<T>,
+}
+
+pub struct DoublyLinkedList<T> {
+    head: Link<T>,
+    tail: WeakLink<T>,
+    len: usize,
+}
+
+impl<T> DoublyLinkedList<T> {
+    pub fn new() -> Self {
+        DoublyLinkedList {
+            head: None,
+            tail: Weak::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let node = Rc::new(RefCell::new(Node {
+            elem,
+            next: self.head.take(),
+            prev: Weak::new(),
+        }));
+        match &node.borrow().next {
+            Some(old_head) => old_head.borrow_mut().prev = Rc::downgrade(&node),
+            None => self.tail = Rc::downgrade(&node),
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let node = Rc::new(RefCell::new(Node {
+            elem,
+            next: None,
+            prev: self.tail.clone(),
+        }));
+        match self.tail.upgrade() {
+            Some(old_tail) => old_tail.borrow_mut().next = Some(Rc::clone(&node)),
+            None => self.head = Some(Rc::clone(&node)),
+        }
+        self.tail = Rc::downgrade(&node);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.take().map(|old_head| {
+            match old_head.borrow_mut().next.take() {
+                Some(new_head) => {
+                    new_head.borrow_mut().prev = Weak::new();
+                    self.head = Some(new_head);
+                }
+                None => self.tail = Weak::new(),
+            }
+            self.len -= 1;
+            Node::into_elem(old_head)
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.tail.upgrade().map(|old_tail| {
+            // Take the one strong pointer to the old tail, from its prev node or the head.
+            let owner = match old_tail.borrow().prev.upgrade() {
+                Some(new_tail) => {
+                    self.tail = Rc::downgrade(&new_tail);
+                    new_tail.borrow_mut().next.take()
+                }
+                None => {
+                    self.tail = Weak::new();
+                    self.head.take()
+                }
+            };
+            drop(owner);
+            self.len -= 1;
+            Node::into_elem(old_tail)
+        })
+    }
+
+    // Ref rather than &T, the element is inside a RefCell.
+    pub fn peek_front(&self) -> Option<Ref<'_, T>> {
+        self.head
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), |node| &node.elem))
+    }
+
+    pub fn peek_back(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.tail.upgrade().map(|node| node.borrow().elem.clone())
+    }
+
+    // Clones of the elements, front to back, or back to front with .rev().
+    pub fn iter(&self) -> Iter<'_, T>
+    where
+        T: Clone,
+    {
+        Iter {
+            front: self.head.clone(),
+            back: self.tail.upgrade(),
+            remaining: self.len,
+            list: PhantomData,
+        }
+    }
+}
+
+impl<T> Node<T> {
+    // Only works once the caller has the only strong pointer left, which pop_front and
+    // pop_back make sure of.
+    fn into_elem(node: Rc<RefCell<Node<T>>>) -> T {
+        match Rc::try_unwrap(node) {
+            Ok(node) => node.into_inner().elem,
+            Err(_) => panic!("a popped node is still shared"),
+        }
+    }
+}
+
+impl<T> Default for DoublyLinkedList<T> {
+    fn default() -> Self {
+        DoublyLinkedList::new()
+    }
+}
+
+// Popping the nodes one by one, rather than letting the head drop the next node which drops the
+// next..., so a long list can't overflow the stack.
+impl<T> Drop for DoublyLinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> FromIterator<T> for DoublyLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = DoublyLinkedList::new();
+        for elem in iter {
+            list.push_back(elem);
+        }
+        list
+    }
+}
+
+// The iterator holds Rcs to the nodes at each end.  It borrows the list (through PhantomData,
+// it doesn't need the reference itself) so the list can't pop a node the iterator still holds.
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    remaining: usize, // So the two ends stop when they meet.
+    list: PhantomData<&'a DoublyLinkedList<T>>,
+}
+
+impl<T: Clone> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.take().map(|node| {
+            let node = node.borrow();
+            self.front = node.next.clone();
+            self.remaining -= 1;
+            node.elem.clone()
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.take().map(|node| {
+            let node = node.borrow();
+            self.back = node.prev.upgrade();
+            self.remaining -= 1;
+            node.elem.clone()
+        })
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for Iter<'_, T> {}
+
+// Moves the elements out, popping from either end.
+pub struct IntoIter<T>(DoublyLinkedList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> IntoIterator for DoublyLinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Weak pointers to every node, front to back, to check on them after the list has gone.
+    fn nodes<T>(list: &DoublyLinkedList<T>) -> Vec<WeakLink<T>> {
+        let mut nodes = Vec::new();
+        let mut next = list.head.clone();
+        while let Some(node) = next {
+            nodes.push(Rc::downgrade(&node));
+            next = node.borrow().next.clone();
+        }
+        nodes
+    }
+
+    #[test]
+    fn push_and_pop_at_both_ends() {
+        let mut list = DoublyLinkedList::new();
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(list.len(), 3);
+        assert_eq!(*list.peek_front().unwrap(), 1);
+        assert_eq!(list.peek_back(), Some(3));
+
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(2));
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+
+        list.push_front(4);
+        assert_eq!(list.pop_back(), Some(4));
+    }
+
+    #[test]
+    fn iterates_both_ways() {
+        let list: DoublyLinkedList<i32> = (1..=4).collect();
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(list.iter().rev().collect::<Vec<_>>(), vec![4, 3, 2, 1]);
+
+        let mut iter = list.iter();
+        assert_eq!((iter.next(), iter.next_back()), (Some(1), Some(4)));
+        assert_eq!((iter.next(), iter.next_back()), (Some(2), Some(3)));
+        assert_eq!((iter.next(), iter.next_back()), (None, None));
+        drop(iter);
+
+        let mut into_iter = list.into_iter();
+        assert_eq!(into_iter.next_back(), Some(4));
+        assert_eq!(into_iter.collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn each_node_has_one_owner_and_one_weak_pointer() {
+        let list: DoublyLinkedList<i32> = (1..=3).collect();
+        let head = list.head.as_ref().unwrap();
+        assert_eq!(Rc::strong_count(head), 1);
+        assert_eq!(Rc::weak_count(head), 1); // From the second node's prev.
+
+        for node in nodes(&list) {
+            let node = node.upgrade().unwrap();
+            // 2 while we hold 'node' ourselves.
+            assert_eq!(Rc::strong_count(&node), 2);
+            // The next node's prev, or the list's tail, plus the Weak from 'nodes'.
+            assert_eq!(Rc::weak_count(&node), 2);
+        }
+    }
+
+    #[test]
+    fn dropping_the_list_frees_every_node() {
+        let list: DoublyLinkedList<String> =
+            ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let nodes = nodes(&list);
+        assert_eq!(nodes.len(), 3);
+
+        drop(list);
+        assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+    }
+
+    #[test]
+    fn popped_nodes_are_freed() {
+        let mut list: DoublyLinkedList<i32> = (1..=3).collect();
+        let nodes = nodes(&list);
+        list.pop_front();
+        list.pop_back();
+        assert!(nodes[0].upgrade().is_none());
+        assert!(nodes[2].upgrade().is_none());
+        assert_eq!(Rc::strong_count(&nodes[1].upgrade().unwrap()), 2);
+    }
+
+    #[test]
+    fn dropping_a_long_list_does_not_overflow_the_stack() {
+        let list: DoublyLinkedList<u32> = (0..1_000_000).collect();
+        assert_eq!(list.len(), 1_000_000);
+        drop(list);
+    }
+}
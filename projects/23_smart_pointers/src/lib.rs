@@ -15,6 +15,7 @@ pub fn box_contrived_example() {
 // A 'construct function' list is a recursive data structure whereby you construct a pair from its 
 // two arguments, which usually are a single value and another pair.  These pairs containing pairs
 // form a list.
+#[allow(dead_code)] // The examples only build these lists, they never read them back.
 enum MyList {
     Cons(i32, Box<MyList>),
     Nil
@@ -138,6 +139,7 @@ pub fn explicit_dropping_early() {
 // Using Rc<T> for a reference counting type that enables multiple ownership.
 // Analogous to shared_ptr in C++.
 use std::rc::Rc; // Rc is not in the prelude.
+#[allow(dead_code)]
 enum MyRcList {
     MyCons(i32, Rc<MyRcList>),
     Nil
@@ -153,6 +155,18 @@ pub fn test_rc_list() {
     let c = MyCons(4, Rc::clone(&a)); // rc::clone is NOT taking a deep copy.
 }
 
+// MyRcList grown into a generic list with methods, see persistent_list.rs.
+pub mod persistent_list;
+use crate::persistent_list::PersistentList;
+
+pub fn test_persistent_list() {
+    let a: PersistentList<i32> = vec![5, 10].into_iter().collect();
+    let b = a.push_front(3); // Like MyCons(3, Rc::clone(&a)).
+    let c = a.push_front(4);
+    println!("a = {:?}, b = {:?}, c = {:?}", a, b, c);
+    println!("b reversed = {:?}, c doubled = {:?}", b.reverse(), c.map(|n| n * 2));
+}
+
 // RefCell<T> and the interior mutability pattern.
 // Interior mutability is a design pattern in Rust that allows you to mutate data even when there 
 // are immutable references to that data (this is normally prohibited by the borrowing rules).
@@ -168,8 +182,18 @@ pub fn test_rc_list() {
 
 pub trait Messenger {
     fn send(&self, msg: &str);
+
+    // Like send, but tells the caller when the message couldn't be delivered.  Messengers that
+    // can't fail can keep this default.  See messengers.rs for ones that can.
+    fn try_send(&self, msg: &str) -> std::io::Result<()> {
+        self.send(msg);
+        Ok(())
+    }
 }
 
+// Ready made messengers: stderr, a rotating log file, HTTP POST and fan-out to several.
+pub mod messengers;
+
 pub struct LimitTracker<'a, T: 'a + Messenger> {
     messenger: &'a T,
     value: usize,
@@ -178,7 +202,7 @@ pub struct LimitTracker<'a, T: 'a + Messenger> {
 
 impl<'a, T> LimitTracker<'a, T>
     where T: Messenger {
-        pub fn new(messenger: &T, max: usize) -> LimitTracker<T> {
+        pub fn new(messenger: &T, max: usize) -> LimitTracker<'_, T> {
             LimitTracker {
                 messenger,
                 value: 0,
@@ -202,6 +226,9 @@ impl<'a, T> LimitTracker<'a, T>
         }
     }
 
+// A LimitTracker that can be shared between threads, see shared_limit_tracker.rs.
+pub mod shared_limit_tracker;
+
 // Creating a Mock to keep track of messages.
 #[cfg(test)]
 mod tests {
@@ -259,6 +286,7 @@ mod tests {
 // in the list.
 use std::cell::RefCell;
 
+#[allow(dead_code)]
 #[derive(Debug)]
 enum MutList {
     MyMutCons(Rc<RefCell<i32>>, Rc<MutList>), 
@@ -285,3 +313,14 @@ pub fn test_multiple_owners_of_mutable() {
 // We also have Mutex<T> which offers interior mutability that is safe to use across multiple 
 // threads.
 
+// Reference cycles and Weak<T>.
+// Rc<T> only frees its value when strong_count reaches 0, so two values holding an Rc to each
+// other are never freed, a memory leak.  Rc::downgrade gives a Weak<T> instead: it counts towards
+// weak_count, which doesn't keep the value alive, and 'upgrade()' returns None once the value has
+// been dropped.  Structures with back-pointers use Weak for them:
+pub mod doubly_linked_list; // Rc to the next node, Weak to the previous one.
+pub mod tree;               // Rc to the children, Weak to the parent.
+
+// Or avoid shared ownership altogether: keep every node in one Vec and link them by index.
+// See arena.rs for a graph built that way.
+pub mod arena;
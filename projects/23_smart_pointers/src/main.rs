@@ -6,6 +6,7 @@ fn main() {
     smart_pointers::test_con_list();
     //box_contrived_example();
     smart_pointers::test_rc_list();
+    smart_pointers::test_persistent_list();
     smart_pointers::test_multiple_owners_of_mutable();
 }
 
@@ -0,0 +1,476 @@
+// Messenger implementations for real use, rather than the MockMessenger in the tests.
+// 1).  WriteMessenger: one line per message to any writer, WriteMessenger::stderr() for stderr.
+// 2).  RotatingFileMessenger: appends to a log file, moving it aside once it gets too big.
+// 3).  HttpMessenger: POSTs each message as the body of a request to a URL (a webhook).
+// 4).  FanOut: sends every message to each of a list of messengers.
+//
+// Each one does its real work in try_send.  'send' can't report a failure so it just drops the
+// message; call try_send when you need to know.  They are all Send + Sync so they can be used
+// with SharedLimitTracker.
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Stderr, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::Messenger;
+
+// The Mutex gives us the '&mut' that writing needs from the '&self' that send has (like the
+// RefCell in MockMessenger, but safe between threads).
+pub struct WriteMessenger<W: Write> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write> WriteMessenger<W> {
+    pub fn new(writer: W) -> WriteMessenger<W> {
+        WriteMessenger {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner().unwrap()
+    }
+}
+
+impl WriteMessenger<Stderr> {
+    pub fn stderr() -> WriteMessenger<Stderr> {
+        WriteMessenger::new(io::stderr())
+    }
+}
+
+impl<W: Write> Messenger for WriteMessenger<W> {
+    fn send(&self, msg: &str) {
+        let _ = self.try_send(msg);
+    }
+
+    fn try_send(&self, msg: &str) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writeln!(writer, "{}", msg)?;
+        writer.flush()
+    }
+}
+
+// Appends one line per message to 'path'.  When the next message would take the file past
+// 'max_bytes' it is renamed to path.1 (path.1 to path.2 and so on) and a new file started.  Only
+// 'keep' old files are kept, the oldest is deleted.
+pub struct RotatingFileMessenger {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: Mutex<Option<File>>, // Opened on the first message.
+}
+
+impl RotatingFileMessenger {
+    pub fn new<P: AsRef<Path>>(path: P, max_bytes: u64, keep: usize) -> RotatingFileMessenger {
+        RotatingFileMessenger {
+            path: path.as_ref().to_path_buf(),
+            max_bytes,
+            keep,
+            file: Mutex::new(None),
+        }
+    }
+
+    // path.1, path.2 etc.
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}", n));
+        self.path.with_file_name(name)
+    }
+
+    fn rotate(&self) -> io::Result<()> {
+        if self.keep == 0 {
+            return fs::remove_file(&self.path);
+        }
+        // Oldest first so nothing is overwritten before it has been moved.
+        for n in (1..self.keep).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                fs::rename(from, self.rotated_path(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))
+    }
+
+    fn open(&self) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+    }
+}
+
+impl Messenger for RotatingFileMessenger {
+    fn send(&self, msg: &str) {
+        let _ = self.try_send(msg);
+    }
+
+    fn try_send(&self, msg: &str) -> io::Result<()> {
+        let line = format!("{}\n", msg);
+        let mut file = self.file.lock().unwrap();
+        if file.is_none() {
+            *file = Some(self.open()?);
+        }
+
+        let len = file.as_ref().unwrap().metadata()?.len();
+        // A message bigger than max_bytes still gets written, to a file of its own.
+        if len > 0 && len + line.len() as u64 > self.max_bytes {
+            *file = None; // Close it before it is renamed.
+            self.rotate()?;
+            *file = Some(self.open()?);
+        }
+
+        file.as_mut().unwrap().write_all(line.as_bytes())
+    }
+}
+
+// POSTs each message as a text/plain body, e.g. to a chat webhook.  Only plain http:// URLs are
+// supported, we write the HTTP/1.1 request by hand on a TcpStream.  Any 2xx status is success.
+pub struct HttpMessenger {
+    host: String, // With the port, e.g. "localhost:8080".
+    path: String,
+    timeout: Duration,
+}
+
+impl HttpMessenger {
+    pub fn new(url: &str) -> Result<HttpMessenger, &'static str> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or("only http:// URLs are supported")?;
+        let (host, path) = match rest.find('/') {
+            Some(slash) => (&rest[..slash], &rest[slash..]),
+            None => (rest, "/"),
+        };
+        if host.is_empty() {
+            return Err("the URL has no host");
+        }
+        // An IPv6 address is in brackets, and its colons aren't the port's.
+        let has_port = host.rsplit(']').next().unwrap_or(host).contains(':');
+        let host = if has_port {
+            host.to_string()
+        } else {
+            format!("{}:80", host)
+        };
+
+        Ok(HttpMessenger {
+            host,
+            path: path.to_string(),
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    // For connecting, and for each read and write after that.
+    pub fn with_timeout(mut self, timeout: Duration) -> HttpMessenger {
+        self.timeout = timeout;
+        self
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "host not found");
+        for addr in self.host.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+impl Messenger for HttpMessenger {
+    fn send(&self, msg: &str) {
+        let _ = self.try_send(msg);
+    }
+
+    fn try_send(&self, msg: &str) -> io::Result<()> {
+        let mut stream = self.connect()?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            msg.len(),
+            msg
+        )?;
+        stream.flush()?;
+
+        // Only the status line matters, e.g. "HTTP/1.1 204 No Content".
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line)?;
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("bad HTTP response: '{}'", status_line.trim_end()),
+                )
+            })?;
+
+        if (200..300).contains(&status) {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "HTTP {} from {}{}",
+                status, self.host, self.path
+            )))
+        }
+    }
+}
+
+// Sends each message to every messenger, even when some fail.
+#[derive(Default)]
+pub struct FanOut {
+    messengers: Vec<Box<dyn Messenger + Send + Sync>>,
+}
+
+impl FanOut {
+    pub fn new() -> FanOut {
+        FanOut::default()
+    }
+
+    pub fn with<M: Messenger + Send + Sync + 'static>(mut self, messenger: M) -> FanOut {
+        self.messengers.push(Box::new(messenger));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.messengers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messengers.is_empty()
+    }
+}
+
+impl Messenger for FanOut {
+    fn send(&self, msg: &str) {
+        for messenger in &self.messengers {
+            messenger.send(msg);
+        }
+    }
+
+    // One error listing every failure.
+    fn try_send(&self, msg: &str) -> io::Result<()> {
+        let failures: Vec<String> = self
+            .messengers
+            .iter()
+            .filter_map(|messenger| messenger.try_send(msg).err())
+            .map(|error| error.to_string())
+            .collect();
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "{} of {} messengers failed: {}",
+                failures.len(),
+                self.messengers.len(),
+                failures.join("; ")
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::sync::Arc;
+    use std::thread;
+
+    // A file in a new directory of its own, which the test removes at the end.
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("smart_pointers-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("messages.log")
+    }
+
+    // A one-request HTTP server that answers with 'status' and hands back the request body.
+    fn stub_server(status: &'static str) -> (String, Receiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks/alerts", listener.local_addr().unwrap());
+        let (sender, receiver) = channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header == "\r\n" {
+                    break;
+                }
+                if let Some(value) = header.strip_prefix("Content-Length: ") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n",
+                status
+            )
+            .unwrap();
+            sender
+                .send((request_line, String::from_utf8(body).unwrap()))
+                .unwrap();
+        });
+        (url, receiver)
+    }
+
+    struct Failing;
+
+    impl Messenger for Failing {
+        fn send(&self, _msg: &str) {}
+
+        fn try_send(&self, _msg: &str) -> io::Result<()> {
+            Err(io::Error::other("always fails"))
+        }
+    }
+
+    #[test]
+    fn write_messenger_writes_lines() {
+        let messenger = WriteMessenger::new(Vec::new());
+        messenger.send("one");
+        messenger.try_send("two").unwrap();
+        assert_eq!(messenger.into_inner(), b"one\ntwo\n");
+    }
+
+    #[test]
+    fn log_files_are_rotated() {
+        let path = temp_path("rotate");
+        let messenger = RotatingFileMessenger::new(&path, 10, 2);
+        for msg in &["aaaa", "bbbb", "cccc", "dddd", "eeee"] {
+            messenger.try_send(msg).unwrap();
+        }
+
+        // Two 5 byte lines fit in 10 bytes.
+        assert_eq!(fs::read_to_string(&path).unwrap(), "eeee\n");
+        assert_eq!(
+            fs::read_to_string(messenger.rotated_path(1)).unwrap(),
+            "cccc\ndddd\n"
+        );
+        assert_eq!(
+            fs::read_to_string(messenger.rotated_path(2)).unwrap(),
+            "aaaa\nbbbb\n"
+        );
+        assert!(!messenger.rotated_path(3).exists());
+
+        messenger.try_send("ffff").unwrap();
+        messenger.try_send("gggg").unwrap();
+        assert_eq!(
+            fs::read_to_string(messenger.rotated_path(2)).unwrap(),
+            "cccc\ndddd\n"
+        );
+        assert!(!messenger.rotated_path(3).exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn unwritable_log_file_is_an_error() {
+        let path = temp_path("missing");
+        let messenger =
+            RotatingFileMessenger::new(path.with_file_name("no/such/dir/messages.log"), 100, 1);
+        assert!(messenger.try_send("lost").is_err());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn http_posts_the_message() {
+        let (url, requests) = stub_server("204 No Content");
+        let messenger = HttpMessenger::new(&url).unwrap();
+        messenger.try_send("Warning: quota over 90%").unwrap();
+
+        let (request_line, body) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /hooks/alerts HTTP/1.1\r\n");
+        assert_eq!(body, "Warning: quota over 90%");
+    }
+
+    #[test]
+    fn http_error_status_is_an_error() {
+        let (url, _requests) = stub_server("500 Internal Server Error");
+        let err = HttpMessenger::new(&url)
+            .unwrap()
+            .try_send("lost")
+            .unwrap_err();
+        assert!(err.to_string().starts_with("HTTP 500 from "), "{}", err);
+    }
+
+    #[test]
+    fn http_connection_refused_is_an_error() {
+        // Bind then drop a listener to find a port nothing is listening on.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let messenger = HttpMessenger::new(&format!("http://127.0.0.1:{}/", port)).unwrap();
+        assert!(messenger.try_send("lost").is_err());
+    }
+
+    #[test]
+    fn http_urls_are_checked() {
+        assert!(HttpMessenger::new("https://example.com/").is_err());
+        assert!(HttpMessenger::new("http:///path").is_err());
+        let messenger = HttpMessenger::new("http://example.com").unwrap();
+        assert_eq!(messenger.host, "example.com:80");
+        assert_eq!(messenger.path, "/");
+
+        let messenger = HttpMessenger::new("http://[::1]/hook").unwrap();
+        assert_eq!(messenger.host, "[::1]:80");
+        assert_eq!(messenger.path, "/hook");
+        let messenger = HttpMessenger::new("http://[::1]:8080/hook").unwrap();
+        assert_eq!(messenger.host, "[::1]:8080");
+    }
+
+    #[test]
+    fn fan_out_reaches_everyone_and_reports_failures() {
+        let path = temp_path("fan_out");
+        let fan_out = FanOut::new()
+            .with(RotatingFileMessenger::new(&path, 1000, 1))
+            .with(Failing)
+            .with(Failing);
+        assert_eq!(fan_out.len(), 3);
+
+        let err = fan_out.try_send("hello").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "2 of 3 messengers failed: always fails; always fails"
+        );
+        // The failures didn't stop the file getting the message.
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello\n");
+        assert!(FanOut::new().try_send("nobody").is_ok());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn works_with_the_shared_tracker() {
+        use crate::shared_limit_tracker::SharedLimitTracker;
+
+        let path = temp_path("tracker");
+        let fan_out = FanOut::new()
+            .with(RotatingFileMessenger::new(&path, 1000, 1))
+            .with(WriteMessenger::new(io::sink()));
+        let tracker = SharedLimitTracker::new(Arc::new(fan_out), 10);
+        tracker.set_value(10);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Oops: over quota\n");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+// A persistent (immutable) singly linked list built on Rc<T>.
+// This is MyRcList from lib.rs grown into something usable: generic over the element type, with
+// methods to build and walk it rather than nesting MyCons by hand.
+//
+// 'Persistent' means a list is never changed once built.  push_front and tail return a new list
+// that shares the rest of its nodes with the old one, so both stay valid and nothing is copied:
+//
+//   let a = PersistentList::new().push_front(3).push_front(2);   // [2, 3]
+//   let b = a.push_front(1);                                     // [1, 2, 3]
+//   let c = a.push_front(0);                                     // [0, 2, 3]
+//
+// b and c each have one node of their own and share the [2, 3] nodes with a.  The shared nodes
+// are freed when the last list that uses them is dropped (Rc::strong_count reaches 0).
+use std::fmt;
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+pub struct PersistentList<T> {
+    head: Link<T>,
+    len: usize,
+}
+
+type Link<T> = Option<Rc<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+impl<T> PersistentList<T> {
+    pub fn new() -> Self {
+        PersistentList { head: None, len: 0 }
+    }
+
+    // A new list with 'elem' in front of this one.  O(1), self is left as it was.
+    pub fn push_front(&self, elem: T) -> Self {
+        PersistentList {
+            head: Some(Rc::new(Node {
+                elem,
+                next: self.head.clone(), // Rc::clone, only bumps the reference count.
+            })),
+            len: self.len + 1,
+        }
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    // Everything after the head, sharing its nodes with this list.  The tail of an empty list is
+    // empty.
+    pub fn tail(&self) -> Self {
+        match &self.head {
+            Some(node) => PersistentList {
+                head: node.next.clone(),
+                len: self.len - 1,
+            },
+            None => PersistentList::new(),
+        }
+    }
+
+    // O(1), the length is kept alongside the head rather than counted.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+            remaining: self.len,
+        }
+    }
+
+    // Both of these build a whole new list, the nodes of a list can't be reused in another
+    // order.
+    pub fn reverse(&self) -> Self
+    where
+        T: Clone,
+    {
+        self.iter().fold(PersistentList::new(), |list, elem| {
+            list.push_front(elem.clone())
+        })
+    }
+
+    pub fn map<U, F>(&self, f: F) -> PersistentList<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        self.iter().map(f).collect()
+    }
+}
+
+// The derived Drop would drop the head node, which drops the next node, which drops the next...
+// one stack frame per element, enough to overflow the stack for a long list.  Instead we unlink
+// the nodes one at a time in a loop.  We stop at the first node some other list still holds,
+// the rest belongs to that list now.
+impl<T> Drop for PersistentList<T> {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(node) = head {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => head = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+// Cloning a list is O(1) whatever T is, both lists share every node.
+impl<T> Clone for PersistentList<T> {
+    fn clone(&self) -> Self {
+        PersistentList {
+            head: self.head.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Default for PersistentList<T> {
+    fn default() -> Self {
+        PersistentList::new()
+    }
+}
+
+// Keeps the order of the iterator, so collecting [1, 2, 3] gives a list with 1 at the head.
+impl<T> FromIterator<T> for PersistentList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        elems
+            .into_iter()
+            .rev()
+            .fold(PersistentList::new(), |list, elem| list.push_front(elem))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PersistentList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Compared element by element with an iterator (a derived PartialEq would recurse like Drop).
+impl<T: PartialEq> PartialEq for PersistentList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for PersistentList<T> {}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            self.remaining -= 1;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a PersistentList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_front_head_and_tail() {
+        let empty = PersistentList::new();
+        assert_eq!(empty.head(), None);
+        assert!(empty.tail().is_empty());
+
+        let list = empty.push_front(3).push_front(2).push_front(1);
+        assert_eq!(list.head(), Some(&1));
+        assert_eq!(list.tail().head(), Some(&2));
+        assert_eq!(list.tail().tail().tail(), empty);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.tail().len(), 2);
+
+        // The old lists are untouched.
+        assert!(empty.is_empty());
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+    }
+
+    #[test]
+    fn lists_share_their_tails() {
+        let a: PersistentList<i32> = vec![2, 3].into_iter().collect();
+        let b = a.push_front(1);
+        let c = a.push_front(0);
+
+        // The very same node, not a copy of it.
+        assert!(std::ptr::eq(b.tail().head().unwrap(), a.head().unwrap()));
+        assert!(std::ptr::eq(c.tail().head().unwrap(), a.head().unwrap()));
+
+        drop(a);
+        assert_eq!(b, vec![1, 2, 3].into_iter().collect());
+        assert_eq!(c, vec![0, 2, 3].into_iter().collect());
+    }
+
+    #[test]
+    fn collect_reverse_and_map() {
+        let list: PersistentList<i32> = (1..=4).collect();
+        assert_eq!(format!("{:?}", list), "[1, 2, 3, 4]");
+        assert_eq!(format!("{:?}", list.reverse()), "[4, 3, 2, 1]");
+        assert_eq!(
+            list.map(|n| n.to_string()),
+            ["1", "2", "3", "4"].iter().map(|s| s.to_string()).collect()
+        );
+        assert_eq!(list.iter().len(), 4);
+        assert_eq!(format!("{:?}", PersistentList::<i32>::new()), "[]");
+    }
+
+    #[test]
+    fn lists_of_different_lengths_are_not_equal() {
+        let short: PersistentList<i32> = (1..3).collect();
+        let long: PersistentList<i32> = (1..4).collect();
+        assert_ne!(short, long);
+        assert_eq!(short.clone(), short);
+    }
+
+    #[test]
+    fn dropping_a_long_list_does_not_overflow_the_stack() {
+        let list: PersistentList<u32> = (0..1_000_000).collect();
+        let shared = list.tail();
+        assert_eq!(list.len(), 1_000_000);
+        drop(list);
+        // Everything but the first node is still alive in 'shared'.
+        assert_eq!(shared.head(), Some(&1));
+        assert_eq!(shared, shared.clone());
+        drop(shared);
+    }
+}
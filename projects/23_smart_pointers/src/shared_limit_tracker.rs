@@ -0,0 +1,276 @@
+// A LimitTracker that can be shared between threads.
+//
+// LimitTracker<'a, T> borrows its messenger and needs '&mut self' to set a value, and the
+// MockMessenger in its tests uses RefCell, which can't be shared between threads.  Here:
+// 1).  The messenger is held in an Arc<M>, so the tracker owns its share of it.
+// 2).  The value and the current warning level are packed into one AtomicU64, so set_value takes
+//      '&self', the tracker itself can go in an Arc and be updated from any thread, and every
+//      update moves the value and the level together.  With two separate atomics a thread could
+//      work out the level from a value another thread had already replaced.
+// 3).  The thresholds are data (Tier) rather than hardcoded, 75%/90%/100% being the default.
+// 4).  Hysteresis: a tier's message is sent once when the value rises past it, and only sent
+//      again after the value has fallen 'hysteresis' below that tier and come back up.  Without
+//      it a value hovering at 90% would send a warning on every call.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use crate::Messenger;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tier {
+    pub at: f64, // A fraction of max, 0.9 is 90%.
+    pub message: String,
+}
+
+impl Tier {
+    pub fn new(at: f64, message: &str) -> Tier {
+        Tier {
+            at,
+            message: String::from(message),
+        }
+    }
+}
+
+pub struct SharedLimitTracker<M: Messenger> {
+    messenger: Arc<M>,
+    max: usize,
+    tiers: Vec<Tier>,
+    hysteresis: f64,
+    // The value in the top 56 bits, and in the bottom 8 how many tiers have been passed (and
+    // their warning sent), 0 when below them all.
+    state: AtomicU64,
+}
+
+const LEVEL_BITS: u32 = 8;
+const LEVEL_MASK: u64 = (1 << LEVEL_BITS) - 1;
+const MAX_VALUE: u64 = u64::MAX >> LEVEL_BITS; // Larger values are clamped to this.
+
+impl<M: Messenger> SharedLimitTracker<M> {
+    // The same tiers and messages as LimitTracker, with 5% hysteresis.
+    pub fn new(messenger: Arc<M>, max: usize) -> SharedLimitTracker<M> {
+        SharedLimitTracker {
+            messenger,
+            max,
+            tiers: vec![
+                Tier::new(0.75, "Warning: quota over 75%"),
+                Tier::new(0.9, "Warning: quota over 90%"),
+                Tier::new(1.0, "Oops: over quota"),
+            ],
+            hysteresis: 0.05,
+            state: AtomicU64::new(0),
+        }
+    }
+
+    // Tiers must be given lowest first, e.g. [50%, 80%].
+    pub fn with_tiers(mut self, tiers: Vec<Tier>) -> Result<SharedLimitTracker<M>, &'static str> {
+        if tiers.is_empty() {
+            return Err("at least one tier is needed");
+        }
+        if tiers.len() as u64 > LEVEL_MASK {
+            return Err("at most 255 tiers are allowed");
+        }
+        if tiers.iter().any(|tier| tier.at.is_nan() || tier.at < 0.0) {
+            return Err("tiers must be at or above 0%");
+        }
+        if tiers.windows(2).any(|pair| pair[0].at >= pair[1].at) {
+            return Err("tiers must be in increasing order");
+        }
+        self.tiers = tiers;
+        Ok(self)
+    }
+
+    // How far (as a fraction of max) the value must fall below a tier before it can fire again.
+    pub fn with_hysteresis(
+        mut self,
+        hysteresis: f64,
+    ) -> Result<SharedLimitTracker<M>, &'static str> {
+        if hysteresis.is_nan() || hysteresis < 0.0 {
+            return Err("hysteresis must be at or above 0");
+        }
+        self.hysteresis = hysteresis;
+        Ok(self)
+    }
+
+    pub fn value(&self) -> usize {
+        (self.state.load(Ordering::SeqCst) >> LEVEL_BITS) as usize
+    }
+
+    pub fn set_value(&self, value: usize) {
+        self.update(|_| value as u64);
+    }
+
+    // For several threads adding to the one total.
+    pub fn add(&self, amount: usize) {
+        self.update(|value| value.saturating_add(amount as u64));
+    }
+
+    // Changes the value and moves to its level in one atomic step, then sends a message if that
+    // is a higher tier.  fetch_update retries if another thread got in first, so each move from
+    // one level to the next is made by exactly one thread and its message is sent once.
+    fn update<F: Fn(u64) -> u64>(&self, change: F) {
+        let next = |state: u64| {
+            let value = change(state >> LEVEL_BITS).min(MAX_VALUE);
+            let level = self.level_for(value, (state & LEVEL_MASK) as usize);
+            value << LEVEL_BITS | level as u64
+        };
+        let old = self
+            .state
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |state| {
+                Some(next(state))
+            })
+            .unwrap();
+
+        let (old_level, new_level) = (
+            (old & LEVEL_MASK) as usize,
+            (next(old) & LEVEL_MASK) as usize,
+        );
+        if new_level > old_level {
+            self.messenger.send(&self.tiers[new_level - 1].message);
+        }
+    }
+
+    // Going up needs the value to reach the tier itself.  Going down only drops the tiers the
+    // value has fallen more than 'hysteresis' below.
+    fn level_for(&self, value: u64, current: usize) -> usize {
+        let fraction = value as f64 / self.max as f64;
+        let reached = self
+            .tiers
+            .iter()
+            .take_while(|tier| fraction >= tier.at)
+            .count();
+        let kept = self.tiers[..current]
+            .iter()
+            .take_while(|tier| fraction >= tier.at - self.hysteresis)
+            .count();
+        reached.max(kept)
+    }
+}
+
+// Forwards every message down a channel, so another thread can receive and deal with them.
+// Messages sent after the receiver is dropped are thrown away.
+pub struct ChannelMessenger {
+    sender: Sender<String>,
+}
+
+impl ChannelMessenger {
+    pub fn new(sender: Sender<String>) -> ChannelMessenger {
+        ChannelMessenger { sender }
+    }
+}
+
+impl Messenger for ChannelMessenger {
+    fn send(&self, msg: &str) {
+        let _ = self.sender.send(String::from(msg));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::thread;
+
+    fn tracker(max: usize) -> (SharedLimitTracker<ChannelMessenger>, Receiver<String>) {
+        let (sender, receiver) = channel();
+        let tracker = SharedLimitTracker::new(Arc::new(ChannelMessenger::new(sender)), max);
+        (tracker, receiver)
+    }
+
+    #[test]
+    fn sends_the_highest_tier_passed() {
+        let (tracker, receiver) = tracker(100);
+        tracker.set_value(50);
+        tracker.set_value(80);
+        tracker.set_value(100);
+        let messages: Vec<String> = receiver.try_iter().collect();
+        assert_eq!(
+            messages,
+            vec!["Warning: quota over 75%", "Oops: over quota"]
+        );
+    }
+
+    #[test]
+    fn the_same_warning_is_not_sent_twice() {
+        let (tracker, receiver) = tracker(100);
+        tracker.set_value(91);
+        tracker.set_value(92);
+        tracker.set_value(87); // Within 5% of the 90% tier.
+        tracker.set_value(91);
+        assert_eq!(receiver.try_iter().count(), 1);
+
+        tracker.set_value(84); // Far enough below 90% to reset it, but still over 75%.
+        tracker.set_value(90);
+        let messages: Vec<String> = receiver.try_iter().collect();
+        assert_eq!(messages, vec!["Warning: quota over 90%"]);
+    }
+
+    #[test]
+    fn custom_tiers_and_hysteresis() {
+        let (tracker, receiver) = tracker(10);
+        let tracker = tracker
+            .with_tiers(vec![Tier::new(0.5, "half"), Tier::new(0.8, "most")])
+            .unwrap()
+            .with_hysteresis(0.0)
+            .unwrap();
+        for value in &[5, 4, 5, 8, 9, 0, 8] {
+            tracker.set_value(*value);
+        }
+        let messages: Vec<String> = receiver.try_iter().collect();
+        assert_eq!(messages, vec!["half", "half", "most", "most"]);
+    }
+
+    #[test]
+    fn bad_tiers_are_rejected() {
+        let (tracker, _receiver) = tracker(10);
+        let tiers = vec![Tier::new(0.9, "a"), Tier::new(0.5, "b")];
+        assert_eq!(
+            tracker.with_tiers(tiers).err(),
+            Some("tiers must be in increasing order")
+        );
+
+        let (tracker, _receiver) = self::tracker(10);
+        assert!(tracker.with_tiers(vec![]).is_err());
+        let (tracker, _receiver) = self::tracker(10);
+        assert!(tracker.with_hysteresis(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn threads_share_one_tracker() {
+        let (tracker, receiver) = tracker(1000);
+        let tracker = Arc::new(tracker);
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let tracker = Arc::clone(&tracker);
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        tracker.add(1);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(tracker.value(), 1000);
+        // Every tier is passed exactly once whichever thread gets there first.
+        let mut messages: Vec<String> = receiver.try_iter().collect();
+        messages.sort();
+        assert_eq!(
+            messages,
+            vec![
+                "Oops: over quota",
+                "Warning: quota over 75%",
+                "Warning: quota over 90%"
+            ]
+        );
+    }
+
+    #[test]
+    fn is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedLimitTracker<ChannelMessenger>>();
+    }
+}
@@ -0,0 +1,296 @@
+// A tree where every node can have any number of children and knows its parent.
+//
+// Like the Node in the Rust book's chapter on Weak<T>: a parent owns its children through
+// Rc<TreeNode>, and a child points back at its parent through Weak<TreeNode>.  If the parent
+// pointer were an Rc too, parent and child would keep each other alive forever.  With Weak,
+// dropping the last Rc to the root frees the whole tree.
+//
+//   let root = TreeNode::new("root");
+//   let leaf = TreeNode::new("leaf");
+//   root.add_child(Rc::clone(&leaf)).unwrap();
+//   assert_eq!(*leaf.parent().unwrap().value(), "root");
+//
+// The parent and the list of children are in RefCells, so they can be changed through the Rc
+// that everyone shares.
+use std::cell::{Ref, RefCell};
+use std::mem;
+use std::rc::{Rc, Weak};
+
+#[derive(Debug)]
+pub struct TreeNode<T> {
+    value: RefCell<T>,
+    parent: RefCell<Weak<TreeNode<T>>>,
+    children: RefCell<Vec<Rc<TreeNode<T>>>>,
+}
+
+impl<T> TreeNode<T> {
+    pub fn new(value: T) -> Rc<TreeNode<T>> {
+        Rc::new(TreeNode {
+            value: RefCell::new(value),
+            parent: RefCell::new(Weak::new()),
+            children: RefCell::new(vec![]),
+        })
+    }
+
+    pub fn value(&self) -> Ref<'_, T> {
+        self.value.borrow()
+    }
+
+    pub fn set_value(&self, value: T) {
+        *self.value.borrow_mut() = value;
+    }
+
+    // None for the root, or once the parent has been dropped.
+    pub fn parent(&self) -> Option<Rc<TreeNode<T>>> {
+        self.parent.borrow().upgrade()
+    }
+
+    pub fn children(&self) -> Vec<Rc<TreeNode<T>>> {
+        self.children.borrow().clone()
+    }
+
+    // Moves 'child' (and everything under it) to the end of this node's children, taking it
+    // away from its old parent if it had one.  A node can't be added under itself or one of its
+    // own descendants, that would be a cycle of Rcs and would never be freed.
+    pub fn add_child(self: &Rc<Self>, child: Rc<TreeNode<T>>) -> Result<(), &'static str> {
+        if self
+            .ancestors_and_self()
+            .any(|node| Rc::ptr_eq(&node, &child))
+        {
+            return Err("a node can't be added under itself");
+        }
+
+        child.detach();
+        *child.parent.borrow_mut() = Rc::downgrade(self);
+        self.children.borrow_mut().push(child);
+        Ok(())
+    }
+
+    // Removes this node from its parent's children.  The node, and its children, live on for as
+    // long as the caller holds an Rc to it.
+    pub fn detach(self: &Rc<Self>) {
+        if let Some(parent) = self.parent() {
+            parent
+                .children
+                .borrow_mut()
+                .retain(|child| !Rc::ptr_eq(child, self));
+        }
+        *self.parent.borrow_mut() = Weak::new();
+    }
+
+    // The root is at depth 0.
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut parent = self.parent();
+        while let Some(node) = parent {
+            depth += 1;
+            parent = node.parent();
+        }
+        depth
+    }
+
+    // The parent, grandparent and so on up to the root.
+    pub fn ancestors(&self) -> Ancestors<T> {
+        Ancestors {
+            next: self.parent(),
+        }
+    }
+
+    fn ancestors_and_self(self: &Rc<Self>) -> Ancestors<T> {
+        Ancestors {
+            next: Some(Rc::clone(self)),
+        }
+    }
+
+    // This node and everything under it, depth first with each parent before its children.
+    pub fn descendants(self: &Rc<Self>) -> Descendants<T> {
+        Descendants {
+            stack: vec![Rc::clone(self)],
+        }
+    }
+}
+
+// Taking the children apart one by one, rather than letting each node drop its children which
+// drop theirs..., so a very deep tree can't overflow the stack.  A child someone else still holds
+// an Rc to is only let go of, its subtree lives on with it.
+impl<T> Drop for TreeNode<T> {
+    fn drop(&mut self) {
+        let mut stack = mem::take(self.children.get_mut());
+        while let Some(node) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                stack.append(node.children.get_mut());
+            }
+        }
+    }
+}
+
+pub struct Ancestors<T> {
+    next: Option<Rc<TreeNode<T>>>,
+}
+
+impl<T> Iterator for Ancestors<T> {
+    type Item = Rc<TreeNode<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = node.parent();
+        Some(node)
+    }
+}
+
+// A stack rather than recursion, so a very deep tree can't overflow the stack.
+pub struct Descendants<T> {
+    stack: Vec<Rc<TreeNode<T>>>,
+}
+
+impl<T> Iterator for Descendants<T> {
+    type Item = Rc<TreeNode<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        // Reversed so the first child comes off the stack first.
+        self.stack
+            .extend(node.children.borrow().iter().rev().cloned());
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // root
+    // +-- a
+    // |   +-- a1
+    // |   +-- a2
+    // +-- b
+    fn tree() -> Rc<TreeNode<&'static str>> {
+        let root = TreeNode::new("root");
+        let a = TreeNode::new("a");
+        root.add_child(Rc::clone(&a)).unwrap();
+        a.add_child(TreeNode::new("a1")).unwrap();
+        a.add_child(TreeNode::new("a2")).unwrap();
+        root.add_child(TreeNode::new("b")).unwrap();
+        root
+    }
+
+    fn values(nodes: impl Iterator<Item = Rc<TreeNode<&'static str>>>) -> Vec<&'static str> {
+        nodes.map(|node| *node.value()).collect()
+    }
+
+    #[test]
+    fn iterates_down_and_up() {
+        let root = tree();
+        assert_eq!(
+            values(root.descendants()),
+            vec!["root", "a", "a1", "a2", "b"]
+        );
+
+        let a2 = root
+            .descendants()
+            .find(|node| *node.value() == "a2")
+            .unwrap();
+        assert_eq!(values(a2.ancestors()), vec!["a", "root"]);
+        assert_eq!(a2.depth(), 2);
+        assert_eq!(root.depth(), 0);
+        assert!(root.parent().is_none());
+    }
+
+    // The same counts as the book's branch and leaf example.
+    #[test]
+    fn parents_are_weak_and_children_strong() {
+        let leaf = TreeNode::new(3);
+        assert_eq!((Rc::strong_count(&leaf), Rc::weak_count(&leaf)), (1, 0));
+
+        {
+            let branch = TreeNode::new(5);
+            branch.add_child(Rc::clone(&leaf)).unwrap();
+
+            assert_eq!(*leaf.parent().unwrap().value(), 5);
+            // 'branch' and the parent pointer in 'leaf'.
+            assert_eq!((Rc::strong_count(&branch), Rc::weak_count(&branch)), (1, 1));
+            // 'leaf' and branch's children.
+            assert_eq!((Rc::strong_count(&leaf), Rc::weak_count(&leaf)), (2, 0));
+        }
+
+        // The branch has gone, even though the leaf pointed at it.
+        assert!(leaf.parent().is_none());
+        assert_eq!((Rc::strong_count(&leaf), Rc::weak_count(&leaf)), (1, 0));
+    }
+
+    #[test]
+    fn dropping_the_root_frees_every_node() {
+        let root = tree();
+        let nodes: Vec<Weak<TreeNode<&str>>> = root
+            .descendants()
+            .map(|node| Rc::downgrade(&node))
+            .collect();
+        assert_eq!(nodes.len(), 5);
+        // Only the parent's children list owns each node.
+        assert!(nodes[1..].iter().all(|node| node.strong_count() == 1));
+
+        drop(root);
+        assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+    }
+
+    #[test]
+    fn dropping_a_deep_tree_does_not_overflow_the_stack() {
+        // Built from the bottom up, each new node becoming the parent of the chain so far.
+        let leaf = TreeNode::new(0);
+        let mut root = Rc::clone(&leaf);
+        for n in 1..200_000 {
+            let parent = TreeNode::new(n);
+            parent.add_child(root).unwrap();
+            root = parent;
+        }
+        assert_eq!(leaf.depth(), 199_999);
+
+        drop(root);
+        // The leaf outlives the tree, it was held here too.
+        assert!(leaf.parent().is_none());
+        assert_eq!(Rc::strong_count(&leaf), 1);
+    }
+
+    #[test]
+    fn moving_a_subtree() {
+        let root = tree();
+        let a = root.children()[0].clone();
+        let b = root.children()[1].clone();
+
+        b.add_child(Rc::clone(&a)).unwrap();
+        assert_eq!(
+            values(root.descendants()),
+            vec!["root", "b", "a", "a1", "a2"]
+        );
+        assert!(Rc::ptr_eq(&a.parent().unwrap(), &b));
+        assert_eq!(Rc::strong_count(&a), 2); // 'a' and b's children.
+
+        a.detach();
+        assert_eq!(values(root.descendants()), vec!["root", "b"]);
+        assert!(a.parent().is_none());
+        assert_eq!(Rc::strong_count(&a), 1);
+    }
+
+    #[test]
+    fn cycles_are_refused() {
+        let root = tree();
+        let a = root.children()[0].clone();
+        let a1 = a.children()[0].clone();
+
+        assert!(a1.add_child(Rc::clone(&root)).is_err());
+        assert!(a.add_child(Rc::clone(&a)).is_err());
+        // Nothing was changed.
+        assert_eq!(
+            values(root.descendants()),
+            vec!["root", "a", "a1", "a2", "b"]
+        );
+    }
+
+    #[test]
+    fn values_can_be_changed_through_a_shared_node() {
+        let root = tree();
+        let b = root.children()[1].clone();
+        b.set_value("bee");
+        assert_eq!(values(root.descendants()).last(), Some(&"bee"));
+    }
+}
@@ -1,32 +1,71 @@
 // Network Programming in Rust,  Abhishek Chanda
 // Page 64
+use json_with_serde::ServerConfig;
 use std::io::{Error, Read, Write};
-use std::net::{TcpListener, TcpStream};
-use std::thread;
+use std::net::TcpListener;
+use std::{env, process, thread};
+use tcp_support::{tls, ConnectionLimit};
 
-fn handle_client(mut stream: TcpStream) -> Result<(), Error> {
-    println!("Incoming connection from: {}", stream.peer_addr()?);
+fn handle_client<S: Read + Write>(mut stream: S) -> Result<(), Error> {
     let mut buf = [0; 512];
     loop {
         let bytes_read = stream.read(&mut buf)?;
         if bytes_read == 0 {
             return Ok(());
         }
-        stream.write(&buf[..bytes_read])?;
+        stream.write_all(&buf[..bytes_read])?;
     }
 }
 
 // nc 127.0.0.1 8888
+//
+// cargo run -- --tls-cert cert.pem --tls-key key.pem
+// ^^^ serve over TLS instead, see the simple_tcp_client project for a matching client.
+// openssl s_client -connect 127.0.0.1:8888
+//
+// cargo run -- --listen 127.0.0.1:9999 --workers 4
+// SERVER_WORKERS=4 cargo run -- --config server.toml
+// ^^^ settings come from ServerConfig, see the json_with_serde project.
 fn main() {
-    let listener = TcpListener::bind("0.0.0.0:8888").expect("Failed to bind!");
+    let mut args: Vec<String> = env::args().collect();
+    let config = ServerConfig::load(&mut args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    if args.len() > 1 {
+        eprintln!("Unexpected arguments: {:?}", &args[1..]);
+        process::exit(1);
+    }
+
+    let tls_config = config
+        .tls_paths()
+        .map(|(cert, key)| tls::server_config(cert, key))
+        .transpose()
+        .expect("Failed to load TLS certificate");
+
+    // A thread per client, like the book, but at most 'workers' at once.  A client that arrives
+    // when they are all busy is turned away, rather than left waiting on clients that may never
+    // finish.
+    let listener = TcpListener::bind(&config.listen).expect("Failed to bind!");
+    let limit = ConnectionLimit::new(config.workers);
     for stream in listener.incoming() {
         match stream {
             Err(e) => {
                 eprintln!("failed: {}", e)
             }
             Ok(stream) => {
+                let Some(permit) = limit.try_acquire() else {
+                    eprintln!("Refusing a connection, {} already open", limit.max());
+                    continue;
+                };
+                let tls_config = tls_config.clone();
                 thread::spawn(move || {
-                    handle_client(stream).unwrap_or_else(|error| eprintln!("{:?}", error));
+                    let _permit = permit;
+                    let result = stream.peer_addr().and_then(|addr| {
+                        println!("Incoming connection from: {}", addr);
+                        handle_client(tls::accept(stream, tls_config.as_ref())?)
+                    });
+                    result.unwrap_or_else(|error| eprintln!("{:?}", error));
                 });
             }
         }
@@ -1,27 +1,48 @@
 // Network Programming in Rust,  Abhishek Chanda
 // Page 66
-use std::io::{self, BufRead, BufReader, Write};
+//
+// The book version built a new BufReader for each reply, see ../tcp_support/src/lib.rs for why
+// that loses data.
+//
+// cargo run -- --tls-ca cert.pem
+// ^^^ talk TLS to a server started with that certificate.
 use std::net::TcpStream;
-use std::str;
+use std::{env, io, process};
+use tcp_support::tls::{self, TlsOptions};
 
 fn main() {
-    let mut stream = TcpStream::connect("127.0.0.1:8888").expect("Failed to connect to server");
+    let mut args: Vec<String> = env::args().collect();
+    let options = TlsOptions::extract(&mut args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+
+    let stream = TcpStream::connect("127.0.0.1:8888").expect("Failed to connect to server");
+    let mut connection =
+        tls::client_connection(stream, &options).expect("Failed to connect to server");
     loop {
         let mut input = String::new();
-        let mut buffer: Vec<u8> = Vec::new();
         io::stdin()
             .read_line(&mut input)
             .expect("Failed to read from stdin");
-        stream
-            .write(input.as_bytes())
-            .expect("Failed to write to server");
 
-        let mut reader = BufReader::new(&stream);
-        reader
-            .read_until(b'\n', &mut buffer)
-            .expect("Failed to read into buffer");
+        let reply = connection
+            .request(&input)
+            .expect("Failed to talk to server");
+        match reply {
+            Some(line) => print!("{}", line),
+            None => {
+                eprintln!("Server closed the connection");
+                break;
+            }
+        }
 
-        let utf8: &str = str::from_utf8(&buffer).expect("Failed to write buffer as string");
-        print!("{}", utf8);
+        // Anything else the server sent in the same burst.
+        for line in connection
+            .read_buffered_lines()
+            .expect("Failed to read into buffer")
+        {
+            print!("{}", line);
+        }
     }
 }
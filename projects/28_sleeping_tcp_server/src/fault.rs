@@ -0,0 +1,124 @@
+// Fault injection.
+// For every message the server rolls a number in 0..100 and compares it against the configured
+// percentages to decide whether to misbehave.  The percentages are checked in a fixed order (drop,
+// partial, corrupt, hang) so, for a given seed, the same rolls always give the same faults.
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    None,
+    Drop,         // Close the connection without replying.
+    PartialWrite, // Send only the first half of the reply, then close.
+    CorruptByte,  // Flip the bits of one byte in the reply.
+    NeverRespond, // Keep the connection open but never reply again.
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultRates {
+    pub drop: u8,
+    pub partial: u8,
+    pub corrupt: u8,
+    pub hang: u8,
+}
+
+impl FaultRates {
+    pub fn total(&self) -> u32 {
+        self.drop as u32 + self.partial as u32 + self.corrupt as u32 + self.hang as u32
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.total() > 100 {
+            return Err(format!(
+                "fault percentages add up to {}%, the maximum is 100%",
+                self.total()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn pick<R: Rng>(&self, rng: &mut R) -> Fault {
+        let roll = rng.gen_range(0..100u32);
+        let mut threshold = 0;
+
+        for (rate, fault) in [
+            (self.drop, Fault::Drop),
+            (self.partial, Fault::PartialWrite),
+            (self.corrupt, Fault::CorruptByte),
+            (self.hang, Fault::NeverRespond),
+        ] {
+            threshold += rate as u32;
+            if roll < threshold {
+                return fault;
+            }
+        }
+
+        Fault::None
+    }
+}
+
+// Flip every bit of one randomly chosen byte.  Flipping all bits guarantees the byte changes.
+pub fn corrupt<R: Rng>(buf: &mut [u8], rng: &mut R) {
+    if buf.is_empty() {
+        return;
+    }
+    let i = rng.gen_range(0..buf.len());
+    buf[i] ^= 0xFF;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn no_rates_means_no_faults() {
+        let rates = FaultRates::default();
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        assert!((0..1000).all(|_| rates.pick(&mut rng) == Fault::None));
+    }
+
+    #[test]
+    fn hundred_percent_always_fires() {
+        let rates = FaultRates {
+            corrupt: 100,
+            ..FaultRates::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        assert!((0..1000).all(|_| rates.pick(&mut rng) == Fault::CorruptByte));
+    }
+
+    #[test]
+    fn rates_over_one_hundred_are_rejected() {
+        let rates = FaultRates {
+            drop: 60,
+            hang: 50,
+            ..FaultRates::default()
+        };
+        assert!(rates.validate().is_err());
+    }
+
+    #[test]
+    fn rates_are_roughly_honoured() {
+        let rates = FaultRates {
+            drop: 25,
+            partial: 25,
+            ..FaultRates::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(99);
+        let drops = (0..10_000)
+            .filter(|_| rates.pick(&mut rng) == Fault::Drop)
+            .count();
+        assert!((2000..3000).contains(&drops), "drops = {}", drops);
+    }
+
+    #[test]
+    fn corrupt_changes_exactly_one_byte() {
+        let original = b"hello world\n".to_vec();
+        let mut buf = original.clone();
+        corrupt(&mut buf, &mut ChaCha8Rng::seed_from_u64(3));
+
+        let changed = original.iter().zip(&buf).filter(|(a, b)| a != b).count();
+        assert_eq!(changed, 1);
+    }
+}
@@ -0,0 +1,145 @@
+// Latency distributions for the chaos server.
+// The original example picked a random sleep of 0-5s from a hardcoded array.  That is still the
+// default (see 'Latency::default') but we can now describe other shapes on the command line:
+//
+//   fixed:250           always sleep 250ms
+//   uniform:100-2000    sleep between 100ms and 2000ms (inclusive)
+//   choice:0,1000,5000  pick one of the listed values
+//   exp:500             exponential distribution with a 500ms mean (long tail of slow replies)
+//
+// All values are in milliseconds.
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Latency {
+    Fixed(Duration),
+    Uniform { min: Duration, max: Duration },
+    Choice(Vec<Duration>),
+    Exponential { mean: Duration },
+}
+
+impl Default for Latency {
+    // The behaviour of the original 'secs_array'.
+    fn default() -> Latency {
+        Latency::Choice((0..=5).map(Duration::from_secs).collect())
+    }
+}
+
+impl Latency {
+    pub fn parse(spec: &str) -> Result<Latency, String> {
+        let (kind, value) = spec
+            .split_once(':')
+            .ok_or_else(|| format!("latency '{}': expected <kind>:<millis>", spec))?;
+
+        match kind {
+            "fixed" => Ok(Latency::Fixed(parse_millis(value)?)),
+            "uniform" => {
+                let (min, max) = value
+                    .split_once('-')
+                    .ok_or_else(|| format!("latency '{}': expected uniform:<min>-<max>", spec))?;
+                let (min, max) = (parse_millis(min)?, parse_millis(max)?);
+                if min > max {
+                    return Err(format!("latency '{}': min is greater than max", spec));
+                }
+                Ok(Latency::Uniform { min, max })
+            }
+            "choice" => {
+                let values = value
+                    .split(',')
+                    .map(parse_millis)
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Latency::Choice(values))
+            }
+            "exp" => Ok(Latency::Exponential {
+                mean: parse_millis(value)?,
+            }),
+            _ => Err(format!("latency '{}': unknown kind '{}'", spec, kind)),
+        }
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Duration {
+        match self {
+            Latency::Fixed(d) => *d,
+            Latency::Uniform { min, max } => rng.gen_range(*min..=*max),
+            Latency::Choice(values) => values.choose(rng).copied().unwrap_or_default(),
+            Latency::Exponential { mean } => {
+                // Inverse transform sampling: -mean * ln(1 - u) for u in [0, 1).
+                let u: f64 = rng.gen();
+                mean.mul_f64(-(1.0 - u).ln())
+            }
+        }
+    }
+}
+
+fn parse_millis(value: &str) -> Result<Duration, String> {
+    value
+        .trim()
+        .parse::<u64>()
+        .map(Duration::from_millis)
+        .map_err(|_| format!("'{}' is not a number of milliseconds", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn parses_each_kind() {
+        assert_eq!(
+            Latency::parse("fixed:250"),
+            Ok(Latency::Fixed(Duration::from_millis(250)))
+        );
+        assert_eq!(
+            Latency::parse("uniform:10-20"),
+            Ok(Latency::Uniform {
+                min: Duration::from_millis(10),
+                max: Duration::from_millis(20)
+            })
+        );
+        assert_eq!(
+            Latency::parse("choice:0,5"),
+            Ok(Latency::Choice(vec![
+                Duration::ZERO,
+                Duration::from_millis(5)
+            ]))
+        );
+        assert_eq!(
+            Latency::parse("exp:100"),
+            Ok(Latency::Exponential {
+                mean: Duration::from_millis(100)
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        assert!(Latency::parse("fixed").is_err());
+        assert!(Latency::parse("fixed:abc").is_err());
+        assert!(Latency::parse("uniform:20-10").is_err());
+        assert!(Latency::parse("gaussian:10").is_err());
+    }
+
+    #[test]
+    fn uniform_samples_stay_in_range() {
+        let latency = Latency::parse("uniform:10-20").unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for _ in 0..1000 {
+            let d = latency.sample(&mut rng);
+            assert!(d >= Duration::from_millis(10) && d <= Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_samples() {
+        let latency = Latency::parse("exp:100").unwrap();
+        let mut a = ChaCha8Rng::seed_from_u64(42);
+        let mut b = ChaCha8Rng::seed_from_u64(42);
+        for _ in 0..100 {
+            assert_eq!(latency.sample(&mut a), latency.sample(&mut b));
+        }
+    }
+}
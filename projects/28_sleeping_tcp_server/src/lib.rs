@@ -0,0 +1,428 @@
+// A chaos version of the sleeping echo server.
+// The book example sleeps for a random 0-5s before echoing.  Here the delay comes from a
+// configurable 'Latency' distribution and, on top of that, the server can misbehave on purpose
+// (see 'Fault') so that client timeout and retry code can be tested.
+//
+// Giving a '--seed' makes a run reproducible: every connection gets its own RNG seeded from the
+// base seed plus the connection number, so the Nth connection always sees the same sleeps and
+// faults regardless of how the threads are scheduled.
+pub mod fault;
+pub mod latency;
+
+use json_with_serde::{ConfigError, ServerConfig};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::io::{Error, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use tcp_support::tls::{self, TlsServerConfig};
+use tcp_support::{ConnectionLimit, Permit};
+
+use fault::{Fault, FaultRates};
+use latency::Latency;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChaosConfig {
+    pub seed: Option<u64>,
+    pub latency: Latency,
+    pub faults: FaultRates,
+}
+
+pub const USAGE: &str = "\
+Usage: sleeping_tcp_server [options]
+  --seed <u64>        seed the RNG so runs are reproducible
+  --latency <spec>    fixed:<ms> | uniform:<min>-<max> | choice:<ms>,<ms>,.. | exp:<mean>
+  --drop <pct>        percentage of messages that close the connection
+  --partial <pct>     percentage of messages that get half a reply, then close
+  --corrupt <pct>     percentage of replies with one corrupted byte
+  --hang <pct>        percentage of messages after which the server never replies
+  --bind <addr>       the same as --listen (default 127.0.0.1:8888)
+Server settings (--listen, --workers, --tls-cert, --tls-key, --config) are read by ServerConfig,
+see the json_with_serde project.";
+
+// Unlike the other servers this only listens on localhost unless told otherwise, as it is a test
+// tool that misbehaves on purpose.
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8888";
+
+// ServerConfig::load with DEFAULT_LISTEN as the default address.  --bind, the flag from before
+// ServerConfig, is still taken as another name for --listen.
+pub fn load_server_config(args: &mut Vec<String>) -> Result<ServerConfig, ConfigError> {
+    for arg in args.iter_mut().filter(|arg| *arg == "--bind") {
+        *arg = String::from("--listen");
+    }
+    let defaults = ServerConfig {
+        listen: String::from(DEFAULT_LISTEN),
+        ..ServerConfig::default()
+    };
+    ServerConfig::load_over(defaults, args)
+}
+
+impl ChaosConfig {
+    // Same shape as 'Config::new' in the io project, args[0] is the program name.
+    pub fn from_args(args: &[String]) -> Result<ChaosConfig, String> {
+        let mut config = ChaosConfig::default();
+        let mut args = args.iter().skip(1);
+
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} expects a value", flag))?;
+
+            match flag.as_str() {
+                "--seed" => {
+                    config.seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("--seed: '{}' is not a u64", value))?,
+                    )
+                }
+                "--latency" => config.latency = Latency::parse(value)?,
+                "--drop" => config.faults.drop = parse_percent(flag, value)?,
+                "--partial" => config.faults.partial = parse_percent(flag, value)?,
+                "--corrupt" => config.faults.corrupt = parse_percent(flag, value)?,
+                "--hang" => config.faults.hang = parse_percent(flag, value)?,
+                _ => return Err(format!("unknown flag '{}'", flag)),
+            }
+        }
+
+        config.faults.validate()?;
+        Ok(config)
+    }
+
+    // The RNG for the nth accepted connection.
+    pub fn rng_for_connection(&self, n: u64) -> ChaCha8Rng {
+        match self.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed.wrapping_add(n)),
+            None => ChaCha8Rng::from_entropy(),
+        }
+    }
+}
+
+fn parse_percent(flag: &str, value: &str) -> Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(pct) if pct <= 100 => Ok(pct),
+        _ => Err(format!("{}: '{}' is not a percentage (0-100)", flag, value)),
+    }
+}
+
+pub fn handle_client<S: Read + Write, R: Rng>(
+    mut stream: S,
+    rng: &mut R,
+    config: &ChaosConfig,
+) -> Result<(), Error> {
+    match serve_messages(&mut stream, rng, config)? {
+        Ending::Closed => Ok(()),
+        Ending::Hung => drain(&mut stream),
+    }
+}
+
+enum Ending {
+    Closed, // By the client, or by a Drop or PartialWrite fault.
+    Hung,   // By a NeverRespond fault.
+}
+
+fn serve_messages<S: Read + Write, R: Rng>(
+    stream: &mut S,
+    rng: &mut R,
+    config: &ChaosConfig,
+) -> Result<Ending, Error> {
+    let mut buf = [0; 512];
+
+    loop {
+        let bytes_read = stream.read(&mut buf)?;
+        if bytes_read == 0 {
+            return Ok(Ending::Closed);
+        };
+
+        let sleep = config.latency.sample(rng);
+        let fault = config.faults.pick(rng);
+
+        println!("Sleeping for {:?}, fault: {:?}", sleep, fault);
+        thread::sleep(sleep);
+
+        let reply = &mut buf[..bytes_read];
+        match fault {
+            Fault::None => stream.write_all(reply)?,
+            Fault::Drop => return Ok(Ending::Closed),
+            Fault::PartialWrite => {
+                stream.write_all(&reply[..bytes_read / 2])?;
+                return Ok(Ending::Closed);
+            }
+            Fault::CorruptByte => {
+                fault::corrupt(reply, rng);
+                stream.write_all(reply)?;
+            }
+            Fault::NeverRespond => return Ok(Ending::Hung),
+        }
+    }
+}
+
+// Once hung we keep draining input so the client sees a live, silent connection.
+fn drain<S: Read>(stream: &mut S) -> Result<(), Error> {
+    let mut buf = [0; 512];
+    while stream.read(&mut buf)? > 0 {}
+    Ok(())
+}
+
+// With a TLS config every connection is wrapped in a TLS session before the chaos starts.
+//
+// Connections are numbered in the order they are accepted, on this thread, so that the numbering
+// (and therefore each connection's RNG) doesn't depend on scheduling.  Each one then gets its own
+// thread, with at most 'workers' being served at once, as in the other servers.  A connection that
+// has been hung on purpose is no longer being served, so it gives its place back: otherwise a
+// few hung clients would keep everyone else out.
+pub fn serve(
+    listener: TcpListener,
+    workers: u64,
+    config: ChaosConfig,
+    tls: Option<Arc<TlsServerConfig>>,
+) {
+    let config = Arc::new(config);
+    let limit = ConnectionLimit::new(workers);
+
+    for (n, stream) in listener.incoming().enumerate() {
+        match stream {
+            Err(e) => eprintln!("Failed: {}", e),
+            Ok(stream) => {
+                let Some(permit) = limit.try_acquire() else {
+                    eprintln!("Refusing a connection, {} already open", limit.max());
+                    continue;
+                };
+                let config = Arc::clone(&config);
+                let tls = tls.clone();
+                let mut rng = config.rng_for_connection(n as u64);
+                thread::spawn(move || {
+                    handle_connection(stream, tls.as_ref(), &mut rng, &config, permit)
+                        .unwrap_or_else(|error| eprintln!("{:?}", error));
+                });
+            }
+        }
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    tls: Option<&Arc<TlsServerConfig>>,
+    rng: &mut ChaCha8Rng,
+    config: &ChaosConfig,
+    permit: Permit,
+) -> Result<(), Error> {
+    println!("Incoming connection from: {}", stream.peer_addr()?);
+    let mut stream = tls::accept(stream, tls)?;
+    match serve_messages(&mut stream, rng, config)? {
+        Ending::Closed => Ok(()),
+        Ending::Hung => {
+            drop(permit);
+            drain(&mut stream)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::time::Duration;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        std::iter::once("sleeping_tcp_server")
+            .chain(list.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    fn start(extra: &[&str]) -> std::net::SocketAddr {
+        let mut list = vec!["--latency", "fixed:0"];
+        list.extend_from_slice(extra);
+        start_with(ChaosConfig::from_args(&args(&list)).unwrap(), 4)
+    }
+
+    fn start_with(config: ChaosConfig, workers: u64) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, workers, config, None));
+        addr
+    }
+
+    #[test]
+    fn defaults_match_the_original_server() {
+        let config = ChaosConfig::from_args(&args(&[])).unwrap();
+        assert_eq!(config, ChaosConfig::default());
+        assert_eq!(config.faults.total(), 0);
+    }
+
+    #[test]
+    fn parses_flags() {
+        let config = ChaosConfig::from_args(&args(&[
+            "--seed",
+            "5",
+            "--drop",
+            "10",
+            "--hang",
+            "20",
+            "--latency",
+            "fixed:3",
+        ]))
+        .unwrap();
+        assert_eq!(config.seed, Some(5));
+        assert_eq!(config.faults.drop, 10);
+        assert_eq!(config.faults.hang, 20);
+        assert_eq!(config.latency, Latency::Fixed(Duration::from_millis(3)));
+    }
+
+    #[test]
+    fn rejects_bad_flags() {
+        assert!(ChaosConfig::from_args(&args(&["--drop"])).is_err());
+        assert!(ChaosConfig::from_args(&args(&["--drop", "101"])).is_err());
+        assert!(ChaosConfig::from_args(&args(&["--drop", "60", "--corrupt", "60"])).is_err());
+        assert!(ChaosConfig::from_args(&args(&["--colour", "red"])).is_err());
+    }
+
+    #[test]
+    fn seeded_connections_are_reproducible() {
+        let config = ChaosConfig {
+            seed: Some(1234),
+            faults: FaultRates {
+                drop: 30,
+                corrupt: 30,
+                ..FaultRates::default()
+            },
+            ..ChaosConfig::default()
+        };
+
+        let run = |n| {
+            let mut rng = config.rng_for_connection(n);
+            (0..50)
+                .map(|_| {
+                    (
+                        config.latency.sample(&mut rng),
+                        config.faults.pick(&mut rng),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(0), run(0));
+        assert_ne!(run(0), run(1));
+    }
+
+    #[test]
+    fn echoes_when_there_are_no_faults() {
+        let mut stream = TcpStream::connect(start(&[])).unwrap();
+        stream.write_all(b"hello\n").unwrap();
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        assert_eq!(line, "hello\n");
+    }
+
+    #[test]
+    fn drop_closes_the_connection() {
+        let mut stream = TcpStream::connect(start(&["--drop", "100"])).unwrap();
+        stream.write_all(b"hello\n").unwrap();
+
+        let mut buf = Vec::new();
+        assert_eq!(stream.read_to_end(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn partial_write_sends_half_then_closes() {
+        let mut stream = TcpStream::connect(start(&["--partial", "100"])).unwrap();
+        stream.write_all(b"abcdefgh").unwrap();
+
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"abcd");
+    }
+
+    #[test]
+    fn hang_never_replies() {
+        let mut stream = TcpStream::connect(start(&["--hang", "100"])).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        stream.write_all(b"hello\n").unwrap();
+
+        let mut buf = [0; 16];
+        let err = stream.read(&mut buf).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+        ));
+    }
+
+    #[test]
+    fn a_hung_connection_doesnt_hold_up_the_next() {
+        // A seed where the first message on connection 0 hangs and on connection 1 is echoed,
+        // drawn the way handle_client draws them.
+        let first_fault = |config: &ChaosConfig, n| {
+            let mut rng = config.rng_for_connection(n);
+            config.latency.sample(&mut rng);
+            config.faults.pick(&mut rng)
+        };
+        let config = (0..)
+            .map(|seed| ChaosConfig {
+                seed: Some(seed),
+                latency: Latency::Fixed(Duration::ZERO),
+                faults: FaultRates {
+                    hang: 50,
+                    ..FaultRates::default()
+                },
+            })
+            .find(|config| {
+                first_fault(config, 0) == Fault::NeverRespond
+                    && first_fault(config, 1) == Fault::None
+            })
+            .unwrap();
+        let addr = start_with(config, 1);
+
+        let mut hung = TcpStream::connect(addr).unwrap();
+        hung.write_all(b"hello\n").unwrap();
+        thread::sleep(Duration::from_millis(100));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream.write_all(b"still here\n").unwrap();
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        assert_eq!(line, "still here\n");
+        drop(hung);
+    }
+
+    #[test]
+    fn connections_over_the_limit_are_refused() {
+        let config = ChaosConfig::from_args(&args(&["--latency", "fixed:0"])).unwrap();
+        let addr = start_with(config, 1);
+
+        // Connected, so being served, but never sends anything.
+        let idle = TcpStream::connect(addr).unwrap();
+        thread::sleep(Duration::from_millis(100));
+
+        let mut refused = TcpStream::connect(addr).unwrap();
+        refused
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut buf = Vec::new();
+        // Closed straight away: an end of file, or a reset if the close beat the client's data.
+        let _ = refused.write_all(b"hello\n");
+        assert!(matches!(refused.read_to_end(&mut buf), Ok(0) | Err(_)));
+        assert!(buf.is_empty());
+        drop(idle);
+    }
+
+    #[test]
+    fn listens_on_localhost_by_default_and_takes_bind() {
+        let mut list = args(&["--seed", "1"]);
+        let config = load_server_config(&mut list).unwrap();
+        assert_eq!(config.listen, DEFAULT_LISTEN);
+        assert_eq!(list, args(&["--seed", "1"]));
+
+        let mut list = args(&["--bind", "127.0.0.1:9999", "--seed", "1"]);
+        let config = load_server_config(&mut list).unwrap();
+        assert_eq!(config.listen, "127.0.0.1:9999");
+        assert_eq!(list, args(&["--seed", "1"]));
+    }
+}
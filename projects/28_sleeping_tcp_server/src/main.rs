@@ -1,42 +1,41 @@
 // Network Programming in Rust,  Abhishek Chanda
 // Page 68
-extern crate rand;
-
-use rand::{rngs::ThreadRng, seq::SliceRandom, thread_rng};
-use std::io::{Error, Read, Write};
-use std::net::{TcpListener, TcpStream};
-use std::thread;
-use std::time::Duration;
-
-fn handle_client(mut stream: TcpStream) -> Result<(), Error> {
-    let mut buf = [0; 512];
-    loop {
-        let bytes_read = stream.read(&mut buf)?;
-        if bytes_read == 0 {
-            return Ok(());
-        };
-
-        let mut rng: ThreadRng = thread_rng();
-        let secs_array: Vec<u32> = vec![0, 1, 2, 3, 4, 5];
-        let secs: u32 = *secs_array.choose(&mut rng).unwrap();
-        let sleep = Duration::from_secs(secs as u64);
-
-        println!("Sleeping for {:?}", sleep);
-        std::thread::sleep(sleep);
-        stream.write(&buf[..bytes_read])?;
-    }
-}
+//
+// Extended into a chaos server, see src/lib.rs.
+//
+// cargo run
+// ^^^ behaves like the book example, sleeps 0-5s then echoes.
+// cargo run -- --listen 127.0.0.1:8888 --seed 42 --latency uniform:0-500 --drop 10 --corrupt 5
+// ^^^ reproducible latency and faults for testing client timeout and retry code.
+// cargo run -- --tls-cert cert.pem --tls-key key.pem --drop 10
+// ^^^ the same over TLS.
+// cargo run -- --listen 0.0.0.0:8888
+// ^^^ reachable from other machines, by default it only listens on 127.0.0.1.
+use sleeping_tcp_server::{load_server_config, serve, ChaosConfig, USAGE};
+use std::env;
+use std::net::TcpListener;
+use std::process;
+use tcp_support::tls;
 
 fn main() {
-    let listener = TcpListener::bind("127.0.0.1:8888").expect("Failed to bind");
-    for stream in listener.incoming() {
-        match stream {
-            Err(e) => eprintln!("Failed: {}", e),
-            Ok(stream) => {
-                thread::spawn(move || {
-                    handle_client(stream).unwrap_or_else(|error| eprintln!("{:?}", error));
-                });
-            }
-        }
-    }
+    let mut args: Vec<String> = env::args().collect();
+    let server_config = load_server_config(&mut args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    let config = ChaosConfig::from_args(&args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    });
+    let tls_config = server_config
+        .tls_paths()
+        .map(|(cert, key)| tls::server_config(cert, key))
+        .transpose()
+        .expect("Failed to load TLS certificate");
+
+    println!("{:?}", server_config);
+    println!("{:?}", config);
+    let listener = TcpListener::bind(&server_config.listen).expect("Failed to bind");
+    serve(listener, server_config.workers, config, tls_config);
 }
@@ -1,35 +1,50 @@
 // Network Programming in Rust,  Abhishek Chanda
 // Page 69
-use std::io::{self, BufRead, BufReader, Write};
-use std::net::{SocketAddr, TcpStream};
-use std::str;
+//
+// Uses the Connection type from the tcp_support project so that replies are read through one
+// long-lived buffer.
+//
+// cargo run -- --tls-ca cert.pem
+// ^^^ talk TLS to a server started with that certificate.
+use std::net::SocketAddr;
 use std::time::Duration;
+use std::{env, io, process};
+use tcp_support::tls::{self, TlsOptions};
 
 fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    let options = TlsOptions::extract(&mut args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+
     let timeout = Duration::from_secs(3);
     let remote: SocketAddr = "127.0.0.1:8888".parse().unwrap();
-    let mut stream =
-        TcpStream::connect_timeout(&remote, timeout).expect("Failed to connect to server");
-    stream
-        .set_read_timeout(Some(timeout))
-        .expect("Failed to set read timeout");
+    let mut connection =
+        tls::connect_timeout(&remote, timeout, &options).expect("Failed to connect to server");
 
     loop {
         let mut input = String::new();
-        let mut buffer: Vec<u8> = Vec::new();
         io::stdin()
             .read_line(&mut input)
             .expect("Failed to read from stdin");
-        stream
-            .write(input.as_bytes())
-            .expect("Failed to write to server");
 
-        let mut reader = BufReader::new(&stream);
-        reader
-            .read_until(b'\n', &mut buffer)
-            .expect("Failed to read into buffer");
+        let reply = connection
+            .request(&input)
+            .expect("Failed to talk to server");
+        match reply {
+            Some(line) => print!("{}", line),
+            None => {
+                eprintln!("Server closed the connection");
+                break;
+            }
+        }
 
-        let utf8: &str = str::from_utf8(&buffer).expect("Failed to write buffer as string");
-        print!("{}", utf8);
+        for line in connection
+            .read_buffered_lines()
+            .expect("Failed to read into buffer")
+        {
+            print!("{}", line);
+        }
     }
 }
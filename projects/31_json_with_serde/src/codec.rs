@@ -0,0 +1,199 @@
+// One place to turn any Serialize/Deserialize type to and from bytes, whatever the format.
+//
+//   let bytes = Format::MessagePack.encode(&config)?;
+//   let config: ServerConfig = Format::MessagePack.decode(&bytes)?;
+//
+// Text formats (JSON, TOML, YAML) suit config files, binary formats (MessagePack, CBOR) are
+// smaller and quicker on the wire.  Note TOML needs a table at the top level, so it can encode
+// structs but not bare numbers or lists, and its integers are i64 so u64 values above i64::MAX
+// can't be written.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+    MessagePack,
+    Cbor,
+}
+
+impl Format {
+    pub const ALL: [Format; 5] = [
+        Format::Json,
+        Format::Toml,
+        Format::Yaml,
+        Format::MessagePack,
+        Format::Cbor,
+    ];
+
+    // The short name used on the command line and in the protocol handshake.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Toml => "toml",
+            Format::Yaml => "yaml",
+            Format::MessagePack => "msgpack",
+            Format::Cbor => "cbor",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        Format::ALL
+            .iter()
+            .copied()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "msgpack" | "mp" => Some(Format::MessagePack),
+            "cbor" => Some(Format::Cbor),
+            _ => None,
+        }
+    }
+
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Format::MessagePack | Format::Cbor)
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        let error = |message: String| CodecError {
+            format: *self,
+            message,
+        };
+
+        match self {
+            Format::Json => serde_json::to_vec(value).map_err(|e| error(e.to_string())),
+            Format::Toml => toml::to_string(value)
+                .map(String::into_bytes)
+                .map_err(|e| error(e.to_string())),
+            Format::Yaml => serde_yaml_ng::to_string(value)
+                .map(String::into_bytes)
+                .map_err(|e| error(e.to_string())),
+            // Named fields (a map rather than an array) so other MessagePack readers can use it.
+            Format::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| error(e.to_string())),
+            Format::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(|e| error(e.to_string()))?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        let error = |message: String| CodecError {
+            format: *self,
+            message,
+        };
+        let text = || std::str::from_utf8(bytes).map_err(|e| error(e.to_string()));
+
+        match self {
+            Format::Json => serde_json::from_slice(bytes).map_err(|e| error(e.to_string())),
+            Format::Toml => toml::from_str(text()?).map_err(|e| error(e.message().to_string())),
+            Format::Yaml => serde_yaml_ng::from_slice(bytes).map_err(|e| error(e.to_string())),
+            Format::MessagePack => rmp_serde::from_slice(bytes).map_err(|e| error(e.to_string())),
+            Format::Cbor => ciborium::from_reader(bytes).map_err(|e| error(e.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodecError {
+    pub format: Format,
+    pub message: String,
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.format, self.message)
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServerConfig;
+    use proptest::prelude::*;
+    use std::path::PathBuf;
+
+    fn server_config() -> impl Strategy<Value = ServerConfig> {
+        (
+            // TOML integers are i64.
+            0..=i64::MAX as u64,
+            any::<bool>(),
+            proptest::option::of("[a-z]{1,10}(\\.[a-z]{1,10}){0,3}"),
+            "[a-z0-9.]{1,15}:[0-9]{1,5}",
+            proptest::option::of("[a-z/]{1,20}\\.pem"),
+            proptest::option::of("[a-z/]{1,20}\\.pem"),
+        )
+            .prop_map(
+                |(workers, ignore, auth_server, listen, tls_cert, tls_key)| ServerConfig {
+                    workers,
+                    ignore,
+                    auth_server,
+                    listen,
+                    tls_cert: tls_cert.map(PathBuf::from),
+                    tls_key: tls_key.map(PathBuf::from),
+                },
+            )
+    }
+
+    proptest! {
+        // A fixed seed so every run (and every machine) tries the same cases.
+        #![proptest_config(ProptestConfig {
+            rng_seed: proptest::test_runner::RngSeed::Fixed(31),
+            ..ProptestConfig::default()
+        })]
+
+        #[test]
+        fn server_config_round_trips_in_every_format(config in server_config()) {
+            for format in Format::ALL {
+                let bytes = format.encode(&config).unwrap();
+                let decoded: ServerConfig = format.decode(&bytes).unwrap();
+                prop_assert_eq!(&decoded, &config, "format {}", format);
+            }
+        }
+    }
+
+    #[test]
+    fn names_and_extensions() {
+        for format in Format::ALL {
+            assert_eq!(Format::from_name(format.name()), Some(format));
+        }
+        assert_eq!(Format::from_name("MSGPACK"), Some(Format::MessagePack));
+        assert_eq!(Format::from_name("xml"), None);
+        assert_eq!(Format::from_extension("yml"), Some(Format::Yaml));
+        assert_eq!(Format::from_extension("ini"), None);
+    }
+
+    #[test]
+    fn binary_formats_are_smaller_than_json() {
+        let config = ServerConfig::default();
+        let json = Format::Json.encode(&config).unwrap();
+        assert!(Format::MessagePack.encode(&config).unwrap().len() < json.len());
+        assert!(Format::Cbor.encode(&config).unwrap().len() < json.len());
+    }
+
+    #[test]
+    fn decode_errors_name_the_format() {
+        let err = Format::Cbor
+            .decode::<ServerConfig>(b"not cbor")
+            .unwrap_err();
+        assert_eq!(err.format, Format::Cbor);
+        assert!(err.to_string().starts_with("cbor: "));
+    }
+}
@@ -0,0 +1,96 @@
+// Network Programming in Rust,  Abhishek Chanda
+// Page 90
+//
+// The book's ServerConfig, shared with the TCP server projects so they can be configured from a
+// file, the environment or the command line (see loader.rs).  Files, and anything else, can be in
+// any of the formats in codec.rs.
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+extern crate serde_json;
+
+pub mod codec;
+pub mod loader;
+pub mod validate;
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub use codec::{CodecError, Format};
+pub use loader::PartialServerConfig;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    // The most clients a server handles at once.  Each client gets its own thread, and one that
+    // connects while this many are open is closed straight away.  The sleeping server doesn't
+    // count a connection it has hung on purpose.
+    pub workers: u64,
+    pub ignore: bool,
+    pub auth_server: Option<String>,
+    pub listen: String,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            workers: 100,
+            ignore: false,
+            auth_server: None,
+            listen: String::from("0.0.0.0:8888"),
+            tls_cert: None,
+            tls_key: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    // Both paths, when the server should use TLS.  'validate' makes sure they come as a pair.
+    pub fn tls_paths(&self) -> Option<(&Path, &Path)> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Some((cert, key)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    // The config file couldn't be read.
+    Io { path: PathBuf, source: io::Error },
+    // The config file couldn't be parsed.
+    Parse { path: PathBuf, message: String },
+    // A field has a bad value, 'field' is the path to it, e.g. "auth_server".
+    Invalid { field: String, message: String },
+}
+
+impl ConfigError {
+    pub fn invalid(field: &str, message: impl Into<String>) -> ConfigError {
+        ConfigError::Invalid {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ConfigError::Parse { path, message } => write!(f, "{}: {}", path.display(), message),
+            ConfigError::Invalid { field, message } => write!(f, "{}: {}", field, message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
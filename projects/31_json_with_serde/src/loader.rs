@@ -0,0 +1,374 @@
+// Layered configuration.
+// A ServerConfig is built from up to four layers, each one overriding the last:
+//
+//   1).  ServerConfig::default()
+//   2).  A JSON, TOML or YAML file, given by --config <path> or SERVER_CONFIG
+//   3).  Environment variables, SERVER_<FIELD> e.g. SERVER_WORKERS=8
+//   4).  Command line flags, --<field> e.g. --workers 8, --auth-server auth.server.io
+//
+// Every layer is read into a PartialServerConfig where each field is optional, so a layer only
+// overrides what it actually sets.  The merged result is then validated (see validate.rs).
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::validate::validate;
+use crate::{ConfigError, Format, ServerConfig};
+
+pub const ENV_PREFIX: &str = "SERVER_";
+pub const ENV_CONFIG_FILE: &str = "SERVER_CONFIG";
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PartialServerConfig {
+    pub workers: Option<u64>,
+    pub ignore: Option<bool>,
+    pub auth_server: Option<String>,
+    pub listen: Option<String>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+}
+
+impl PartialServerConfig {
+    // The format is picked from the file extension, e.g. .json, .toml or .yaml.
+    pub fn from_file(path: &Path) -> Result<PartialServerConfig, ConfigError> {
+        let parse_error = |message: String| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message,
+        };
+        let format = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(Format::from_extension)
+            .ok_or_else(|| {
+                parse_error(String::from(
+                    "unknown config format, expected .json, .toml, .yaml, .msgpack or .cbor",
+                ))
+            })?;
+
+        let contents = fs::read(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        format
+            .decode(&contents)
+            .map_err(|e| parse_error(e.to_string()))
+    }
+
+    pub fn from_env() -> Result<PartialServerConfig, ConfigError> {
+        PartialServerConfig::from_vars(env::vars())
+    }
+
+    // Variables without the SERVER_ prefix, or that don't name a field, are ignored.
+    pub fn from_vars<I>(vars: I) -> Result<PartialServerConfig, ConfigError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut partial = PartialServerConfig::default();
+        for (name, value) in vars {
+            if let Some(field) = name.strip_prefix(ENV_PREFIX) {
+                let field = field.to_lowercase();
+                if FIELDS.contains(&field.as_str()) {
+                    partial.set(&field, &value, &name)?;
+                }
+            }
+        }
+        Ok(partial)
+    }
+
+    // Removes the flags it understands from args, leaving the rest for the program.  Returns the
+    // --config path separately since it says where to find another layer.
+    pub fn from_args(
+        args: &mut Vec<String>,
+    ) -> Result<(PartialServerConfig, Option<PathBuf>), ConfigError> {
+        let mut partial = PartialServerConfig::default();
+        let mut config_file = None;
+        let mut remaining = Vec::with_capacity(args.len());
+        let mut iter = args.drain(..);
+
+        while let Some(arg) = iter.next() {
+            let field = match arg.strip_prefix("--") {
+                Some(name) => name.replace('-', "_"),
+                None => {
+                    remaining.push(arg);
+                    continue;
+                }
+            };
+
+            if field == "ignore" {
+                // A switch, no value needed.
+                partial.ignore = Some(true);
+            } else if field == "config" || FIELDS.contains(&field.as_str()) {
+                let value = iter.next().ok_or_else(|| {
+                    ConfigError::invalid(&field, format!("{} expects a value", arg))
+                })?;
+                if field == "config" {
+                    config_file = Some(PathBuf::from(value));
+                } else {
+                    partial.set(&field, &value, &arg)?;
+                }
+            } else {
+                remaining.push(arg);
+            }
+        }
+
+        drop(iter);
+        *args = remaining;
+        Ok((partial, config_file))
+    }
+
+    // Sets one field from its text form, 'origin' says where the value came from for errors.
+    fn set(&mut self, field: &str, value: &str, origin: &str) -> Result<(), ConfigError> {
+        match field {
+            "workers" => {
+                self.workers = Some(value.parse().map_err(|_| {
+                    ConfigError::invalid(
+                        field,
+                        format!("expected a whole number, got '{}' ({})", value, origin),
+                    )
+                })?)
+            }
+            "ignore" => {
+                self.ignore = Some(match value {
+                    "true" | "1" => true,
+                    "false" | "0" => false,
+                    _ => {
+                        return Err(ConfigError::invalid(
+                            field,
+                            format!("expected true or false, got '{}' ({})", value, origin),
+                        ))
+                    }
+                })
+            }
+            "auth_server" => self.auth_server = Some(value.to_string()),
+            "listen" => self.listen = Some(value.to_string()),
+            "tls_cert" => self.tls_cert = Some(PathBuf::from(value)),
+            "tls_key" => self.tls_key = Some(PathBuf::from(value)),
+            _ => {
+                return Err(ConfigError::invalid(
+                    field,
+                    format!("unknown field ({})", origin),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    // Overwrites the fields of 'config' that this layer sets.
+    pub fn apply_to(self, mut config: ServerConfig) -> ServerConfig {
+        if let Some(workers) = self.workers {
+            config.workers = workers;
+        }
+        if let Some(ignore) = self.ignore {
+            config.ignore = ignore;
+        }
+        if self.auth_server.is_some() {
+            config.auth_server = self.auth_server;
+        }
+        if let Some(listen) = self.listen {
+            config.listen = listen;
+        }
+        if self.tls_cert.is_some() {
+            config.tls_cert = self.tls_cert;
+        }
+        if self.tls_key.is_some() {
+            config.tls_key = self.tls_key;
+        }
+        config
+    }
+}
+
+const FIELDS: [&str; 6] = [
+    "workers",
+    "ignore",
+    "auth_server",
+    "listen",
+    "tls_cert",
+    "tls_key",
+];
+
+impl ServerConfig {
+    // Applies the layers in order on top of the defaults, then validates the result.
+    pub fn from_layers<I>(layers: I) -> Result<ServerConfig, ConfigError>
+    where
+        I: IntoIterator<Item = PartialServerConfig>,
+    {
+        ServerConfig::from_layers_over(ServerConfig::default(), layers)
+    }
+
+    // The same, on top of a program's own defaults.
+    pub fn from_layers_over<I>(
+        defaults: ServerConfig,
+        layers: I,
+    ) -> Result<ServerConfig, ConfigError>
+    where
+        I: IntoIterator<Item = PartialServerConfig>,
+    {
+        let config = layers
+            .into_iter()
+            .fold(defaults, |config, layer| layer.apply_to(config));
+        validate(&config)?;
+        Ok(config)
+    }
+
+    // The usual way to configure a server: defaults, file, environment, then command line.
+    // Recognised flags are removed from args.
+    pub fn load(args: &mut Vec<String>) -> Result<ServerConfig, ConfigError> {
+        ServerConfig::load_over(ServerConfig::default(), args)
+    }
+
+    // The same, for a program whose defaults differ from ServerConfig::default().
+    pub fn load_over(
+        defaults: ServerConfig,
+        args: &mut Vec<String>,
+    ) -> Result<ServerConfig, ConfigError> {
+        let (cli, cli_file) = PartialServerConfig::from_args(args)?;
+        let env = PartialServerConfig::from_env()?;
+
+        let file = match cli_file.or_else(|| env::var_os(ENV_CONFIG_FILE).map(PathBuf::from)) {
+            Some(path) => PartialServerConfig::from_file(&path)?,
+            None => PartialServerConfig::default(),
+        };
+
+        ServerConfig::from_layers_over(defaults, [file, env, cli])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    // The test removes the file when it is done with it.
+    fn write_temp(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("json_with_serde-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn later_layers_win() {
+        let file = PartialServerConfig {
+            workers: Some(4),
+            auth_server: Some(String::from("file.server.io")),
+            ..PartialServerConfig::default()
+        };
+        let env = PartialServerConfig::from_vars(vars(&[("SERVER_WORKERS", "8")])).unwrap();
+        let (cli, _) =
+            PartialServerConfig::from_args(&mut args(&["prog", "--workers", "16"])).unwrap();
+
+        let config = ServerConfig::from_layers([file, env, cli]).unwrap();
+        assert_eq!(config.workers, 16);
+        assert_eq!(config.auth_server.as_deref(), Some("file.server.io"));
+        assert_eq!(config.listen, ServerConfig::default().listen);
+    }
+
+    #[test]
+    fn layers_go_over_a_programs_own_defaults() {
+        let defaults = ServerConfig {
+            listen: String::from("127.0.0.1:8888"),
+            ..ServerConfig::default()
+        };
+        let (cli, _) =
+            PartialServerConfig::from_args(&mut args(&["prog", "--workers", "2"])).unwrap();
+
+        let config = ServerConfig::from_layers_over(defaults.clone(), [cli]).unwrap();
+        assert_eq!(config.workers, 2);
+        assert_eq!(config.listen, "127.0.0.1:8888");
+
+        let (cli, _) =
+            PartialServerConfig::from_args(&mut args(&["prog", "--listen", "0.0.0.0:9999"]))
+                .unwrap();
+        let config = ServerConfig::from_layers_over(defaults, [cli]).unwrap();
+        assert_eq!(config.listen, "0.0.0.0:9999");
+    }
+
+    #[test]
+    fn reads_json_and_toml_files() {
+        let json = write_temp(
+            "a.json",
+            r#"{ "workers": 3, "auth_server": "auth.server.io" }"#,
+        );
+        let toml = write_temp("a.toml", "workers = 3\nauth_server = \"auth.server.io\"\n");
+
+        let from_json = PartialServerConfig::from_file(&json).unwrap();
+        let from_toml = PartialServerConfig::from_file(&toml).unwrap();
+        assert_eq!(from_json, from_toml);
+        assert_eq!(from_json.workers, Some(3));
+        fs::remove_file(&json).unwrap();
+        fs::remove_file(&toml).unwrap();
+    }
+
+    #[test]
+    fn unknown_file_fields_are_rejected() {
+        let path = write_temp("typo.toml", "wrokers = 3\n");
+        let err = PartialServerConfig::from_file(&path).unwrap_err();
+        assert!(err.to_string().contains("wrokers"), "{}", err);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_an_io_error() {
+        let err = PartialServerConfig::from_file(Path::new("/does/not/exist.json")).unwrap_err();
+        assert!(matches!(err, ConfigError::Io { .. }));
+    }
+
+    #[test]
+    fn env_ignores_unrelated_variables() {
+        let partial = PartialServerConfig::from_vars(vars(&[
+            ("PATH", "/usr/bin"),
+            ("SERVER_CONFIG", "server.toml"),
+            ("SERVER_IGNORE", "true"),
+            ("SERVER_AUTH_SERVER", "auth.server.io"),
+        ]))
+        .unwrap();
+        assert_eq!(partial.ignore, Some(true));
+        assert_eq!(partial.auth_server.as_deref(), Some("auth.server.io"));
+    }
+
+    #[test]
+    fn bad_env_value_names_the_field_and_variable() {
+        let err = PartialServerConfig::from_vars(vars(&[("SERVER_WORKERS", "lots")])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "workers: expected a whole number, got 'lots' (SERVER_WORKERS)"
+        );
+    }
+
+    #[test]
+    fn args_keeps_unrecognised_flags() {
+        let mut list = args(&[
+            "prog",
+            "--server",
+            "--ignore",
+            "--auth-server",
+            "auth.server.io",
+            "--config",
+            "x.toml",
+        ]);
+        let (partial, file) = PartialServerConfig::from_args(&mut list).unwrap();
+
+        assert_eq!(list, vec!["prog", "--server"]);
+        assert_eq!(partial.ignore, Some(true));
+        assert_eq!(partial.auth_server.as_deref(), Some("auth.server.io"));
+        assert_eq!(file, Some(PathBuf::from("x.toml")));
+    }
+
+    #[test]
+    fn invalid_values_fail_after_merging() {
+        let (cli, _) =
+            PartialServerConfig::from_args(&mut args(&["prog", "--auth-server", "bad..host"]))
+                .unwrap();
+        let err = ServerConfig::from_layers([cli]).unwrap_err();
+        assert_eq!(err.to_string(), "auth_server: invalid hostname 'bad..host'");
+    }
+}
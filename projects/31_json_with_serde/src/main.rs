@@ -1,23 +1,24 @@
 // Network Programming in Rust,  Abhishek Chanda
 // Page 90
-#[macro_use]
-extern crate serde_derive;
-
-extern crate serde;
+//
+// ServerConfig now lives in src/lib.rs so the TCP servers can share it.
+//
+// cargo run
+// cargo run -- --workers 8 --auth-server auth.server.io
+// SERVER_WORKERS=8 cargo run -- --config server.toml
+// ^^^ see src/loader.rs for how the layers are merged.
+extern crate json_with_serde;
 extern crate serde_json;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct ServerConfig {
-    workers: u64,
-    ignore: bool,
-    auth_server: Option<String>,
-}
+use json_with_serde::{Format, ServerConfig};
+use std::{env, process};
 
 fn main() {
     let config = ServerConfig {
         workers: 100,
         ignore: false,
         auth_server: Some("auth.server.io".to_string()),
+        ..ServerConfig::default()
     };
 
     println!("To and from JSON");
@@ -26,4 +27,19 @@ fn main() {
 
     let obj: ServerConfig = serde_json::from_str(&json).unwrap();
     println!("{:?}", obj);
+
+    // The same through the format agnostic codec, see src/codec.rs.
+    for format in Format::ALL {
+        let bytes = format.encode(&config).unwrap();
+        let obj: ServerConfig = format.decode(&bytes).unwrap();
+        println!("{} ({} bytes): {:?}", format, bytes.len(), obj);
+    }
+
+    println!("Loaded from defaults, file, environment and command line");
+    let mut args: Vec<String> = env::args().collect();
+    let loaded = ServerConfig::load(&mut args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    println!("{:?}", loaded);
 }
@@ -0,0 +1,144 @@
+// Checks a fully merged ServerConfig.  Errors name the offending field, e.g.
+//   auth_server: invalid hostname 'auth..server'
+use crate::{ConfigError, ServerConfig};
+
+pub fn validate(config: &ServerConfig) -> Result<(), ConfigError> {
+    if config.workers == 0 {
+        return Err(ConfigError::invalid("workers", "must be greater than 0"));
+    }
+
+    if let Some(auth_server) = &config.auth_server {
+        if !is_valid_host_and_port(auth_server) {
+            return Err(ConfigError::invalid(
+                "auth_server",
+                format!("invalid hostname '{}'", auth_server),
+            ));
+        }
+    }
+
+    match config.listen.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+        _ => {
+            return Err(ConfigError::invalid(
+                "listen",
+                format!("expected <host>:<port>, got '{}'", config.listen),
+            ))
+        }
+    }
+
+    match (&config.tls_cert, &config.tls_key) {
+        (Some(_), None) => Err(ConfigError::invalid(
+            "tls_key",
+            "required when tls_cert is set",
+        )),
+        (None, Some(_)) => Err(ConfigError::invalid(
+            "tls_cert",
+            "required when tls_key is set",
+        )),
+        _ => Ok(()),
+    }
+}
+
+// A hostname with an optional ':port', e.g. "auth.server.io" or "auth.server.io:443".
+fn is_valid_host_and_port(value: &str) -> bool {
+    match value.rsplit_once(':') {
+        Some((host, port)) => port.parse::<u16>().is_ok() && is_valid_hostname(host),
+        None => is_valid_hostname(value),
+    }
+}
+
+// RFC 1123: dot separated labels of 1-63 letters, digits or hyphens, not starting or ending with
+// a hyphen, at most 253 characters overall.  A trailing dot (fully qualified) is allowed.
+pub fn is_valid_hostname(host: &str) -> bool {
+    let host = host.strip_suffix('.').unwrap_or(host);
+    if host.is_empty() || host.len() > 253 {
+        return false;
+    }
+
+    host.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(validate(&ServerConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn hostnames() {
+        assert!(is_valid_hostname("auth.server.io"));
+        assert!(is_valid_hostname("localhost"));
+        assert!(is_valid_hostname("a-b.example.com."));
+        assert!(!is_valid_hostname(""));
+        assert!(!is_valid_hostname("auth..server"));
+        assert!(!is_valid_hostname("-auth.server"));
+        assert!(!is_valid_hostname("auth_server.io"));
+        assert!(!is_valid_hostname(&"a".repeat(64)));
+    }
+
+    #[test]
+    fn zero_workers_is_rejected() {
+        let config = ServerConfig {
+            workers: 0,
+            ..ServerConfig::default()
+        };
+        assert_eq!(
+            validate(&config).unwrap_err().to_string(),
+            "workers: must be greater than 0"
+        );
+    }
+
+    #[test]
+    fn bad_auth_server_names_the_field() {
+        let config = ServerConfig {
+            auth_server: Some(String::from("auth server")),
+            ..ServerConfig::default()
+        };
+        assert_eq!(
+            validate(&config).unwrap_err().to_string(),
+            "auth_server: invalid hostname 'auth server'"
+        );
+    }
+
+    #[test]
+    fn auth_server_may_have_a_port() {
+        let config = ServerConfig {
+            auth_server: Some(String::from("auth.server.io:8443")),
+            ..ServerConfig::default()
+        };
+        assert!(validate(&config).is_ok());
+    }
+
+    #[test]
+    fn listen_needs_a_port() {
+        let config = ServerConfig {
+            listen: String::from("0.0.0.0"),
+            ..ServerConfig::default()
+        };
+        assert!(validate(&config)
+            .unwrap_err()
+            .to_string()
+            .starts_with("listen: "));
+    }
+
+    #[test]
+    fn tls_paths_come_in_pairs() {
+        let config = ServerConfig {
+            tls_cert: Some("cert.pem".into()),
+            ..ServerConfig::default()
+        };
+        assert_eq!(
+            validate(&config).unwrap_err().to_string(),
+            "tls_key: required when tls_cert is set"
+        );
+    }
+}
@@ -0,0 +1,33 @@
+// Network Programming in Rust,  Abhishek Chanda
+// Page 93
+//
+// The messages sent between the client and server in main.rs.  They are plain serde types, so
+// any format in json_with_serde's codec can carry them, see protocol.rs.
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+
+pub mod protocol;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Point3D {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+impl Point3D {
+    // Distance from the origin.  Squared in f64 since u32::pow overflows for large points.
+    pub fn distance(&self) -> f64 {
+        let (x, y, z) = (f64::from(self.x), f64::from(self.y), f64::from(self.z));
+        (x * x + y * y + z * z).sqrt()
+    }
+}
+
+// The server's answer in the framed protocol.  A struct rather than a bare f64 since TOML can
+// only encode tables.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Reply {
+    pub distance: f64,
+}
@@ -1,81 +1,112 @@
 // Network Programming in Rust,  Abhishek Chanda
 // Page 93
-#[macro_use]
-extern crate serde_derive;
+extern crate json_tcp_client_and_server;
 
-extern crate serde;
-extern crate serde_json;
-
-use std::io::{stdin, BufRead, BufReader, Error, Write};
+use json_tcp_client_and_server::protocol::{self, handle_client};
+use json_tcp_client_and_server::Point3D;
+use json_with_serde::{Format, ServerConfig};
+use std::io::stdin;
 use std::net::{TcpListener, TcpStream};
-use std::{env, str, thread};
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Point3D {
-    x: u32,
-    y: u32,
-    z: u32,
-}
-
-fn handle_client(stream: TcpStream) -> Result<(), Error> {
-    println!("Incoming connection from: {}", stream.peer_addr()?);
+use std::sync::Arc;
+use std::{env, process, thread};
+use tcp_support::tls::{self, TlsOptions, TlsServerConfig};
+use tcp_support::ConnectionLimit;
 
-    let mut data = Vec::new();
-    let mut stream = BufReader::new(stream);
-
-    loop {
-        data.clear();
-        let bytes_read = stream.read_until(b'\n', &mut data)?;
-        println!("Read {} bytes", bytes_read);
-        if bytes_read == 0 {
-            return Ok(());
-        }
-        let input: Point3D = serde_json::from_slice(&data)?;
-        let value = input.x.pow(2) + input.y.pow(2) + input.z.pow(2);
+// cargo run -- --server
+// cargo run -- --client
+//
+// cargo run -- --server --tls-cert cert.pem --tls-key key.pem
+// cargo run -- --client --tls-ca cert.pem
+// ^^^ the same over TLS.
+//
+// cargo run -- --server --config server.toml --workers 4
+// ^^^ server settings come from ServerConfig, see the json_with_serde project.
+//
+// cargo run -- --client --format msgpack,json
+// ^^^ the formats to offer the server, most preferred first, see src/protocol.rs.
+fn main() {
+    let mut args: Vec<_> = env::args().collect();
 
-        write!(stream.get_mut(), "{}", f64::from(value).sqrt())?;
-        write!(stream.get_mut(), "{}", "\n")?;
+    match args.get(1).map(String::as_str) {
+        Some("--server") => server(&mut args),
+        Some("--client") => client(&mut args),
+        _ => usage(),
     }
 }
 
-// cargo run -- --server
-// cargo run -- --client
-fn main() {
-    let args: Vec<_> = env::args().collect();
+fn usage() -> ! {
+    eprintln!("Expected: ");
+    eprintln!("  [--server] [ServerConfig flags, e.g. --tls-cert <pem> --tls-key <pem>]");
+    eprintln!("  [--client] [--tls-ca <pem>] [--format json,toml,yaml,msgpack,cbor]");
+    process::exit(1);
+}
+
+fn server(args: &mut Vec<String>) {
+    let config = ServerConfig::load(args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
     if args.len() != 2 {
-        eprintln!("Expected: ");
-        eprintln!("  [--client] || [--server]");
-        std::process::exit(1);
+        usage();
     }
 
-    if args[1] == "--server" {
-        server();
-    } else if args[1] == "--client" {
-        client();
-    }
+    let tls_config = config
+        .tls_paths()
+        .map(|(cert, key)| tls::server_config(cert, key))
+        .transpose()
+        .expect("Failed to load TLS certificate");
+
+    let listener = TcpListener::bind(&config.listen).expect("Failed to bind");
+    serve(listener, ConnectionLimit::new(config.workers), tls_config);
 }
 
-fn server() {
-    let listener = TcpListener::bind("0.0.0.0:8888").expect("Failed to bind");
+// A thread per client, but at most 'workers' at once.  A client that arrives when they are all
+// busy is turned away, rather than left waiting on clients that may never finish.
+fn serve(listener: TcpListener, limit: ConnectionLimit, tls_config: Option<Arc<TlsServerConfig>>) {
     for stream in listener.incoming() {
         match stream {
             Err(e) => eprintln!("Failed: {}", e),
             Ok(stream) => {
+                let Some(permit) = limit.try_acquire() else {
+                    eprintln!("Refusing a connection, {} already open", limit.max());
+                    continue;
+                };
+                let tls_config = tls_config.clone();
                 thread::spawn(move || {
-                    handle_client(stream).unwrap_or_else(|error| eprintln!("{:?}", error));
+                    let _permit = permit;
+                    let result = stream.peer_addr().and_then(|addr| {
+                        println!("Incoming connection from: {}", addr);
+                        handle_client(tls::accept(stream, tls_config.as_ref())?, &Format::ALL)
+                    });
+                    result.unwrap_or_else(|error| eprintln!("{:?}", error));
                 });
             }
         }
     }
 }
 
-fn client() {
-    let mut stream = TcpStream::connect("127.0.0.1:8888").expect("Failed to connect");
+fn client(args: &mut Vec<String>) {
+    let options = TlsOptions::extract(args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    let formats = formats_from_args(args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    if args.len() != 2 {
+        usage();
+    }
+
+    let stream = TcpStream::connect("127.0.0.1:8888").expect("Failed to connect");
+    let mut connection = tls::client_connection(stream, &options).expect("Failed to connect");
+    let format = protocol::client_handshake(&mut connection, &formats)
+        .expect("Failed to agree on a format with the server");
+    println!("Using {}", format);
     println!("Enter 3d point as comma separated integers");
 
     loop {
         let mut input = String::new();
-        let mut buffer: Vec<u8> = Vec::new();
 
         stdin()
             .read_line(&mut input)
@@ -88,25 +119,27 @@ fn client() {
             z: parts[2].parse().unwrap(),
         };
 
-        let json = serde_json::to_string(&point).unwrap();
-        println!("{}", json);
-
-        let mut bytes_out: Vec<u8> = Vec::new();
-        bytes_out.extend(json.as_bytes());
-        bytes_out.extend("\n".as_bytes());
-        stream
-            .write_all(&bytes_out)
-            .expect("Failed to write to stream");
+        println!("{:?}", point);
 
-        let mut reader = BufReader::new(&stream);
-        reader
-            .read_until(b'\n', &mut buffer)
-            .expect("Failed to read into buffer");
+        let reply =
+            protocol::request(&mut connection, format, &point).expect("Failed to talk to server");
+        println!("Response from server: {}", reply.distance);
+    }
+}
 
-        let input = str::from_utf8(&buffer).expect("Failed to write buffer as string");
-        if input == "" {
-            eprintln!("Empty response from server");
-        }
-        print!("Response from server: {}", input);
+// Removes '--format <names>' from args, defaulting to JSON when it isn't given.
+fn formats_from_args(args: &mut Vec<String>) -> Result<Vec<Format>, String> {
+    let position = match args.iter().position(|arg| arg == "--format") {
+        Some(position) => position,
+        None => return Ok(vec![Format::Json]),
+    };
+    if position + 1 >= args.len() {
+        return Err(String::from("--format expects a comma separated list"));
     }
+
+    let names: Vec<String> = args.drain(position..position + 2).collect();
+    names[1]
+        .split(',')
+        .map(|name| Format::from_name(name).ok_or(format!("unknown format '{}'", name)))
+        .collect()
 }
@@ -0,0 +1,214 @@
+// Format negotiation.
+//
+// The client opens with one text line listing the formats it can speak, most preferred first:
+//
+//   HELLO msgpack,cbor,json
+//
+// The server picks the first one it also supports and answers 'USE msgpack', or
+// 'ERR no common format' and closes the connection.  From then on every Point3D and Reply is a
+// length-prefixed frame (see tcp_support) encoded in the chosen format.
+//
+// A first line that isn't a HELLO is taken to be a Point3D in JSON from an older client, and the
+// server keeps to the original one JSON line in, one distance line out protocol.
+use json_with_serde::Format;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use tcp_support::{read_frame, write_frame, Connection};
+
+use crate::{Point3D, Reply};
+
+pub const HELLO: &str = "HELLO";
+pub const USE: &str = "USE";
+pub const NO_COMMON_FORMAT: &str = "ERR no common format";
+
+pub fn hello_line(formats: &[Format]) -> String {
+    let names: Vec<&str> = formats.iter().map(Format::name).collect();
+    format!("{} {}\n", HELLO, names.join(","))
+}
+
+// The formats offered in a HELLO line, or None if it isn't one.  Unknown names are skipped so
+// newer clients can offer formats older servers have never heard of.
+pub fn parse_hello(line: &str) -> Option<Vec<Format>> {
+    let names = line.trim_end().strip_prefix(HELLO)?.strip_prefix(' ')?;
+    Some(names.split(',').filter_map(Format::from_name).collect())
+}
+
+// The client's most preferred format that the server supports.
+pub fn negotiate(offered: &[Format], supported: &[Format]) -> Option<Format> {
+    offered
+        .iter()
+        .copied()
+        .find(|format| supported.contains(format))
+}
+
+fn invalid_data<E: ToString>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+pub fn handle_client<S: Read + Write>(stream: S, supported: &[Format]) -> io::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let mut line = Vec::new();
+    if stream.read_until(b'\n', &mut line)? == 0 {
+        return Ok(());
+    }
+
+    let offered = match parse_hello(&String::from_utf8_lossy(&line)) {
+        Some(offered) => offered,
+        None => return handle_json_lines(stream, line),
+    };
+    let format = match negotiate(&offered, supported) {
+        Some(format) => format,
+        None => return writeln!(stream.get_mut(), "{}", NO_COMMON_FORMAT),
+    };
+    println!("Using {}", format);
+    writeln!(stream.get_mut(), "{} {}", USE, format)?;
+
+    while let Some(frame) = read_frame(&mut stream)? {
+        let input: Point3D = format.decode(&frame).map_err(invalid_data)?;
+        let reply = Reply {
+            distance: input.distance(),
+        };
+        let bytes = format.encode(&reply).map_err(invalid_data)?;
+        write_frame(stream.get_mut(), &bytes)?;
+    }
+    Ok(())
+}
+
+// The book's protocol, 'first' is the line already read while looking for a HELLO.
+fn handle_json_lines<S: Read + Write>(mut stream: BufReader<S>, first: Vec<u8>) -> io::Result<()> {
+    let mut data = first;
+    loop {
+        println!("Read {} bytes", data.len());
+        let input: Point3D = serde_json::from_slice(&data)?;
+        writeln!(stream.get_mut(), "{}", input.distance())?;
+
+        data.clear();
+        if stream.read_until(b'\n', &mut data)? == 0 {
+            return Ok(());
+        }
+    }
+}
+
+// Sends the HELLO and returns the format the server chose.
+pub fn client_handshake<R: Read, W: Write>(
+    connection: &mut Connection<R, W>,
+    formats: &[Format],
+) -> io::Result<Format> {
+    let reply = connection
+        .request(&hello_line(formats))?
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+    reply
+        .trim_end()
+        .strip_prefix(USE)
+        .and_then(|name| name.strip_prefix(' '))
+        .and_then(Format::from_name)
+        .filter(|format| formats.contains(format))
+        .ok_or_else(|| invalid_data(format!("handshake failed: {}", reply.trim_end())))
+}
+
+// Sends one point in the negotiated format and waits for the distance.
+pub fn request<R: Read, W: Write>(
+    connection: &mut Connection<R, W>,
+    format: Format,
+    point: &Point3D,
+) -> io::Result<Reply> {
+    let bytes = format.encode(point).map_err(invalid_data)?;
+    connection.send_frame(&bytes)?;
+    let frame = connection
+        .read_frame()?
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+    format.decode(&frame).map_err(invalid_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    fn serve_once(supported: &'static [Format]) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle_client(stream, supported).unwrap();
+        });
+        TcpStream::connect(addr).unwrap()
+    }
+
+    proptest! {
+        // A fixed seed so every run (and every machine) tries the same cases.
+        #![proptest_config(ProptestConfig {
+            rng_seed: proptest::test_runner::RngSeed::Fixed(32),
+            ..ProptestConfig::default()
+        })]
+
+        #[test]
+        fn messages_round_trip_in_every_format(x: u32, y: u32, z: u32) {
+            let point = Point3D { x, y, z };
+            let reply = Reply { distance: point.distance() };
+            for format in Format::ALL {
+                let decoded: Point3D = format.decode(&format.encode(&point).unwrap()).unwrap();
+                prop_assert_eq!(decoded, point, "format {}", format);
+                let decoded: Reply = format.decode(&format.encode(&reply).unwrap()).unwrap();
+                prop_assert_eq!(decoded, reply, "format {}", format);
+            }
+        }
+    }
+
+    #[test]
+    fn hello_lines() {
+        let line = hello_line(&[Format::MessagePack, Format::Json]);
+        assert_eq!(line, "HELLO msgpack,json\n");
+        assert_eq!(
+            parse_hello(&line),
+            Some(vec![Format::MessagePack, Format::Json])
+        );
+        assert_eq!(parse_hello("HELLO xml,cbor"), Some(vec![Format::Cbor]));
+        assert_eq!(parse_hello("{\"x\":1,\"y\":2,\"z\":3}"), None);
+        assert_eq!(parse_hello("HELLOWORLD"), None);
+    }
+
+    #[test]
+    fn the_client_preference_wins() {
+        let supported = [Format::Json, Format::Cbor, Format::MessagePack];
+        assert_eq!(
+            negotiate(&[Format::Cbor, Format::Json], &supported),
+            Some(Format::Cbor)
+        );
+        assert_eq!(negotiate(&[Format::Toml, Format::Yaml], &supported), None);
+    }
+
+    #[test]
+    fn every_format_works_over_a_socket() {
+        for format in Format::ALL {
+            let mut connection = Connection::new(serve_once(&Format::ALL)).unwrap();
+            assert_eq!(
+                client_handshake(&mut connection, &[format]).unwrap(),
+                format
+            );
+
+            let reply = request(&mut connection, format, &Point3D { x: 2, y: 3, z: 6 }).unwrap();
+            assert_eq!(reply.distance, 7.0);
+            let reply = request(&mut connection, format, &Point3D { x: 0, y: 0, z: 0 }).unwrap();
+            assert_eq!(reply.distance, 0.0);
+        }
+    }
+
+    #[test]
+    fn no_common_format_is_refused() {
+        let mut connection = Connection::new(serve_once(&[Format::Json])).unwrap();
+        let err = client_handshake(&mut connection, &[Format::Cbor]).unwrap_err();
+        assert_eq!(err.to_string(), "handshake failed: ERR no common format");
+    }
+
+    #[test]
+    fn json_lines_without_a_hello_still_work() {
+        let mut connection = Connection::new(serve_once(&Format::ALL)).unwrap();
+        let reply = connection.request("{\"x\":2,\"y\":3,\"z\":6}").unwrap();
+        assert_eq!(reply.as_deref(), Some("7\n"));
+        let reply = connection.request("{\"x\":1,\"y\":0,\"z\":0}").unwrap();
+        assert_eq!(reply.as_deref(), Some("1\n"));
+    }
+}
@@ -0,0 +1,92 @@
+// cargo test --test fuzz
+// FUZZ_CASES=100000 cargo test --release --test fuzz
+//
+// Fuzz targets for handle_client, run over a MemoryStream from ../test_support rather than a
+// socket.  A client can send anything at all, which should end the connection with an error at
+// worst, never panic the server.
+use json_tcp_client_and_server::protocol::{handle_client, parse_hello};
+use json_tcp_client_and_server::Point3D;
+use json_with_serde::Format;
+use proptest::prelude::*;
+use tcp_support::write_frame;
+use test_support::fuzz::mutated;
+use test_support::MemoryStream;
+
+// Points in the book's protocol, one JSON object per line.
+const JSON_LINES: &[&str] = &[
+    "{\"x\":3,\"y\":4,\"z\":12}\n",
+    "{\"x\": 0, \"y\": 0, \"z\": 0}\n{\"x\":4294967295,\"y\":1,\"z\":2}\n",
+    "{\"z\":1,\"y\":2,\"x\":3}\r\n",
+];
+
+// The framed protocol: a HELLO and then points encoded in the format the server will pick.
+fn framed_corpus() -> Vec<Vec<u8>> {
+    let point = Point3D { x: 3, y: 4, z: 12 };
+    Format::ALL
+        .iter()
+        .map(|format| {
+            let mut input = format!("HELLO {}\n", format.name()).into_bytes();
+            for _ in 0..2 {
+                write_frame(&mut input, &format.encode(&point).unwrap()).unwrap();
+            }
+            input
+        })
+        .collect()
+}
+
+// What handle_json_lines should answer: the distance of each point up to the first line that
+// isn't one, and whether it stopped at a bad line.
+fn expected_json_lines_output(input: &[u8]) -> (String, bool) {
+    let mut output = String::new();
+    for line in input.split_inclusive(|&b| b == b'\n') {
+        match serde_json::from_slice::<Point3D>(line) {
+            Ok(point) => output.push_str(&format!("{}\n", point.distance())),
+            Err(_) => return (output, true),
+        }
+    }
+    (output, false)
+}
+
+proptest! {
+    #![proptest_config(test_support::fuzz_config(32))]
+
+    #[test]
+    fn json_lines(input in mutated(JSON_LINES.iter().map(|s| s.as_bytes().to_vec()).collect())) {
+        let first_line = input.split(|&b| b == b'\n').next().unwrap_or(&[]);
+        prop_assume!(parse_hello(&String::from_utf8_lossy(first_line)).is_none());
+
+        let mut stream = MemoryStream::new(input.clone());
+        let result = handle_client(&mut stream, &Format::ALL);
+        let (expected, fails) = expected_json_lines_output(&input);
+        prop_assert_eq!(stream.output_text(), expected);
+        prop_assert_eq!(result.is_err(), fails);
+    }
+
+    #[test]
+    fn framed(input in mutated(framed_corpus())) {
+        let mut stream = MemoryStream::new(input);
+        let _ = handle_client(&mut stream, &Format::ALL);
+    }
+
+    #[test]
+    fn framed_with_one_format(input in mutated(framed_corpus())) {
+        let mut stream = MemoryStream::new(input);
+        if handle_client(&mut stream, &[Format::Json]).is_ok() {
+            let output = stream.output_text();
+            prop_assert!(
+                output.is_empty() || output.starts_with("USE json\n") || output.starts_with("ERR"),
+                "{:?}",
+                output
+            );
+        }
+    }
+
+    // Point3D's own parsing: whatever it accepts comes out the same when encoded again.
+    #[test]
+    fn point_json(input in mutated(JSON_LINES.iter().map(|s| s.as_bytes().to_vec()).collect())) {
+        if let Ok(point) = serde_json::from_slice::<Point3D>(&input) {
+            let json = serde_json::to_vec(&point).unwrap();
+            prop_assert_eq!(serde_json::from_slice::<Point3D>(&json).unwrap(), point);
+        }
+    }
+}
@@ -0,0 +1,375 @@
+// Integer addition that doesn't overflow silently.
+//
+// '+' panics on overflow in a debug build, but in a release build wraps around without a word:
+// i32::MAX + 1 is i32::MIN.  These do the same sums, for every integer type, with the overflow
+// handled one of five ways, chosen by the caller:
+// -    checked_*      None on overflow.
+// -    saturating_*   Stops at the type's MIN or MAX.
+// -    wrapping_*     Wraps around, like '+' in release, but on purpose.
+// -    overflowing_*  The wrapped result and whether it overflowed.
+// -    try_*          Err(Overflow) on overflow, which says what the sum was.
+//
+// The standard library has all of these as methods on each integer type.  The Integer trait
+// gathers them together, so they can be used in generic code like this.
+//
+// automated_tests::add_stuff and cargo_and_crates_io::add_one use try_add and checked_add_one,
+// and panic on overflow in every build.
+//
+// cargo test runs the examples in the /// comments as well as the tests at the bottom.
+use std::fmt;
+
+/// The integer types: i8 to i128, u8 to u128, isize and usize.
+pub trait Integer: Copy + Eq + Ord + fmt::Debug + fmt::Display {
+    const ZERO: Self;
+    const ONE: Self;
+    const MIN: Self;
+    const MAX: Self;
+    /// The type's name, "i32" etc.
+    const NAME: &'static str;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn overflowing_add(self, rhs: Self) -> (Self, bool);
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                const ZERO: $t = 0;
+                const ONE: $t = 1;
+                const MIN: $t = <$t>::MIN;
+                const MAX: $t = <$t>::MAX;
+                const NAME: &'static str = stringify!($t);
+
+                fn checked_add(self, rhs: $t) -> Option<$t> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn saturating_add(self, rhs: $t) -> $t {
+                    <$t>::saturating_add(self, rhs)
+                }
+
+                fn wrapping_add(self, rhs: $t) -> $t {
+                    <$t>::wrapping_add(self, rhs)
+                }
+
+                fn overflowing_add(self, rhs: $t) -> ($t, bool) {
+                    <$t>::overflowing_add(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// The error from the try_* functions: a sum too big, or too small, for its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow<T> {
+    pub lhs: T,
+    pub rhs: T,
+}
+
+impl<T: Integer> fmt::Display for Overflow<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} + {} overflows {}", self.lhs, self.rhs, T::NAME)
+    }
+}
+
+impl<T: Integer> std::error::Error for Overflow<T> {}
+
+/// Adds two numbers, or None if the sum doesn't fit in their type.
+///
+/// # Examples
+/// ```
+/// let answer = numeric::checked_add(2, 2);
+///
+/// assert_eq!(Some(4), answer);
+/// assert_eq!(None, numeric::checked_add(i32::MAX, 1));
+/// ```
+pub fn checked_add<T: Integer>(a: T, b: T) -> Option<T> {
+    a.checked_add(b)
+}
+
+/// Adds two numbers, stopping at the type's largest or smallest value.
+///
+/// # Examples
+/// ```
+/// let answer = numeric::saturating_add(250u8, 10);
+///
+/// assert_eq!(255, answer);
+/// assert_eq!(i8::MIN, numeric::saturating_add(-100i8, -100));
+/// ```
+pub fn saturating_add<T: Integer>(a: T, b: T) -> T {
+    a.saturating_add(b)
+}
+
+/// Adds two numbers, wrapping around past the type's largest or smallest value.
+///
+/// # Examples
+/// ```
+/// let answer = numeric::wrapping_add(250u8, 10);
+///
+/// assert_eq!(4, answer);
+/// assert_eq!(i32::MIN, numeric::wrapping_add(i32::MAX, 1));
+/// ```
+pub fn wrapping_add<T: Integer>(a: T, b: T) -> T {
+    a.wrapping_add(b)
+}
+
+/// Adds two numbers, giving the wrapped sum and whether it overflowed.
+///
+/// # Examples
+/// ```
+/// let answer = numeric::overflowing_add(250u8, 10);
+///
+/// assert_eq!((4, true), answer);
+/// assert_eq!((7, false), numeric::overflowing_add(3u8, 4));
+/// ```
+pub fn overflowing_add<T: Integer>(a: T, b: T) -> (T, bool) {
+    a.overflowing_add(b)
+}
+
+/// Adds two numbers, or an Overflow error saying what the sum was.
+///
+/// # Examples
+/// ```
+/// let answer = numeric::try_add(2i64, 2);
+///
+/// assert_eq!(Ok(4), answer);
+///
+/// let error = numeric::try_add(i64::MAX, 1).unwrap_err();
+/// assert_eq!("9223372036854775807 + 1 overflows i64", error.to_string());
+/// ```
+pub fn try_add<T: Integer>(a: T, b: T) -> Result<T, Overflow<T>> {
+    a.checked_add(b).ok_or(Overflow { lhs: a, rhs: b })
+}
+
+/// Adds one to the number given, or None if it is already the type's largest value.
+///
+/// # Examples
+/// ```
+/// let arg = 5;
+/// let answer = numeric::checked_add_one(arg);
+///
+/// assert_eq!(Some(6), answer);
+/// assert_eq!(None, numeric::checked_add_one(u8::MAX));
+/// ```
+pub fn checked_add_one<T: Integer>(x: T) -> Option<T> {
+    checked_add(x, T::ONE)
+}
+
+/// Adds one to the number given, unless it is already the type's largest value.
+///
+/// # Examples
+/// ```
+/// let arg = 5;
+/// let answer = numeric::saturating_add_one(arg);
+///
+/// assert_eq!(6, answer);
+/// assert_eq!(u8::MAX, numeric::saturating_add_one(u8::MAX));
+/// ```
+pub fn saturating_add_one<T: Integer>(x: T) -> T {
+    saturating_add(x, T::ONE)
+}
+
+/// Adds one to the number given, wrapping around to the smallest value after the largest.
+///
+/// # Examples
+/// ```
+/// let arg = 5;
+/// let answer = numeric::wrapping_add_one(arg);
+///
+/// assert_eq!(6, answer);
+/// assert_eq!(i32::MIN, numeric::wrapping_add_one(i32::MAX));
+/// ```
+pub fn wrapping_add_one<T: Integer>(x: T) -> T {
+    wrapping_add(x, T::ONE)
+}
+
+/// Adds one to the number given, giving the wrapped result and whether it overflowed.
+///
+/// # Examples
+/// ```
+/// let arg = 5;
+/// let answer = numeric::overflowing_add_one(arg);
+///
+/// assert_eq!((6, false), answer);
+/// assert_eq!((0, true), numeric::overflowing_add_one(u16::MAX));
+/// ```
+pub fn overflowing_add_one<T: Integer>(x: T) -> (T, bool) {
+    overflowing_add(x, T::ONE)
+}
+
+/// Adds one to the number given, or an Overflow error if it is already the type's largest value.
+///
+/// # Examples
+/// ```
+/// let arg = 5;
+/// let answer = numeric::try_add_one(arg);
+///
+/// assert_eq!(Ok(6), answer);
+/// assert_eq!("127 + 1 overflows i8", numeric::try_add_one(i8::MAX).unwrap_err().to_string());
+/// ```
+pub fn try_add_one<T: Integer>(x: T) -> Result<T, Overflow<T>> {
+    try_add(x, T::ONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every pair of values of an 8 bit type, against the same sum in i32, which can't overflow.
+    macro_rules! exhaustive_8_bit {
+        ($name:ident, $t:ty) => {
+            #[test]
+            fn $name() {
+                let (min, max) = (i32::from(<$t>::MIN), i32::from(<$t>::MAX));
+                for a in <$t>::MIN..=<$t>::MAX {
+                    for b in <$t>::MIN..=<$t>::MAX {
+                        let exact = i32::from(a) + i32::from(b);
+                        let fits = (min..=max).contains(&exact);
+                        let wrapped = (exact - min).rem_euclid(max - min + 1) + min;
+
+                        assert_eq!(
+                            checked_add(a, b).map(i32::from),
+                            Some(exact).filter(|_| fits)
+                        );
+                        assert_eq!(i32::from(saturating_add(a, b)), exact.clamp(min, max));
+                        assert_eq!(i32::from(wrapping_add(a, b)), wrapped);
+                        let (sum, overflowed) = overflowing_add(a, b);
+                        assert_eq!((i32::from(sum), overflowed), (wrapped, !fits));
+                        assert_eq!(try_add(a, b).is_err(), !fits);
+                    }
+                }
+            }
+        };
+    }
+
+    exhaustive_8_bit!(every_i8_pair, i8);
+    exhaustive_8_bit!(every_u8_pair, u8);
+
+    // Every value of a 16 bit type plus one.
+    macro_rules! exhaustive_add_one {
+        ($name:ident, $t:ty) => {
+            #[test]
+            fn $name() {
+                for x in <$t>::MIN..=<$t>::MAX {
+                    if x == <$t>::MAX {
+                        assert_eq!(checked_add_one(x), None);
+                        assert_eq!(saturating_add_one(x), x);
+                        assert_eq!(wrapping_add_one(x), <$t>::MIN);
+                        assert_eq!(overflowing_add_one(x), (<$t>::MIN, true));
+                        assert_eq!(try_add_one(x), Err(Overflow { lhs: x, rhs: 1 }));
+                    } else {
+                        let next = i32::from(x) + 1;
+                        assert_eq!(checked_add_one(x).map(i32::from), Some(next));
+                        assert_eq!(i32::from(saturating_add_one(x)), next);
+                        assert_eq!(i32::from(wrapping_add_one(x)), next);
+                        assert_eq!(overflowing_add_one(x).1, false);
+                        assert_eq!(try_add_one(x).map(i32::from), Ok(next));
+                    }
+                }
+            }
+        };
+    }
+
+    exhaustive_add_one!(every_i16_plus_one, i16);
+    exhaustive_add_one!(every_u16_plus_one, u16);
+
+    // The values either side of each boundary, for the types too big to try every value: MIN,
+    // MAX, 0, the halves and their neighbours.  Each pair is checked against the room left
+    // between a and MAX (or MIN), which doesn't need a wider type, so works for i128 and u128.
+    macro_rules! boundaries {
+        ($name:ident, $t:ty) => {
+            #[test]
+            fn $name() {
+                let mut values: Vec<$t> = vec![
+                    <$t>::MIN,
+                    <$t>::MIN + 1,
+                    <$t>::MIN / 2,
+                    0,
+                    1,
+                    2,
+                    <$t>::MAX / 2,
+                    <$t>::MAX / 2 + 1,
+                    <$t>::MAX - 1,
+                    <$t>::MAX,
+                ];
+                // -1 and -2 for the signed types.  For the unsigned ones these wrap to MAX and
+                // MAX - 1, which are already there.
+                values.push((0 as $t).wrapping_sub(1));
+                values.push((0 as $t).wrapping_sub(2));
+
+                for &a in &values {
+                    for &b in &values {
+                        // Overflow is only possible with both the same sign, and then happens
+                        // when a is past MAX - b (or MIN - b), neither of which can overflow.
+                        #[allow(unused_comparisons)]
+                        let overflows = if b >= 0 {
+                            a > <$t>::MAX - b
+                        } else {
+                            a < <$t>::MIN - b
+                        };
+                        let expected = if overflows {
+                            None
+                        } else {
+                            Some(a.wrapping_add(b))
+                        };
+
+                        assert_eq!(checked_add(a, b), expected, "{} + {}", a, b);
+                        assert_eq!(try_add(a, b).ok(), expected);
+                        assert_eq!(overflowing_add(a, b), (a.wrapping_add(b), overflows));
+                        let saturated = match expected {
+                            Some(sum) => sum,
+                            #[allow(unused_comparisons)]
+                            None if b > 0 => <$t>::MAX,
+                            None => <$t>::MIN,
+                        };
+                        assert_eq!(saturating_add(a, b), saturated, "{} + {}", a, b);
+                    }
+                }
+
+                assert_eq!(checked_add_one(<$t>::MAX), None);
+                assert_eq!(checked_add_one(<$t>::MAX - 1), Some(<$t>::MAX));
+                assert_eq!(wrapping_add_one(<$t>::MAX), <$t>::MIN);
+                assert_eq!(saturating_add_one(<$t>::MAX), <$t>::MAX);
+                assert_eq!(<$t as Integer>::NAME, stringify!($t));
+            }
+        };
+    }
+
+    boundaries!(i8_boundaries, i8);
+    boundaries!(i16_boundaries, i16);
+    boundaries!(i32_boundaries, i32);
+    boundaries!(i64_boundaries, i64);
+    boundaries!(i128_boundaries, i128);
+    boundaries!(isize_boundaries, isize);
+    boundaries!(u8_boundaries, u8);
+    boundaries!(u16_boundaries, u16);
+    boundaries!(u32_boundaries, u32);
+    boundaries!(u64_boundaries, u64);
+    boundaries!(u128_boundaries, u128);
+    boundaries!(usize_boundaries, usize);
+
+    #[test]
+    fn overflow_errors() {
+        let error = try_add(-100i8, -100).unwrap_err();
+        assert_eq!(
+            error,
+            Overflow {
+                lhs: -100,
+                rhs: -100
+            }
+        );
+        assert_eq!(error.to_string(), "-100 + -100 overflows i8");
+
+        let boxed: Box<dyn std::error::Error> = Box::new(try_add_one(u128::MAX).unwrap_err());
+        assert_eq!(
+            boxed.to_string(),
+            "340282366920938463463374607431768211455 + 1 overflows u128"
+        );
+    }
+}
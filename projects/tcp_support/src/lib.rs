@@ -0,0 +1,324 @@
+// Networking shared by the TCP clients and servers in the other projects, which add it as a
+// dependency:
+//
+//   [dependencies]
+//   tcp_support = { path = "../tcp_support" }
+//
+// Connection and the frame functions are below, the optional TLS setup for both sides is in tls,
+// and ConnectionLimit, which caps how many clients a server handles at once, is in limit.
+//
+// Connection: a long-lived, line based connection.
+//
+// The book clients created a fresh 'BufReader::new(&stream)' for every response.  A BufReader
+// reads as much as the socket gives it, not just up to the newline, so if the server sent two
+// lines in one write the second line sat in that reader's buffer and was thrown away when the
+// reader was dropped at the end of the loop.
+//
+// 'Connection' fixes this by owning one reader and one writer for the lifetime of the connection.
+// For a TcpStream these are two handles to the same socket (see 'TcpStream::try_clone').
+// Streams that can't be cloned, like a TLS session, are wrapped in 'Shared' instead.
+//
+// Binary payloads may contain newlines, so they are sent as frames instead of lines: a 4 byte
+// big-endian length followed by that many bytes.  Lines and frames can be mixed on the one
+// connection, e.g. a text handshake followed by framed messages.
+pub mod limit;
+pub mod tls;
+
+pub use limit::{ConnectionLimit, Permit};
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+
+pub struct Connection<R, W> {
+    reader: BufReader<R>,
+    writer: W,
+}
+
+impl Connection<TcpStream, TcpStream> {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        let writer = stream.try_clone()?;
+        Ok(Connection::from_parts(stream, writer))
+    }
+
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Connection::new(TcpStream::connect(addr)?)
+    }
+}
+
+impl<R: Read, W: Write> Connection<R, W> {
+    pub fn from_parts(reader: R, writer: W) -> Self {
+        Connection {
+            reader: BufReader::new(reader),
+            writer,
+        }
+    }
+
+    // Sends one line, adding the trailing newline if it is missing.
+    pub fn send_line(&mut self, line: &str) -> io::Result<()> {
+        self.writer.write_all(line.as_bytes())?;
+        if !line.ends_with('\n') {
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.flush()
+    }
+
+    // Reads one line including its newline.  Returns None once the other side has closed the
+    // connection.  A final line without a newline is still returned.
+    pub fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut buffer = Vec::new();
+        let bytes_read = self.reader.read_until(b'\n', &mut buffer)?;
+        if bytes_read == 0 {
+            return Ok(None);
+        }
+
+        String::from_utf8(buffer)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    // Reads exactly 'count' lines, for responses that are known to span several lines.
+    pub fn read_lines(&mut self, count: usize) -> io::Result<Vec<String>> {
+        let mut lines = Vec::with_capacity(count);
+        while lines.len() < count {
+            match self.read_line()? {
+                Some(line) => lines.push(line),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("expected {} lines, got {}", count, lines.len()),
+                    ))
+                }
+            }
+        }
+        Ok(lines)
+    }
+
+    // Returns every complete line that has already arrived, without blocking on the socket.
+    // Use this after a read to pick up pipelined responses.
+    pub fn read_buffered_lines(&mut self) -> io::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        while self.reader.buffer().contains(&b'\n') {
+            match self.read_line()? {
+                Some(line) => lines.push(line),
+                None => break,
+            }
+        }
+        Ok(lines)
+    }
+
+    pub fn send_frame(&mut self, payload: &[u8]) -> io::Result<()> {
+        write_frame(&mut self.writer, payload)?;
+        self.writer.flush()
+    }
+
+    // Reads one frame.  Returns None if the other side closed the connection between frames.
+    pub fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        read_frame(&mut self.reader)
+    }
+
+    // Sends a line and waits for the first line of the reply.
+    pub fn request(&mut self, line: &str) -> io::Result<Option<String>> {
+        self.send_line(line)?;
+        self.read_line()
+    }
+
+    pub fn reader(&self) -> &R {
+        self.reader.get_ref()
+    }
+
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+}
+
+// Frames larger than this are refused rather than allocated.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("frame of {} bytes is too large", payload.len()),
+        ));
+    }
+    writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+    writer.write_all(payload)
+}
+
+pub fn read_frame<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0; 4];
+    let mut filled = 0;
+    while filled < length.len() {
+        match reader.read(&mut length[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes is too large", length),
+        ));
+    }
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+// One stream used as both the reader and the writer of a Connection.  Each read or write locks
+// the stream only for the duration of that call.
+pub struct Shared<S>(Arc<Mutex<S>>);
+
+impl<S> Shared<S> {
+    pub fn new(stream: S) -> Self {
+        Shared(Arc::new(Mutex::new(stream)))
+    }
+}
+
+impl<S> Clone for Shared<S> {
+    fn clone(&self) -> Self {
+        Shared(Arc::clone(&self.0))
+    }
+}
+
+impl<S: Read> Read for Shared<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
+
+impl<S: Write> Write for Shared<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{SocketAddr, TcpListener};
+    use std::thread;
+    use std::time::Duration;
+
+    // Starts a server that runs 'respond' on the first connection it accepts.
+    fn serve_once<F>(respond: F) -> SocketAddr
+    where
+        F: FnOnce(TcpStream) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            respond(stream);
+        });
+        addr
+    }
+
+    #[test]
+    fn two_lines_in_one_write_are_both_read() {
+        let addr = serve_once(|mut stream| {
+            stream.write_all(b"first\nsecond\n").unwrap();
+        });
+
+        let mut connection = Connection::connect(addr).unwrap();
+        assert_eq!(connection.read_line().unwrap().as_deref(), Some("first\n"));
+        assert_eq!(connection.read_line().unwrap().as_deref(), Some("second\n"));
+        assert_eq!(connection.read_line().unwrap(), None);
+    }
+
+    #[test]
+    fn pipelined_lines_are_available_without_blocking() {
+        let addr = serve_once(|mut stream| {
+            let mut line = String::new();
+            BufReader::new(stream.try_clone().unwrap())
+                .read_line(&mut line)
+                .unwrap();
+            stream.write_all(b"one\ntwo\nthree\n").unwrap();
+            // Keep the socket open so a blocking read would hang the test.
+            thread::sleep(Duration::from_millis(500));
+        });
+
+        let mut connection = Connection::connect(addr).unwrap();
+        assert_eq!(connection.request("go").unwrap().as_deref(), Some("one\n"));
+        assert_eq!(
+            connection.read_buffered_lines().unwrap(),
+            vec!["two\n", "three\n"]
+        );
+        assert!(connection.read_buffered_lines().unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_line_split_across_writes_is_reassembled() {
+        let addr = serve_once(|mut stream| {
+            stream.write_all(b"hel").unwrap();
+            stream.flush().unwrap();
+            thread::sleep(Duration::from_millis(50));
+            stream.write_all(b"lo\n").unwrap();
+        });
+
+        let mut connection = Connection::connect(addr).unwrap();
+        assert_eq!(connection.read_line().unwrap().as_deref(), Some("hello\n"));
+    }
+
+    #[test]
+    fn read_lines_reports_early_close() {
+        let addr = serve_once(|mut stream| {
+            stream.write_all(b"only one\n").unwrap();
+        });
+
+        let mut connection = Connection::connect(addr).unwrap();
+        let err = connection.read_lines(2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn send_line_adds_a_newline() {
+        let mut written = Vec::new();
+        let mut connection = Connection::from_parts(&b""[..], &mut written);
+        connection.send_line("no newline").unwrap();
+        connection.send_line("has newline\n").unwrap();
+        assert_eq!(written, b"no newline\nhas newline\n");
+    }
+
+    #[test]
+    fn frames_round_trip_after_a_line() {
+        let mut written = Vec::new();
+        let mut connection = Connection::from_parts(&b""[..], &mut written);
+        connection.send_line("HELLO").unwrap();
+        connection.send_frame(b"with\nnewline").unwrap();
+        connection.send_frame(b"").unwrap();
+
+        let mut connection = Connection::from_parts(&written[..], io::sink());
+        assert_eq!(connection.read_line().unwrap().as_deref(), Some("HELLO\n"));
+        assert_eq!(
+            connection.read_frame().unwrap().as_deref(),
+            Some(&b"with\nnewline"[..])
+        );
+        assert_eq!(connection.read_frame().unwrap().as_deref(), Some(&b""[..]));
+        assert_eq!(connection.read_frame().unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_and_oversized_frames_are_errors() {
+        let truncated = [0, 0, 0, 5, b'a', b'b'];
+        let err = read_frame(&mut &truncated[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = read_frame(&mut &[0, 0][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let huge = (MAX_FRAME_LEN as u32 + 1).to_be_bytes();
+        let err = read_frame(&mut &huge[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,113 @@
+// A limit on how many connections a server handles at once, the 'workers' of ServerConfig.
+//
+// Each connection gets a Permit, and its thread holds on to it until the connection is done.
+// When every permit is out, try_acquire gives None and the server closes the new connection
+// rather than leave it waiting behind clients that may never finish.
+//
+//   let limit = ConnectionLimit::new(config.workers);
+//   match limit.try_acquire() {
+//       Some(permit) => {
+//           thread::spawn(move || {
+//               let _permit = permit;
+//               ...
+//           });
+//       }
+//       None => eprintln!("Refusing connection, {} already open", limit.max()),
+//   }
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct ConnectionLimit {
+    open: Arc<AtomicU64>,
+    max: u64,
+}
+
+impl ConnectionLimit {
+    pub fn new(max: u64) -> ConnectionLimit {
+        ConnectionLimit {
+            open: Arc::new(AtomicU64::new(0)),
+            max,
+        }
+    }
+
+    // A permit for one more connection, or None if 'max' are already open.
+    pub fn try_acquire(&self) -> Option<Permit> {
+        self.open
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                (open < self.max).then(|| open + 1)
+            })
+            .ok()?;
+        Some(Permit {
+            open: Arc::clone(&self.open),
+        })
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn open(&self) -> u64 {
+        self.open.load(Ordering::SeqCst)
+    }
+}
+
+// Gives its place back when dropped.
+#[derive(Debug)]
+pub struct Permit {
+    open: Arc<AtomicU64>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.open.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn permits_run_out_and_come_back() {
+        let limit = ConnectionLimit::new(2);
+        let a = limit.try_acquire().unwrap();
+        let b = limit.try_acquire().unwrap();
+        assert!(limit.try_acquire().is_none());
+        assert_eq!(limit.open(), 2);
+
+        drop(a);
+        let c = limit.try_acquire().unwrap();
+        assert!(limit.try_acquire().is_none());
+        drop((b, c));
+        assert_eq!(limit.open(), 0);
+    }
+
+    #[test]
+    fn no_connections_with_a_limit_of_zero() {
+        assert!(ConnectionLimit::new(0).try_acquire().is_none());
+    }
+
+    #[test]
+    fn never_more_than_max_across_threads() {
+        let limit = ConnectionLimit::new(3);
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let limit = limit.clone();
+                thread::spawn(move || {
+                    (0..1000)
+                        .filter_map(|_| {
+                            let permit = limit.try_acquire();
+                            assert!(limit.open() <= 3);
+                            permit
+                        })
+                        .count()
+                })
+            })
+            .collect();
+        let granted: usize = threads.into_iter().map(|t| t.join().unwrap()).sum();
+        assert!(granted > 0);
+        assert_eq!(limit.open(), 0);
+    }
+}
@@ -0,0 +1,379 @@
+// Optional TLS for the TCP examples, using rustls (a TLS stack written in Rust).
+//
+// Servers are given a certificate chain and private key in PEM format:
+//   --tls-cert cert.pem --tls-key key.pem
+// Clients are given the certificate(s) to trust, there is no system CA store involved so a
+// self-signed certificate works:
+//   --tls-ca cert.pem [--tls-name localhost]
+//
+// Without these flags everything stays plain TCP.
+//
+// The certificate must be a leaf (end entity) certificate, rustls rejects a CA certificate used
+// as the server's own.  With openssl:
+//   openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes -days 365 \
+//     -keyout key.pem -out cert.pem -subj "/CN=localhost" \
+//     -addext "subjectAltName=DNS:localhost" -addext "basicConstraints=critical,CA:FALSE"
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{
+    ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned,
+};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{Connection, Shared};
+
+// So that programs using this module don't need their own rustls dependency.
+pub use rustls::{ClientConfig as TlsClientConfig, ServerConfig as TlsServerConfig};
+
+// Anything we can read and write, so servers can treat plain and TLS streams the same way.
+pub trait Stream: Read + Write + Send {}
+impl<S: Read + Write + Send> Stream for S {}
+
+pub type BoxedConnection = Connection<Box<dyn Read + Send>, Box<dyn Write + Send>>;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TlsOptions {
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    pub ca: Option<PathBuf>,
+    pub server_name: Option<String>,
+}
+
+impl TlsOptions {
+    // Removes the --tls-* flags (and their values) from args, leaving the rest for the program.
+    pub fn extract(args: &mut Vec<String>) -> Result<TlsOptions, String> {
+        let mut options = TlsOptions::default();
+        let mut remaining = Vec::with_capacity(args.len());
+        let mut iter = args.drain(..);
+
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--tls-") {
+                remaining.push(arg);
+                continue;
+            }
+
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("{} expects a value", arg))?;
+            match arg.as_str() {
+                "--tls-cert" => options.cert = Some(PathBuf::from(value)),
+                "--tls-key" => options.key = Some(PathBuf::from(value)),
+                "--tls-ca" => options.ca = Some(PathBuf::from(value)),
+                "--tls-name" => options.server_name = Some(value),
+                _ => return Err(format!("unknown flag '{}'", arg)),
+            }
+        }
+
+        drop(iter);
+        *args = remaining;
+        Ok(options)
+    }
+
+    // None when the server should speak plain TCP.
+    pub fn server_config(&self) -> io::Result<Option<Arc<ServerConfig>>> {
+        match (&self.cert, &self.key) {
+            (None, None) => Ok(None),
+            (Some(cert), Some(key)) => server_config(cert, key).map(Some),
+            _ => Err(invalid_input(
+                "--tls-cert and --tls-key must be given together",
+            )),
+        }
+    }
+
+    // None when the client should speak plain TCP.
+    pub fn client_config(&self) -> io::Result<Option<Arc<ClientConfig>>> {
+        match &self.ca {
+            None => Ok(None),
+            Some(ca) => client_config(ca).map(Some),
+        }
+    }
+
+    pub fn server_name(&self) -> &str {
+        self.server_name.as_deref().unwrap_or("localhost")
+    }
+}
+
+pub fn server_config(cert_path: &Path, key_path: &Path) -> io::Result<Arc<ServerConfig>> {
+    let certs = load_certs(cert_path)?;
+    let key = load_key(key_path)?;
+
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(invalid_data)?;
+    Ok(Arc::new(config))
+}
+
+pub fn client_config(ca_path: &Path) -> io::Result<Arc<ClientConfig>> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(ca_path)? {
+        roots.add(cert).map_err(invalid_data)?;
+    }
+
+    let config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+// Runs the server side of the handshake.  With no config the plain stream is returned.
+pub fn accept(
+    mut stream: TcpStream,
+    config: Option<&Arc<ServerConfig>>,
+) -> io::Result<Box<dyn Stream>> {
+    let config = match config {
+        None => return Ok(Box::new(stream)),
+        Some(config) => Arc::clone(config),
+    };
+
+    let mut session = ServerConnection::new(config).map_err(invalid_data)?;
+    while session.is_handshaking() {
+        session.complete_io(&mut stream)?;
+    }
+    Ok(Box::new(StreamOwned::new(session, stream)))
+}
+
+// Runs the client side of the handshake and wraps the result in a Connection.  With no config
+// this is a plain Connection over the TcpStream.
+pub fn client_connection(stream: TcpStream, options: &TlsOptions) -> io::Result<BoxedConnection> {
+    let config = match options.client_config()? {
+        None => {
+            let writer = stream.try_clone()?;
+            return Ok(Connection::from_parts(Box::new(stream), Box::new(writer)));
+        }
+        Some(config) => config,
+    };
+
+    let name = ServerName::try_from(options.server_name().to_string()).map_err(invalid_input)?;
+    let mut session = ClientConnection::new(config, name).map_err(invalid_data)?;
+    let mut stream = stream;
+    while session.is_handshaking() {
+        session.complete_io(&mut stream)?;
+    }
+
+    // A TLS session can't be cloned like a TcpStream so the reader and writer share it.
+    let shared = Shared::new(StreamOwned::new(session, stream));
+    Ok(Connection::from_parts(
+        Box::new(shared.clone()),
+        Box::new(shared),
+    ))
+}
+
+// client_connection, connecting with a timeout and using the same timeout for every read,
+// including those of the TLS handshake.
+pub fn connect_timeout(
+    addr: &SocketAddr,
+    timeout: Duration,
+    options: &TlsOptions,
+) -> io::Result<BoxedConnection> {
+    let stream = TcpStream::connect_timeout(addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    client_connection(stream, options)
+}
+
+fn load_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let certs = rustls_pemfile::certs(&mut reader).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(invalid_data(format!(
+            "no certificates in {}",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| invalid_data(format!("no private key in {}", path.display())))
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn invalid_input<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+    use std::{fs, thread};
+
+    // Writes a freshly generated self-signed certificate for 'localhost' to a temporary directory
+    // and returns the options a server and client would be started with.  The test removes the
+    // directory with remove_certificate when it is done.
+    fn self_signed(test_name: &str) -> TlsOptions {
+        let dir =
+            std::env::temp_dir().join(format!("tcp_support-{}-{}", test_name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        fs::write(&cert, certified.cert.pem()).unwrap();
+        fs::write(&key, certified.key_pair.serialize_pem()).unwrap();
+
+        TlsOptions {
+            cert: Some(cert.clone()),
+            key: Some(key),
+            ca: Some(cert),
+            server_name: None,
+        }
+    }
+
+    fn remove_certificate(options: &TlsOptions) {
+        let dir = options
+            .cert
+            .as_ref()
+            .and_then(|cert| cert.parent())
+            .unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    // A TLS echo server for one connection, echoing line by line.
+    fn echo_server(options: &TlsOptions) -> std::net::SocketAddr {
+        let config = options.server_config().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let Ok(stream) = accept(stream, config.as_ref()) else {
+                return;
+            };
+            let mut stream = BufReader::new(stream);
+            let mut line = String::new();
+            while stream.read_line(&mut line).unwrap_or(0) > 0 {
+                stream.get_mut().write_all(line.as_bytes()).unwrap();
+                stream.get_mut().flush().unwrap();
+                line.clear();
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn extracts_tls_flags() {
+        let mut args: Vec<String> = [
+            "prog",
+            "--server",
+            "--tls-cert",
+            "c.pem",
+            "--tls-key",
+            "k.pem",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let options = TlsOptions::extract(&mut args).unwrap();
+        assert_eq!(args, vec!["prog", "--server"]);
+        assert_eq!(options.cert, Some(PathBuf::from("c.pem")));
+        assert_eq!(options.key, Some(PathBuf::from("k.pem")));
+        assert_eq!(options.ca, None);
+    }
+
+    #[test]
+    fn cert_without_key_is_an_error() {
+        let options = TlsOptions {
+            cert: Some(PathBuf::from("cert.pem")),
+            ..TlsOptions::default()
+        };
+        assert_eq!(
+            options.server_config().unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn plain_when_no_options_are_given() {
+        let options = TlsOptions::default();
+        assert!(options.server_config().unwrap().is_none());
+        assert!(options.client_config().unwrap().is_none());
+    }
+
+    #[test]
+    fn round_trip_over_tls() {
+        let options = self_signed("round_trip");
+        let addr = echo_server(&options);
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut connection = client_connection(stream, &options).unwrap();
+        assert_eq!(
+            connection.request("hello").unwrap().as_deref(),
+            Some("hello\n")
+        );
+        assert_eq!(
+            connection.request("again").unwrap().as_deref(),
+            Some("again\n")
+        );
+        remove_certificate(&options);
+    }
+
+    #[test]
+    fn two_lines_in_one_tls_record() {
+        let options = self_signed("two_lines");
+        let config = options.server_config().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut stream = accept(stream, config.as_ref()).unwrap();
+            stream.write_all(b"first\nsecond\n").unwrap();
+            stream.flush().unwrap();
+            thread::sleep(std::time::Duration::from_millis(200));
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut connection = client_connection(stream, &options).unwrap();
+        assert_eq!(
+            connection.read_lines(2).unwrap(),
+            vec!["first\n", "second\n"]
+        );
+        remove_certificate(&options);
+    }
+
+    #[test]
+    fn connect_timeout_gives_up_on_a_silent_handshake() {
+        let options = self_signed("silent");
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            // Accepts, but never answers the client's hello.
+            let (_stream, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_secs(2));
+        });
+
+        let err = connect_timeout(&addr, Duration::from_millis(200), &options).err();
+        assert!(matches!(
+            err.map(|err| err.kind()),
+            Some(io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+        ));
+        remove_certificate(&options);
+    }
+
+    #[test]
+    fn untrusted_certificate_is_rejected() {
+        let server = self_signed("untrusted_server");
+        let other = self_signed("untrusted_client");
+        let client = TlsOptions {
+            ca: other.ca.clone(),
+            ..TlsOptions::default()
+        };
+        let addr = echo_server(&server);
+
+        let stream = TcpStream::connect(addr).unwrap();
+        assert!(client_connection(stream, &client).is_err());
+        remove_certificate(&server);
+        remove_certificate(&other);
+    }
+}
@@ -0,0 +1,132 @@
+// proptest strategies for malformed input.
+//
+// Purely random bytes are almost always rejected at the first byte, so they only test the
+// first check a parser makes.  mutated starts from examples of valid input, a corpus, and makes
+// a few random changes to one of them, which gets much further into the parser.  This is how
+// coverage guided fuzzers like cargo fuzz work, without the coverage guidance, but it runs on
+// stable Rust as part of cargo test.
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Mutation {
+    FlipBit(usize, u8),
+    SetByte(usize, u8),
+    Insert(usize, u8),
+    Remove(usize),
+    // Copies a run of bytes, start and length, to another place.
+    Splice(usize, usize, usize),
+    Truncate(usize),
+}
+
+// Positions are picked as any usize and then taken modulo the length at the time, so they stay
+// in range however the earlier mutations have changed it.
+fn mutation() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        (any::<usize>(), 0..8u8).prop_map(|(at, bit)| Mutation::FlipBit(at, bit)),
+        (any::<usize>(), interesting_byte()).prop_map(|(at, b)| Mutation::SetByte(at, b)),
+        (any::<usize>(), interesting_byte()).prop_map(|(at, b)| Mutation::Insert(at, b)),
+        any::<usize>().prop_map(Mutation::Remove),
+        (any::<usize>(), 1..16usize, any::<usize>())
+            .prop_map(|(from, len, to)| Mutation::Splice(from, len, to)),
+        any::<usize>().prop_map(Mutation::Truncate),
+    ]
+}
+
+// Any byte, but more often one that means something to a text parser.
+fn interesting_byte() -> impl Strategy<Value = u8> {
+    prop_oneof![
+        2 => any::<u8>(),
+        1 => prop::sample::select(b"\"\\{}[],:-.0123456789eE \n\r\t\0\xff".to_vec()),
+    ]
+}
+
+fn apply(bytes: &mut Vec<u8>, mutation: &Mutation) {
+    if bytes.is_empty() {
+        if let Mutation::Insert(_, b) = mutation {
+            bytes.push(*b);
+        }
+        return;
+    }
+    let len = bytes.len();
+    match *mutation {
+        Mutation::FlipBit(at, bit) => bytes[at % len] ^= 1 << bit,
+        Mutation::SetByte(at, b) => bytes[at % len] = b,
+        Mutation::Insert(at, b) => bytes.insert(at % (len + 1), b),
+        Mutation::Remove(at) => {
+            bytes.remove(at % len);
+        }
+        Mutation::Splice(from, run, to) => {
+            let from = from % len;
+            let run: Vec<u8> = bytes[from..len.min(from + run)].to_vec();
+            let to = to % (len + 1);
+            bytes.splice(to..to, run);
+        }
+        Mutation::Truncate(at) => bytes.truncate(at % len),
+    }
+}
+
+// One of the corpus, with between 1 and 8 mutations.  The corpus mustn't be empty.
+pub fn mutated(corpus: Vec<Vec<u8>>) -> impl Strategy<Value = Vec<u8>> {
+    (prop::sample::select(corpus), vec(mutation(), 1..=8)).prop_map(|(mut bytes, mutations)| {
+        for m in &mutations {
+            apply(&mut bytes, m);
+        }
+        bytes
+    })
+}
+
+// mutated for text, with any invalid UTF-8 the mutations make replaced by U+FFFD.
+pub fn mutated_text(corpus: &[&str]) -> impl Strategy<Value = String> {
+    let corpus = corpus.iter().map(|s| s.as_bytes().to_vec()).collect();
+    mutated(corpus).prop_map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// Random bytes, for completeness alongside mutated.
+pub fn bytes() -> impl Strategy<Value = Vec<u8>> {
+    vec(any::<u8>(), 0..256)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::strategy::ValueTree;
+    use proptest::test_runner::TestRunner;
+
+    #[test]
+    fn mutations_stay_in_range() {
+        let mut bytes = b"abc".to_vec();
+        apply(&mut bytes, &Mutation::Insert(5, b'x'));
+        assert_eq!(bytes, b"axbc");
+        apply(&mut bytes, &Mutation::Splice(2, 100, 0));
+        assert_eq!(bytes, b"bcaxbc");
+        apply(&mut bytes, &Mutation::Truncate(9));
+        assert_eq!(bytes, b"bca");
+        apply(&mut bytes, &Mutation::FlipBit(4, 0));
+        assert_eq!(bytes, b"bba");
+
+        let mut empty = Vec::new();
+        apply(&mut empty, &Mutation::Remove(3));
+        assert_eq!(empty, b"");
+        apply(&mut empty, &Mutation::Insert(3, b'z'));
+        assert_eq!(empty, b"z");
+    }
+
+    #[test]
+    fn mutated_input_differs_from_the_corpus() {
+        let corpus = vec![b"{\"x\":1}".to_vec()];
+        let mut runner = TestRunner::new(crate::fuzz_config(0));
+        let mut changed = 0;
+        for _ in 0..100 {
+            let value = mutated(corpus.clone())
+                .new_tree(&mut runner)
+                .unwrap()
+                .current();
+            if value != corpus[0] {
+                changed += 1;
+            }
+        }
+        // A flipped bit can be flipped back, so not every one.
+        assert!(changed > 80, "{}", changed);
+    }
+}
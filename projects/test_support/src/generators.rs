@@ -0,0 +1,84 @@
+// proptest strategies for well formed input.
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+// Text that is likely to matter to code that works on lines: plain ASCII words, accented and
+// other non-ASCII letters, tabs and '\r', and empty lines.
+pub fn line() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => "[a-zA-Z ,.:]{0,30}",
+        1 => "[a-zA-Zäöüßéİıσς日本 \t]{0,20}",
+        1 => Just(String::new()),
+        1 => "[a-z ]{0,10}\r",
+    ]
+}
+
+pub fn lines() -> impl Strategy<Value = Vec<String>> {
+    vec(line(), 0..20)
+}
+
+// A word to search for and some lines of text to search in, with the word put into some of the
+// lines so a search has something to find.
+pub fn needle_and_haystack() -> impl Strategy<Value = (String, Vec<String>)> {
+    ("[a-zA-Zéß]{1,5}", lines(), vec(any::<bool>(), 20)).prop_map(|(needle, mut lines, put)| {
+        for (line, put) in lines.iter_mut().zip(put) {
+            if put {
+                let at = line.char_indices().count() / 2;
+                let at = line.char_indices().nth(at).map_or(line.len(), |(i, _)| i);
+                line.insert_str(at, &needle);
+            }
+        }
+        (needle, lines)
+    })
+}
+
+// Command line arguments, as from env::args().collect(): the program's name and then 0 to 5
+// arguments, any of which might be empty or contain spaces or non-ASCII.
+pub fn args() -> impl Strategy<Value = Vec<String>> {
+    ("[a-z/]{1,10}", vec(any::<String>(), 0..6)).prop_map(|(program, mut args)| {
+        args.insert(0, program);
+        args
+    })
+}
+
+// Two i32s whose sum doesn't overflow, for testing arithmetic that isn't meant to.
+pub fn i32_pair_without_overflow() -> impl Strategy<Value = (i32, i32)> {
+    any::<i32>().prop_flat_map(|a| {
+        let b = if a >= 0 {
+            i32::MIN..=i32::MAX - a
+        } else {
+            i32::MIN - a..=i32::MAX
+        };
+        (Just(a), b)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::test_runner::TestRunner;
+
+    #[test]
+    fn generated_values_are_as_described() {
+        let mut runner = TestRunner::new(crate::fixed_seed(0));
+        runner
+            .run(&i32_pair_without_overflow(), |(a, b)| {
+                prop_assert!(a.checked_add(b).is_some());
+                Ok(())
+            })
+            .unwrap();
+        runner
+            .run(&needle_and_haystack(), |(needle, lines)| {
+                prop_assert!(!needle.is_empty());
+                prop_assert!(lines.iter().all(|line| !line.contains('\n')));
+                Ok(())
+            })
+            .unwrap();
+        runner
+            .run(&args(), |args| {
+                prop_assert!(!args.is_empty() && args.len() <= 6);
+                Ok(())
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+// Helpers shared by the property based and fuzz tests in the other projects, which add it as a
+// dev-dependency:
+//
+//   [dev-dependencies]
+//   proptest = "1"
+//   test_support = { path = "../test_support" }
+//
+// generators has proptest strategies for the kinds of input the projects take, fuzz has
+// strategies for malformed input, and MemoryStream stands in for a TcpStream.
+//
+// Everything runs as part of 'cargo test', with a fixed seed so every run (and every machine)
+// tries the same cases.  A failure can then be reproduced just by running the test again.
+use proptest::test_runner::{Config, RngSeed};
+
+pub mod fuzz;
+pub mod generators;
+mod stream;
+
+pub use stream::MemoryStream;
+
+// The number of cases fuzz_config runs when FUZZ_CASES isn't set.
+pub const DEFAULT_FUZZ_CASES: u32 = 1_000;
+
+// proptest's default number of cases, from a fixed seed.  Each test file should use its own seed.
+pub fn fixed_seed(seed: u64) -> Config {
+    Config {
+        rng_seed: RngSeed::Fixed(seed),
+        ..Config::default()
+    }
+}
+
+// For the fuzz targets, which need more cases than a property test to get anywhere.  Set
+// FUZZ_CASES to run more, or fewer, locally:  FUZZ_CASES=100000 cargo test --test fuzz
+pub fn fuzz_config(seed: u64) -> Config {
+    let cases = std::env::var("FUZZ_CASES")
+        .ok()
+        .and_then(|cases| cases.parse().ok())
+        .unwrap_or(DEFAULT_FUZZ_CASES);
+    Config {
+        cases,
+        ..fixed_seed(seed)
+    }
+}
@@ -0,0 +1,42 @@
+use std::io::{self, Cursor, Read, Write};
+
+// An in-memory connection for code that takes a Read + Write, like a TcpStream.  Reads come from
+// 'input' and then report the end of the stream, as if the other side had closed it.  Writes are
+// collected in 'output'.
+#[derive(Debug, Default)]
+pub struct MemoryStream {
+    input: Cursor<Vec<u8>>,
+    pub output: Vec<u8>,
+}
+
+impl MemoryStream {
+    pub fn new(input: impl Into<Vec<u8>>) -> MemoryStream {
+        MemoryStream {
+            input: Cursor::new(input.into()),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn output_text(&self) -> String {
+        String::from_utf8_lossy(&self.output).into_owned()
+    }
+}
+
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.read(buf)
+    }
+}
+
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Read and Write for &mut MemoryStream come from the standard library's blanket impls, so a
+// test can pass &mut stream and look at the output afterwards.